@@ -20,16 +20,27 @@
 //!
 //! Should only be used for benchmarking as it may break in other contexts.
 
-use crate::service::FullClient;
+use crate::{
+	decode::Decoder,
+	service::FullClient,
+	tx::{self, SigningParams},
+};
 
+use codec::Decode;
 use node_template_runtime as runtime;
-use runtime::{AccountId, Balance, BalancesCall, SystemCall};
+use runtime::{
+	apis::PaymentApi, extensions::Payer, pallet_template, AccountId, Balance, BalancesCall,
+	BlockNumber, Hash, Index, RuntimeCall, SystemCall,
+};
 use sc_cli::Result;
 use sc_client_api::BlockBackend;
-use sp_core::{Encode, Pair};
+use sp_api::ProvideRuntimeApi;
 use sp_inherents::{InherentData, InherentDataProvider};
 use sp_keyring::Sr25519Keyring;
-use sp_runtime::{OpaqueExtrinsic, SaturatedConversion};
+use sp_runtime::{
+	generic::{BlockId, Era},
+	OpaqueExtrinsic,
+};
 
 use std::{sync::Arc, time::Duration};
 
@@ -38,12 +49,13 @@ use std::{sync::Arc, time::Duration};
 /// Note: Should only be used for benchmarking.
 pub struct RemarkBuilder {
 	client: Arc<FullClient>,
+	signer: Sr25519Keyring,
 }
 
 impl RemarkBuilder {
-	/// Creates a new [`Self`] from the given client.
-	pub fn new(client: Arc<FullClient>) -> Self {
-		Self { client }
+	/// Creates a new [`Self`] from the given client, signing as `signer`.
+	pub fn new(client: Arc<FullClient>, signer: Sr25519Keyring) -> Self {
+		Self { client, signer }
 	}
 }
 
@@ -57,10 +69,9 @@ impl frame_benchmarking_cli::ExtrinsicBuilder for RemarkBuilder {
 	}
 
 	fn build(&self, nonce: u32) -> std::result::Result<OpaqueExtrinsic, &'static str> {
-		let acc = Sr25519Keyring::Bob.pair();
 		let extrinsic: OpaqueExtrinsic = create_benchmark_extrinsic(
 			self.client.as_ref(),
-			acc,
+			self.signer,
 			SystemCall::remark { remark: vec![] }.into(),
 			nonce,
 		)
@@ -75,14 +86,20 @@ impl frame_benchmarking_cli::ExtrinsicBuilder for RemarkBuilder {
 /// Note: Should only be used for benchmarking.
 pub struct TransferKeepAliveBuilder {
 	client: Arc<FullClient>,
+	signer: Sr25519Keyring,
 	dest: AccountId,
 	value: Balance,
 }
 
 impl TransferKeepAliveBuilder {
-	/// Creates a new [`Self`] from the given client.
-	pub fn new(client: Arc<FullClient>, dest: AccountId, value: Balance) -> Self {
-		Self { client, dest, value }
+	/// Creates a new [`Self`] from the given client, signing as `signer`.
+	pub fn new(
+		client: Arc<FullClient>,
+		signer: Sr25519Keyring,
+		dest: AccountId,
+		value: Balance,
+	) -> Self {
+		Self { client, signer, dest, value }
 	}
 }
 
@@ -96,10 +113,9 @@ impl frame_benchmarking_cli::ExtrinsicBuilder for TransferKeepAliveBuilder {
 	}
 
 	fn build(&self, nonce: u32) -> std::result::Result<OpaqueExtrinsic, &'static str> {
-		let acc = Sr25519Keyring::Bob.pair();
 		let extrinsic: OpaqueExtrinsic = create_benchmark_extrinsic(
 			self.client.as_ref(),
-			acc,
+			self.signer,
 			BalancesCall::transfer_keep_alive {
 				dest: self.dest.clone().into(),
 				value: self.value.into(),
@@ -113,12 +129,199 @@ impl frame_benchmarking_cli::ExtrinsicBuilder for TransferKeepAliveBuilder {
 	}
 }
 
-/// Create a transaction using the given `call`.
+/// Generates `TemplateModule::do_something` extrinsics for the benchmarks.
+///
+/// Note: Should only be used for benchmarking.
+pub struct DoSomethingBuilder {
+	client: Arc<FullClient>,
+	signer: Sr25519Keyring,
+	something: u32,
+}
+
+impl DoSomethingBuilder {
+	/// Creates a new [`Self`] from the given client, signing as `signer`.
+	pub fn new(client: Arc<FullClient>, signer: Sr25519Keyring, something: u32) -> Self {
+		Self { client, signer, something }
+	}
+}
+
+impl frame_benchmarking_cli::ExtrinsicBuilder for DoSomethingBuilder {
+	fn pallet(&self) -> &str {
+		"template"
+	}
+
+	fn extrinsic(&self) -> &str {
+		"do_something"
+	}
+
+	fn build(&self, nonce: u32) -> std::result::Result<OpaqueExtrinsic, &'static str> {
+		let extrinsic: OpaqueExtrinsic = create_benchmark_extrinsic(
+			self.client.as_ref(),
+			self.signer,
+			RuntimeCall::TemplateModule(pallet_template::Call::do_something {
+				something: self.something,
+			}),
+			nonce,
+		)
+		.into();
+
+		Ok(extrinsic)
+	}
+}
+
+/// Generates `TemplateModule::cause_error` extrinsics for the benchmarks.
+///
+/// The call fails as long as nothing is stored, so this measures the error path.
+///
+/// Note: Should only be used for benchmarking.
+pub struct CauseErrorBuilder {
+	client: Arc<FullClient>,
+	signer: Sr25519Keyring,
+}
+
+impl CauseErrorBuilder {
+	/// Creates a new [`Self`] from the given client, signing as `signer`.
+	pub fn new(client: Arc<FullClient>, signer: Sr25519Keyring) -> Self {
+		Self { client, signer }
+	}
+}
+
+impl frame_benchmarking_cli::ExtrinsicBuilder for CauseErrorBuilder {
+	fn pallet(&self) -> &str {
+		"template"
+	}
+
+	fn extrinsic(&self) -> &str {
+		"cause_error"
+	}
+
+	fn build(&self, nonce: u32) -> std::result::Result<OpaqueExtrinsic, &'static str> {
+		let extrinsic: OpaqueExtrinsic = create_benchmark_extrinsic(
+			self.client.as_ref(),
+			self.signer,
+			RuntimeCall::TemplateModule(pallet_template::Call::cause_error {}),
+			nonce,
+		)
+		.into();
+
+		Ok(extrinsic)
+	}
+}
+
+/// Generates feeless `TemplateModule::do_something` extrinsics for the benchmarks, registered as
+/// pallet `feeless`.
+///
+/// The chain spec's `feeless` genesis config has to allowlist the signer or the call, or the
+/// extrinsics are not built. Only the first of them, up to the signer's quota, are feeless in a
+/// block; the ones after are charged.
+///
+/// Note: Should only be used for benchmarking.
+pub struct FeelessBuilder {
+	client: Arc<FullClient>,
+	signer: Sr25519Keyring,
+	something: u32,
+}
+
+impl FeelessBuilder {
+	/// Creates a new [`Self`] from the given client, signing as `signer`.
+	pub fn new(client: Arc<FullClient>, signer: Sr25519Keyring, something: u32) -> Self {
+		Self { client, signer, something }
+	}
+}
+
+impl frame_benchmarking_cli::ExtrinsicBuilder for FeelessBuilder {
+	fn pallet(&self) -> &str {
+		"feeless"
+	}
+
+	fn extrinsic(&self) -> &str {
+		"do_something"
+	}
+
+	fn build(&self, nonce: u32) -> std::result::Result<OpaqueExtrinsic, &'static str> {
+		let extrinsic: OpaqueExtrinsic = create_benchmark_extrinsic(
+			self.client.as_ref(),
+			self.signer,
+			RuntimeCall::TemplateModule(pallet_template::Call::do_something {
+				something: self.something,
+			}),
+			nonce,
+		)
+		.into();
+
+		let best = BlockId::Hash(self.client.chain_info().best_hash);
+		match self.client.runtime_api().payer(&best, extrinsic.clone()) {
+			Ok(Payer::Nobody) => Ok(extrinsic),
+			Ok(_) => Err("The chain's allowlist doesn't waive the fee of the signer's call"),
+			Err(_) => Err("Unable to query the payer of the extrinsic"),
+		}
+	}
+}
+
+/// Generates extrinsics of an arbitrary call for the benchmarks, registered as pallet `custom`
+/// and named after the call.
+///
+/// Note: Should only be used for benchmarking.
+pub struct CallBuilder {
+	client: Arc<FullClient>,
+	signer: Sr25519Keyring,
+	call: RuntimeCall,
+	name: String,
+}
+
+impl CallBuilder {
+	/// Creates a new [`Self`] from the given client, signing as `signer`.
+	///
+	/// `call` is either the hex encoded call, or its JSON description as shown by `inspect`,
+	/// e.g. `{"pallet": "TemplateModule", "name": "do_something", "args": {"something": 42}}`.
+	pub fn new(
+		client: Arc<FullClient>,
+		signer: Sr25519Keyring,
+		call: &str,
+	) -> std::result::Result<Self, String> {
+		let decoder = Decoder::default();
+		let call = if call.trim_start().starts_with('{') {
+			let described = serde_json::from_str(call)
+				.map_err(|e| format!("Invalid call description: {}", e))?;
+			decoder.encode_call(&described)?
+		} else {
+			let encoded =
+				sp_core::bytes::from_hex(call).map_err(|e| format!("Invalid call hex: {}", e))?;
+			RuntimeCall::decode(&mut &encoded[..]).map_err(|e| format!("Invalid call: {}", e))?
+		};
+		let name = decoder.call(&call).name;
+
+		Ok(Self { client, signer, call, name })
+	}
+}
+
+impl frame_benchmarking_cli::ExtrinsicBuilder for CallBuilder {
+	fn pallet(&self) -> &str {
+		"custom"
+	}
+
+	fn extrinsic(&self) -> &str {
+		&self.name
+	}
+
+	fn build(&self, nonce: u32) -> std::result::Result<OpaqueExtrinsic, &'static str> {
+		let extrinsic: OpaqueExtrinsic =
+			create_benchmark_extrinsic(self.client.as_ref(), self.signer, self.call.clone(), nonce)
+				.into();
+
+		Ok(extrinsic)
+	}
+}
+
+/// Create a transaction using the given `call`, signed by `signer`.
+///
+/// It has neither tip nor sponsor, so the runtime waives its fee if its allowlist allows `signer`
+/// or `call`, see [`FeelessBuilder`].
 ///
 /// Note: Should only be used for benchmarking.
 pub fn create_benchmark_extrinsic(
 	client: &FullClient,
-	sender: sp_core::sr25519::Pair,
+	signer: Sr25519Keyring,
 	call: runtime::RuntimeCall,
 	nonce: u32,
 ) -> runtime::UncheckedExtrinsic {
@@ -126,46 +329,20 @@ pub fn create_benchmark_extrinsic(
 	let best_hash = client.chain_info().best_hash;
 	let best_block = client.chain_info().best_number;
 
-	let period = runtime::BlockHashCount::get()
-		.checked_next_power_of_two()
-		.map(|c| c / 2)
-		.unwrap_or(2) as u64;
-	let extra: runtime::SignedExtra = (
-		frame_system::CheckNonZeroSender::<runtime::Runtime>::new(),
-		frame_system::CheckSpecVersion::<runtime::Runtime>::new(),
-		frame_system::CheckTxVersion::<runtime::Runtime>::new(),
-		frame_system::CheckGenesis::<runtime::Runtime>::new(),
-		frame_system::CheckEra::<runtime::Runtime>::from(sp_runtime::generic::Era::mortal(
-			period,
-			best_block.saturated_into(),
-		)),
-		frame_system::CheckNonce::<runtime::Runtime>::from(nonce),
-		frame_system::CheckWeight::<runtime::Runtime>::new(),
-		pallet_transaction_payment::ChargeTransactionPayment::<runtime::Runtime>::from(0),
-	);
-
-	let raw_payload = runtime::SignedPayload::from_raw(
-		call.clone(),
-		extra.clone(),
-		(
-			(),
-			runtime::VERSION.spec_version,
-			runtime::VERSION.transaction_version,
-			genesis_hash,
-			best_hash,
-			(),
-			(),
-			(),
-		),
-	);
-	let signature = raw_payload.using_encoded(|e| sender.sign(e));
-
-	runtime::UncheckedExtrinsic::new_signed(
-		call.clone(),
-		sp_runtime::AccountId32::from(sender.public()).into(),
-		runtime::Signature::Sr25519(signature.clone()),
-		extra.clone(),
-	)
+	let params = signing_params(genesis_hash, nonce, best_block, best_hash);
+	tx::sign(call, &signer.pair(), &params)
+}
+
+/// The params benchmark transactions are signed with: mortal for as long as the runtime allows,
+/// starting at the given best block.
+pub fn signing_params(
+	genesis_hash: Hash,
+	nonce: Index,
+	best_number: BlockNumber,
+	best_hash: Hash,
+) -> SigningParams {
+	let era = Era::mortal(runtime::MaxMortality::get(), best_number.into());
+	SigningParams::new(genesis_hash, nonce, era, best_hash)
 }
 
 /// Generates inherent data for the `benchmark overhead` command.
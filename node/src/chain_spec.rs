@@ -1,18 +1,155 @@
+#[cfg(not(feature = "babe"))]
+use node_template_runtime::AuraConfig;
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-	SystemConfig, WASM_BINARY,
+	opaque::Block, AccountId, Balance, BalancesConfig, BlockNumber, GenesisConfig, GrandpaConfig,
+	Hash, Header, SS58Prefix, Signature, SudoConfig, SystemConfig, WASM_BINARY,
 };
-use sc_service::ChainType;
+#[cfg(feature = "babe")]
+use node_template_runtime::{BabeConfig, BABE_GENESIS_EPOCH_CONFIG};
+use sc_chain_spec::ChainSpecExtension;
+use sc_finality_grandpa::AuthoritySetHardFork;
+use sc_service::{config::MultiaddrWithPeerId, ChainType, Properties};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+#[cfg(not(feature = "babe"))]
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{sr25519, Pair, Public};
-use sp_finality_grandpa::AuthorityId as GrandpaId;
-use sp_runtime::traits::{IdentifyAccount, Verify};
+#[cfg(feature = "babe")]
+use sp_consensus_babe::AuthorityId as BabeId;
+use sp_core::{crypto::Ss58Codec, sr25519, Get, Pair, Public};
+use sp_finality_grandpa::{AuthorityId as GrandpaId, AuthorityList, GrandpaApi, SetId};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Header as HeaderT, IdentifyAccount, Verify},
+};
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+/// Node-specific fields in the chain spec.
+#[derive(Default, Clone, Serialize, Deserialize, ChainSpecExtension)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Extensions {
+	/// How blocks are produced.
+	pub consensus: Consensus,
+	/// Authority set changes forced upon GRANDPA, e.g. to recover from stalled finality.
+	pub grandpa_hard_forks: Vec<GrandpaHardFork>,
+	/// A finalized block that warp syncing nodes start from instead of genesis.
+	pub checkpoint: Option<Checkpoint>,
+}
+
+/// How the blocks of a chain are produced.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Consensus {
+	/// Genesis authorities produce blocks in slots and finalize them with GRANDPA.
+	#[default]
+	Authorities,
+	/// Anyone mines blocks, without authorities or finality.
+	ProofOfWork,
+}
+
+/// An authority set change forced upon GRANDPA when the given block is imported.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrandpaHardFork {
+	/// The id of the new authority set.
+	pub set_id: SetId,
+	/// The hash of the block enacting the change.
+	pub block_hash: Hash,
+	/// The number of the block enacting the change.
+	pub block_number: BlockNumber,
+	/// The new authority set.
+	pub authorities: AuthorityList,
+	/// If set, the change is only applied if this is the last finalized block at that point.
+	pub last_finalized: Option<BlockNumber>,
+}
+
+impl From<GrandpaHardFork> for AuthoritySetHardFork<Block> {
+	fn from(fork: GrandpaHardFork) -> Self {
+		AuthoritySetHardFork {
+			set_id: fork.set_id,
+			block: (fork.block_hash, fork.block_number),
+			authorities: fork.authorities,
+			last_finalized: fork.last_finalized,
+		}
+	}
+}
+
+/// A finalized block together with the GRANDPA authority set that finalizes its descendants.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+	/// The header of the finalized block.
+	pub header: Header,
+	/// The id of the authority set.
+	pub set_id: SetId,
+	/// The authority set.
+	pub authorities: AuthorityList,
+}
+
+impl Checkpoint {
+	/// Reads the checkpoint for the given finalized block from the client.
+	pub fn from_client<C>(client: &C, block: BlockId<Block>) -> Result<Self, String>
+	where
+		C: HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+		C::Api: GrandpaApi<Block>,
+	{
+		let header = client
+			.header(block)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Unknown block {:?}", block))?;
+		let number = *header.number();
+		let hash = header.hash();
+
+		let info = client.info();
+		if number > info.finalized_number || client.hash(number).ok().flatten() != Some(hash) {
+			return Err(format!("Block #{} ({}) is not finalized", number, hash))
+		}
+
+		let at = BlockId::Hash(hash);
+		let api = client.runtime_api();
+		let set_id = api.current_set_id(&at).map_err(|e| e.to_string())?;
+		let authorities = api.grandpa_authorities(&at).map_err(|e| e.to_string())?;
+
+		Ok(Self { header, set_id, authorities })
+	}
+}
+
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
-pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig>;
+pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig, Extensions>;
+
+/// The GRANDPA hard forks declared by the chain spec.
+pub fn grandpa_hard_forks(
+	chain_spec: &dyn sc_service::ChainSpec,
+) -> Vec<AuthoritySetHardFork<Block>> {
+	sc_chain_spec::get_extension::<Vec<GrandpaHardFork>>(chain_spec.extensions())
+		.cloned()
+		.unwrap_or_default()
+		.into_iter()
+		.map(Into::into)
+		.collect()
+}
+
+/// The checkpoint declared by the chain spec, if any.
+pub fn checkpoint(chain_spec: &dyn sc_service::ChainSpec) -> Option<Checkpoint> {
+	sc_chain_spec::get_extension::<Option<Checkpoint>>(chain_spec.extensions())
+		.cloned()
+		.flatten()
+}
+
+/// Embeds `checkpoint` into the chain spec.
+pub fn set_checkpoint(
+	chain_spec: &mut dyn sc_service::ChainSpec,
+	checkpoint: Checkpoint,
+) -> Result<(), String> {
+	let extension =
+		sc_chain_spec::get_extension_mut::<Option<Checkpoint>>(chain_spec.extensions_mut())
+			.ok_or("The chain spec does not support checkpoints")?;
+	*extension = Some(checkpoint);
+
+	Ok(())
+}
 
 /// Generate a crypto pair from seed.
 pub fn get_from_seed<TPublic: Public>(seed: &str) -> <TPublic::Pair as Pair>::Public {
@@ -31,65 +168,238 @@ where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Generate an Aura authority key.
-pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
-	(get_from_seed::<AuraId>(s), get_from_seed::<GrandpaId>(s))
+/// The block production authority key, depending on the consensus engine the node is built with.
+#[cfg(not(feature = "babe"))]
+pub type BlockAuthorId = AuraId;
+/// The block production authority key, depending on the consensus engine the node is built with.
+#[cfg(feature = "babe")]
+pub type BlockAuthorId = BabeId;
+
+/// Generate an Aura (or BABE) and GRANDPA authority key pair.
+pub fn authority_keys_from_seed(s: &str) -> (BlockAuthorId, GrandpaId) {
+	(get_from_seed::<BlockAuthorId>(s), get_from_seed::<GrandpaId>(s))
+}
+
+/// The symbol of the native token shown by wallets.
+pub const TOKEN_SYMBOL: &str = "UNIT";
+/// The number of decimals of the native token shown by wallets.
+pub const TOKEN_DECIMALS: u32 = 12;
+
+/// Builds a [`ChainSpec`] whose properties agree with the runtime.
+///
+/// `tokenSymbol` and `tokenDecimals` default to [`TOKEN_SYMBOL`] and [`TOKEN_DECIMALS`], while
+/// `ss58Format` is always the runtime's `SS58Prefix`.
+pub struct ChainSpecBuilder {
+	name: String,
+	id: String,
+	chain_type: ChainType,
+	boot_nodes: Vec<MultiaddrWithPeerId>,
+	protocol_id: Option<String>,
+	properties: Properties,
+	extensions: Extensions,
+}
+
+impl ChainSpecBuilder {
+	/// Creates a builder for a chain spec with the given name and id.
+	pub fn new(name: &str, id: &str, chain_type: ChainType) -> Self {
+		Self {
+			name: name.into(),
+			id: id.into(),
+			chain_type,
+			boot_nodes: Vec::new(),
+			protocol_id: None,
+			properties: Properties::new(),
+			extensions: Default::default(),
+		}
+		.token(TOKEN_SYMBOL, TOKEN_DECIMALS)
+	}
+
+	/// Sets the bootnodes.
+	pub fn boot_nodes(mut self, boot_nodes: Vec<MultiaddrWithPeerId>) -> Self {
+		self.boot_nodes = boot_nodes;
+		self
+	}
+
+	/// Sets the network protocol id.
+	pub fn protocol_id(mut self, protocol_id: &str) -> Self {
+		self.protocol_id = Some(protocol_id.into());
+		self
+	}
+
+	/// Sets the symbol and number of decimals of the native token.
+	pub fn token(mut self, symbol: &str, decimals: u32) -> Self {
+		self.properties.insert("tokenSymbol".into(), symbol.into());
+		self.properties.insert("tokenDecimals".into(), decimals.into());
+		self
+	}
+
+	/// Adds properties, overriding those set before. `ss58Format` can't be overridden.
+	pub fn properties(mut self, properties: Properties) -> Self {
+		self.properties.extend(properties);
+		self
+	}
+
+	/// Sets the node-specific chain spec fields.
+	pub fn extensions(mut self, extensions: Extensions) -> Self {
+		self.extensions = extensions;
+		self
+	}
+
+	/// Builds the chain spec, with the genesis state created by `genesis`.
+	pub fn build(self, genesis: impl Fn() -> GenesisConfig + Send + Sync + 'static) -> ChainSpec {
+		let mut properties = self.properties;
+		properties.insert("ss58Format".into(), SS58Prefix::get().into());
+
+		ChainSpec::from_genesis(
+			&self.name,
+			&self.id,
+			self.chain_type,
+			genesis,
+			self.boot_nodes,
+			// Telemetry
+			None,
+			self.protocol_id.as_deref(),
+			// Fork ID
+			None,
+			Some(properties),
+			self.extensions,
+		)
+	}
 }
 
 pub fn development_config() -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
 
-	Ok(ChainSpec::from_genesis(
-		// Name
-		"Development",
-		// ID
-		"dev",
-		ChainType::Development,
-		move || {
+	Ok(ChainSpecBuilder::new("Development", "dev", ChainType::Development).build(move || {
+		testnet_genesis(
+			wasm_binary,
+			// Initial PoA authorities
+			vec![authority_keys_from_seed("Alice")],
+			// Sudo account
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			// Pre-funded accounts
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				get_account_id_from_seed::<sr25519::Public>("Bob"),
+				get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
+				get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
+			],
+			true,
+		)
+	}))
+}
+
+pub fn local_testnet_config() -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
+
+	Ok(ChainSpecBuilder::new("Local Testnet", "local_testnet", ChainType::Local).build(move || {
+		testnet_genesis(
+			wasm_binary,
+			// Initial PoA authorities
+			vec![authority_keys_from_seed("Alice"), authority_keys_from_seed("Bob")],
+			// Sudo account
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			// Pre-funded accounts
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				get_account_id_from_seed::<sr25519::Public>("Bob"),
+				get_account_id_from_seed::<sr25519::Public>("Charlie"),
+				get_account_id_from_seed::<sr25519::Public>("Dave"),
+				get_account_id_from_seed::<sr25519::Public>("Eve"),
+				get_account_id_from_seed::<sr25519::Public>("Ferdie"),
+				get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
+				get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
+				get_account_id_from_seed::<sr25519::Public>("Charlie//stash"),
+				get_account_id_from_seed::<sr25519::Public>("Dave//stash"),
+				get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
+				get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
+			],
+			true,
+		)
+	}))
+}
+
+/// A live network whose keys are supplied by the operator instead of derived from dev seeds.
+///
+/// Each value is read from an environment variable or, if that is unset, from the file named by
+/// the same variable with a `_FILE` suffix:
+///
+/// - `STAGING_AUTHORITIES`: whitespace separated `<block author key>:<GRANDPA key>` pairs of SS58
+///   encoded public keys. Lines starting with `#` are ignored.
+/// - `STAGING_SUDO`: the SS58 encoded sudo account, which is also the only endowed account.
+pub fn staging_config() -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or_else(|| "Staging wasm not available".to_string())?;
+	let authorities = parse_authorities(&env_or_file("STAGING_AUTHORITIES")?)?;
+	let root_key = AccountId::from_ss58check(env_or_file("STAGING_SUDO")?.trim())
+		.map_err(|e| format!("Invalid STAGING_SUDO: {:?}", e))?;
+
+	Ok(ChainSpecBuilder::new("Staging Testnet", "staging", ChainType::Live)
+		.protocol_id("staging")
+		.build(move || {
 			testnet_genesis(
 				wasm_binary,
-				// Initial PoA authorities
-				vec![authority_keys_from_seed("Alice")],
-				// Sudo account
-				get_account_id_from_seed::<sr25519::Public>("Alice"),
-				// Pre-funded accounts
-				vec![
-					get_account_id_from_seed::<sr25519::Public>("Alice"),
-					get_account_id_from_seed::<sr25519::Public>("Bob"),
-					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
-				],
-				true,
+				authorities.clone(),
+				root_key.clone(),
+				vec![root_key.clone()],
+				false,
 			)
-		},
-		// Bootnodes
-		vec![],
-		// Telemetry
-		None,
-		// Protocol ID
-		None,
-		None,
-		// Properties
-		None,
-		// Extensions
-		None,
-	))
+		}))
 }
 
-pub fn local_testnet_config() -> Result<ChainSpec, String> {
+/// Reads the environment variable `name`, falling back to the file named by `{name}_FILE`.
+fn env_or_file(name: &str) -> Result<String, String> {
+	if let Ok(value) = std::env::var(name) {
+		return Ok(value)
+	}
+
+	let file_var = format!("{}_FILE", name);
+	let path = std::env::var(&file_var)
+		.map_err(|_| format!("Neither {} nor {} is set", name, file_var))?;
+	std::fs::read_to_string(&path).map_err(|e| format!("Error reading {}: {}", path, e))
+}
+
+/// Parses `<block author key>:<GRANDPA key>` pairs, see [`staging_config`].
+fn parse_authorities(input: &str) -> Result<Vec<(BlockAuthorId, GrandpaId)>, String> {
+	let authorities = input
+		.lines()
+		.filter(|line| !line.trim_start().starts_with('#'))
+		.flat_map(str::split_whitespace)
+		.map(|pair| {
+			let (author, grandpa) = pair.split_once(':').ok_or_else(|| {
+				format!("Expected `<block author key>:<GRANDPA key>`, got {}", pair)
+			})?;
+			Ok((
+				BlockAuthorId::from_ss58check(author)
+					.map_err(|e| format!("Invalid block author key {}: {:?}", author, e))?,
+				GrandpaId::from_ss58check(grandpa)
+					.map_err(|e| format!("Invalid GRANDPA key {}: {:?}", grandpa, e))?,
+			))
+		})
+		.collect::<Result<Vec<_>, String>>()?;
+
+	if authorities.is_empty() {
+		return Err("At least one staging authority is required".into())
+	}
+
+	Ok(authorities)
+}
+
+/// Whether the chain spec describes an authority-less proof-of-work network.
+pub fn is_pow(chain_spec: &dyn sc_service::ChainSpec) -> bool {
+	sc_chain_spec::get_extension::<Consensus>(chain_spec.extensions()) ==
+		Some(&Consensus::ProofOfWork)
+}
+
+pub fn pow_testnet_config() -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
 
-	Ok(ChainSpec::from_genesis(
-		// Name
-		"Local Testnet",
-		// ID
-		"local_testnet",
-		ChainType::Local,
-		move || {
+	Ok(ChainSpecBuilder::new("Proof-of-Work Testnet", "pow_testnet", ChainType::Local)
+		.extensions(Extensions { consensus: Consensus::ProofOfWork, ..Default::default() })
+		.build(move || {
 			testnet_genesis(
 				wasm_binary,
-				// Initial PoA authorities
-				vec![authority_keys_from_seed("Alice"), authority_keys_from_seed("Bob")],
+				// No authorities, blocks are mined.
+				vec![],
 				// Sudo account
 				get_account_id_from_seed::<sr25519::Public>("Alice"),
 				// Pre-funded accounts
@@ -100,50 +410,51 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 					get_account_id_from_seed::<sr25519::Public>("Dave"),
 					get_account_id_from_seed::<sr25519::Public>("Eve"),
 					get_account_id_from_seed::<sr25519::Public>("Ferdie"),
-					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Charlie//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Dave//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 				],
 				true,
 			)
-		},
-		// Bootnodes
-		vec![],
-		// Telemetry
-		None,
-		// Protocol ID
-		None,
-		// Properties
-		None,
-		None,
-		// Extensions
-		None,
-	))
+		}))
 }
 
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
-	initial_authorities: Vec<(AuraId, GrandpaId)>,
+	initial_authorities: Vec<(BlockAuthorId, GrandpaId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
 	_enable_println: bool,
+) -> GenesisConfig {
+	genesis_config(
+		wasm_binary,
+		initial_authorities,
+		root_key,
+		// Configure endowed accounts with initial balance of 1 << 60.
+		endowed_accounts.into_iter().map(|k| (k, 1 << 60)).collect(),
+	)
+}
+
+/// Configure initial storage state for FRAME modules, with explicit initial balances.
+pub fn genesis_config(
+	wasm_binary: &[u8],
+	initial_authorities: Vec<(BlockAuthorId, GrandpaId)>,
+	root_key: AccountId,
+	balances: Vec<(AccountId, Balance)>,
 ) -> GenesisConfig {
 	GenesisConfig {
 		system: SystemConfig {
 			// Add Wasm runtime to storage.
 			code: wasm_binary.to_vec(),
 		},
-		balances: BalancesConfig {
-			// Configure endowed accounts with initial balance of 1 << 60.
-			balances: endowed_accounts.iter().cloned().map(|k| (k, 1 << 60)).collect(),
-		},
+		balances: BalancesConfig { balances },
+		#[cfg(not(feature = "babe"))]
 		aura: AuraConfig {
 			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
 		},
+		#[cfg(feature = "babe")]
+		babe: BabeConfig {
+			authorities: initial_authorities.iter().map(|x| (x.0.clone(), 1)).collect(),
+			epoch_config: Some(BABE_GENESIS_EPOCH_CONFIG),
+		},
 		grandpa: GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
 		},
@@ -152,5 +463,7 @@ fn testnet_genesis(
 			key: Some(root_key),
 		},
 		transaction_payment: Default::default(),
+		difficulty: Default::default(),
+		feeless: Default::default(),
 	}
 }
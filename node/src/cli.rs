@@ -1,4 +1,5 @@
-use sc_cli::RunCmd;
+use sc_cli::{BlockNumberOrHash, CliConfiguration, NodeKeyParams, RunCmd, SharedParams};
+use std::path::PathBuf;
 
 #[derive(Debug, clap::Parser)]
 pub struct Cli {
@@ -7,6 +8,38 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: RunCmd,
+
+	/// Mine blocks with the given number of threads, at least one.
+	///
+	/// Only valid for proof-of-work chains such as `--chain pow`.
+	#[arg(
+		long,
+		value_name = "THREADS",
+		value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+	)]
+	pub pow_mine: Option<usize>,
+
+	/// Index the chain into the SQLite database at the given path, for the `indexer_*` RPC
+	/// methods.
+	#[arg(long, value_name = "PATH")]
+	pub indexer_db: Option<PathBuf>,
+
+	/// Index all finalized blocks from genesis, not only the new ones.
+	///
+	/// Old blocks can only be indexed if the node keeps their state and bodies, so this requires
+	/// an archive node, e.g. `--state-pruning archive`.
+	#[arg(long, requires = "indexer_db")]
+	pub indexer_backfill: bool,
+
+	/// Record the statuses of the transactions the node sees in the chain's data directory, for
+	/// the `tx_*` RPC methods.
+	#[arg(long)]
+	pub tx_status: bool,
+
+	/// Also serve the RPC methods on the interfaces of the given RPC middleware configuration,
+	/// with method access lists, rate limits and a subscription cap.
+	#[arg(long, value_name = "PATH")]
+	pub rpc_middleware: Option<PathBuf>,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -16,7 +49,10 @@ pub enum Subcommand {
 	Key(sc_cli::KeySubcommand),
 
 	/// Build a chain specification.
-	BuildSpec(sc_cli::BuildSpecCmd),
+	BuildSpec(BuildSpecCmd),
+
+	/// Generate plain and raw chain specifications from a network description file.
+	GenerateSpec(crate::generate_spec::GenerateSpecCmd),
 
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
@@ -24,15 +60,25 @@ pub enum Subcommand {
 	/// Export blocks.
 	ExportBlocks(sc_cli::ExportBlocksCmd),
 
+	/// Export decoded extrinsics and events as JSON Lines.
+	ExportEvents(crate::export_events::ExportEventsCmd),
+
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Submit transactions to a node at a target rate and report their latency.
+	Load(crate::load::LoadCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
+	/// Dump the state at a finalized block into a snapshot file, or restore one.
+	#[command(subcommand)]
+	Snapshot(crate::snapshot::SnapshotCmd),
+
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
@@ -50,4 +96,38 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Decode blocks and extrinsics.
+	Inspect(crate::inspect::InspectCmd),
+
+	/// Build and sign a transaction offline, and optionally submit it.
+	Tx(crate::tx::TxCmd),
+
+	/// Set up and verify the session keys of an authority.
+	#[command(subcommand)]
+	Validator(crate::validator::ValidatorCmd),
+}
+
+/// The `build-spec` command, which can also embed a checkpoint into the chain spec.
+#[derive(Debug, clap::Parser)]
+pub struct BuildSpecCmd {
+	#[clap(flatten)]
+	pub inner: sc_cli::BuildSpecCmd,
+
+	/// Embed the given finalized block and its GRANDPA authority set as checkpoint, which warp
+	/// syncing nodes start from.
+	///
+	/// The block, given by number or hash, is read from the node's database.
+	#[arg(long, value_name = "HASH or NUMBER")]
+	pub checkpoint: Option<BlockNumberOrHash>,
+}
+
+impl CliConfiguration for BuildSpecCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.inner.shared_params
+	}
+
+	fn node_key_params(&self) -> Option<&NodeKeyParams> {
+		Some(&self.inner.node_key_params)
+	}
 }
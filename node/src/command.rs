@@ -1,14 +1,33 @@
 use crate::{
-	benchmarking::{inherent_benchmark_data, RemarkBuilder, TransferKeepAliveBuilder},
+	benchmarking::{
+		inherent_benchmark_data, CallBuilder, CauseErrorBuilder, DoSomethingBuilder,
+		FeelessBuilder, RemarkBuilder, TransferKeepAliveBuilder,
+	},
 	chain_spec,
 	cli::{Cli, Subcommand},
-	service,
+	indexer::IndexerConfig,
+	rpc::middleware::MiddlewareConfig,
+	service::{self, FullBackend, FullClient},
+	snapshot::SnapshotCmd,
+	tx_status,
+	validator::ValidatorCmd,
 };
 use frame_benchmarking_cli::{BenchmarkCmd, ExtrinsicFactory, SUBSTRATE_REFERENCE_HARDWARE};
-use node_template_runtime::{Block, EXISTENTIAL_DEPOSIT};
+use node_template_runtime::{opaque::Block as OpaqueBlock, Block, EXISTENTIAL_DEPOSIT};
 use sc_cli::{ChainSpec, RuntimeVersion, SubstrateCli};
-use sc_service::PartialComponents;
+use sc_consensus::DefaultImportQueue;
+use sc_service::{
+	error::Error as ServiceError, BlocksPruning, Configuration, PartialComponents, TaskManager,
+};
 use sp_keyring::Sr25519Keyring;
+use std::{path::Path, sync::Arc};
+
+/// The account signing the extrinsics of `benchmark overhead` and `benchmark extrinsic`.
+const BENCHMARK_SIGNER: Sr25519Keyring = Sr25519Keyring::Bob;
+
+/// The environment variable holding the call that `benchmark extrinsic` measures as pallet
+/// `custom`, in hex or as JSON.
+const BENCHMARK_CALL: &str = "BENCHMARK_CALL";
 
 impl SubstrateCli for Cli {
 	fn impl_name() -> String {
@@ -39,6 +58,8 @@ impl SubstrateCli for Cli {
 		Ok(match id {
 			"dev" => Box::new(chain_spec::development_config()?),
 			"" | "local" => Box::new(chain_spec::local_testnet_config()?),
+			"pow" => Box::new(chain_spec::pow_testnet_config()?),
+			"staging" => Box::new(chain_spec::staging_config()?),
 			path =>
 				Box::new(chain_spec::ChainSpec::from_json_file(std::path::PathBuf::from(path))?),
 		})
@@ -49,60 +70,131 @@ impl SubstrateCli for Cli {
 	}
 }
 
+/// The client, backend, import queue and task manager of a chain.
+type ImportComponents =
+	(Arc<FullClient>, Arc<FullBackend>, DefaultImportQueue<Block, FullClient>, TaskManager);
+
+/// Builds the client, backend, import queue and task manager for the consensus engine of the chain
+/// spec.
+fn import_components(config: &Configuration) -> Result<ImportComponents, ServiceError> {
+	if chain_spec::is_pow(&*config.chain_spec) {
+		let PartialComponents { client, backend, task_manager, import_queue, .. } =
+			service::new_partial_pow(config)?;
+		Ok((client, backend, import_queue, task_manager))
+	} else {
+		let PartialComponents { client, backend, task_manager, import_queue, .. } =
+			service::new_partial(config)?;
+		Ok((client, backend, import_queue, task_manager))
+	}
+}
+
 /// Parse and run command line arguments
 pub fn run() -> sc_cli::Result<()> {
 	let cli = Cli::from_args();
 
 	match &cli.subcommand {
 		Some(Subcommand::Key(cmd)) => cmd.run(&cli),
+		Some(Subcommand::GenerateSpec(cmd)) => cmd.run(),
+		Some(Subcommand::Tx(cmd)) => cmd.run(),
+		Some(Subcommand::Load(cmd)) => cmd.run(),
 		Some(Subcommand::BuildSpec(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
-			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
+			runner.sync_run(|mut config| {
+				if let Some(block) = &cmd.checkpoint {
+					if chain_spec::is_pow(&*config.chain_spec) {
+						return Err("Proof-of-work chains have no finality to checkpoint".into())
+					}
+					let PartialComponents { client, .. } = service::new_partial_base(&config)?;
+					let checkpoint = chain_spec::Checkpoint::from_client(
+						&*client,
+						block.parse::<OpaqueBlock>()?,
+					)?;
+					chain_spec::set_checkpoint(&mut *config.chain_spec, checkpoint)?;
+				}
+
+				cmd.inner.run(config.chain_spec, config.network)
+			})
 		},
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let PartialComponents { client, task_manager, import_queue, .. } =
-					service::new_partial(&config)?;
+				let (client, _, import_queue, task_manager) = import_components(&config)?;
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
 		Some(Subcommand::ExportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let PartialComponents { client, task_manager, .. } = service::new_partial(&config)?;
+				let (client, _, _, task_manager) = import_components(&config)?;
 				Ok((cmd.run(client, config.database), task_manager))
 			})
 		},
 		Some(Subcommand::ExportState(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let PartialComponents { client, task_manager, .. } = service::new_partial(&config)?;
+				let (client, _, _, task_manager) = import_components(&config)?;
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let PartialComponents { client, task_manager, import_queue, .. } =
-					service::new_partial(&config)?;
+				let (client, _, import_queue, task_manager) = import_components(&config)?;
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
 		Some(Subcommand::PurgeChain(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
-			runner.sync_run(|config| cmd.run(config.database))
+			runner.sync_run(|config| {
+				let database = config.database.path().map(Path::to_path_buf);
+				cmd.run(config.database)?;
+				// The transaction statuses go along with the chain, unless purging was cancelled.
+				if database.map_or(false, |database| !database.exists()) {
+					tx_status::remove(&config.data_path)?;
+				}
+				Ok(())
+			})
+		},
+		Some(Subcommand::Snapshot(SnapshotCmd::Create(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial_base(&config)?;
+				cmd.run(&client)
+			})
+		},
+		Some(Subcommand::Snapshot(SnapshotCmd::Restore(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				if chain_spec::is_pow(&*config.chain_spec) {
+					return Err("Proof-of-work chains have no finalized state to restore".into())
+				}
+				let PartialComponents { client, other: (block_import, ..), .. } =
+					service::new_partial(&config)?;
+				cmd.run(&client, block_import)
+			})
 		},
 		Some(Subcommand::Revert(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let PartialComponents { client, task_manager, backend, .. } =
-					service::new_partial(&config)?;
-				let aux_revert = Box::new(|client, _, blocks| {
-					sc_finality_grandpa::revert(client, blocks)?;
-					Ok(())
-				});
-				Ok((cmd.run(client, backend, Some(aux_revert)), task_manager))
+				let is_pow = chain_spec::is_pow(&*config.chain_spec);
+				let (client, backend, _, task_manager) = import_components(&config)?;
+				let aux_revert =
+					Box::new(|client: Arc<FullClient>, backend: Arc<FullBackend>, blocks| {
+						#[cfg(feature = "babe")]
+						sc_consensus_babe::revert(client.clone(), backend, blocks)?;
+						// Aura keeps no auxiliary data that would need reverting.
+						#[cfg(not(feature = "babe"))]
+						let _ = backend;
+						sc_finality_grandpa::revert(client, blocks)?;
+						Ok(())
+					});
+				// Proof-of-work chains have no BABE or GRANDPA data to revert.
+				let revert = if is_pow {
+					cmd.run(client, backend, None)
+				} else {
+					cmd.run(client, backend, Some(aux_revert))
+				};
+				Ok((revert, task_manager))
 			})
 		},
 		Some(Subcommand::Benchmark(cmd)) => {
@@ -124,7 +216,7 @@ pub fn run() -> sc_cli::Result<()> {
 						cmd.run::<Block, service::ExecutorDispatch>(config)
 					},
 					BenchmarkCmd::Block(cmd) => {
-						let PartialComponents { client, .. } = service::new_partial(&config)?;
+						let (client, ..) = import_components(&config)?;
 						cmd.run(client)
 					},
 					#[cfg(not(feature = "runtime-benchmarks"))]
@@ -134,16 +226,15 @@ pub fn run() -> sc_cli::Result<()> {
 					),
 					#[cfg(feature = "runtime-benchmarks")]
 					BenchmarkCmd::Storage(cmd) => {
-						let PartialComponents { client, backend, .. } =
-							service::new_partial(&config)?;
+						let (client, backend, ..) = import_components(&config)?;
 						let db = backend.expose_db();
 						let storage = backend.expose_storage();
 
 						cmd.run(config, client, db, storage)
 					},
 					BenchmarkCmd::Overhead(cmd) => {
-						let PartialComponents { client, .. } = service::new_partial(&config)?;
-						let ext_builder = RemarkBuilder::new(client.clone());
+						let (client, ..) = import_components(&config)?;
+						let ext_builder = RemarkBuilder::new(client.clone(), BENCHMARK_SIGNER);
 
 						cmd.run(
 							config,
@@ -154,16 +245,28 @@ pub fn run() -> sc_cli::Result<()> {
 						)
 					},
 					BenchmarkCmd::Extrinsic(cmd) => {
-						let PartialComponents { client, .. } = service::new_partial(&config)?;
-						// Register the *Remark* and *TKA* builders.
-						let ext_factory = ExtrinsicFactory(vec![
-							Box::new(RemarkBuilder::new(client.clone())),
+						let (client, ..) = import_components(&config)?;
+						// Register the *Remark*, *TKA*, `TemplateModule` and feeless builders.
+						let mut ext_factory = ExtrinsicFactory(vec![
+							Box::new(RemarkBuilder::new(client.clone(), BENCHMARK_SIGNER)),
 							Box::new(TransferKeepAliveBuilder::new(
 								client.clone(),
+								BENCHMARK_SIGNER,
 								Sr25519Keyring::Alice.to_account_id(),
 								EXISTENTIAL_DEPOSIT,
 							)),
+							Box::new(DoSomethingBuilder::new(client.clone(), BENCHMARK_SIGNER, 42)),
+							Box::new(CauseErrorBuilder::new(client.clone(), BENCHMARK_SIGNER)),
+							Box::new(FeelessBuilder::new(client.clone(), BENCHMARK_SIGNER, 42)),
 						]);
+						// Any other call can be measured as pallet `custom`.
+						if let Ok(call) = std::env::var(BENCHMARK_CALL) {
+							ext_factory.0.push(Box::new(CallBuilder::new(
+								client.clone(),
+								BENCHMARK_SIGNER,
+								&call,
+							)?));
+						}
 
 						cmd.run(client, inherent_benchmark_data()?, Vec::new(), &ext_factory)
 					},
@@ -193,10 +296,66 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run::<Block>(&config))
 		},
+		Some(Subcommand::ExportEvents(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial_base(&config)?;
+				cmd.run(&client)
+			})
+		},
+		Some(Subcommand::Inspect(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial_base(&config)?;
+				cmd.run(&client)
+			})
+		},
+		Some(Subcommand::Validator(ValidatorCmd::Init(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(&config))
+		},
+		Some(Subcommand::Validator(ValidatorCmd::Check(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				if chain_spec::is_pow(&*config.chain_spec) {
+					return Err("Proof-of-work chains have no authorities".into())
+				}
+				let PartialComponents { client, keystore_container, .. } =
+					service::new_partial(&config)?;
+				cmd.run(&client, keystore_container.sync_keystore())
+			})
+		},
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
+			let pow_mine = cli.pow_mine;
+			let tx_status = cli.tx_status;
+			let indexer = cli
+				.indexer_db
+				.clone()
+				.map(|path| IndexerConfig { path, backfill: cli.indexer_backfill });
+			let rpc_middleware =
+				cli.rpc_middleware.as_deref().map(MiddlewareConfig::load).transpose()?;
 			runner.run_node_until_exit(|config| async move {
-				service::new_full(config).map_err(sc_cli::Error::Service)
+				let is_pow = chain_spec::is_pow(&*config.chain_spec);
+				let archive = config.state_pruning.as_ref().map_or(false, |p| p.is_archive()) &&
+					!matches!(config.blocks_pruning, BlocksPruning::Some(_));
+				if is_pow && indexer.is_some() {
+					Err("`--indexer-db` requires finality, which proof-of-work chains lack.".into())
+				} else if is_pow && tx_status {
+					Err("`--tx-status` requires finality, which proof-of-work chains lack.".into())
+				} else if indexer.as_ref().map_or(false, |indexer| indexer.backfill) && !archive {
+					Err("`--indexer-backfill` requires an archive node, see `--state-pruning`."
+						.into())
+				} else if is_pow {
+					service::new_full_pow(config, pow_mine, rpc_middleware)
+						.map_err(sc_cli::Error::Service)
+				} else if pow_mine.is_some() {
+					Err("`--pow-mine` requires a proof-of-work chain spec, e.g. `--chain pow`."
+						.into())
+				} else {
+					service::new_full(config, indexer, tx_status, rpc_middleware)
+						.map_err(sc_cli::Error::Service)
+				}
 			})
 		},
 	}
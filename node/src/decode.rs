@@ -0,0 +1,458 @@
+//! Decoding of the runtime's SCALE encoded types into JSON and back, driven by their type
+//! information.
+//!
+//! Used by the commands that show chain data to humans, so that they don't have to know about
+//! every pallet's calls and events.
+
+use codec::{Compact, Decode, Encode, Output};
+use node_template_runtime::{RuntimeCall, RuntimeEvent, SignedExtra};
+use scale_info::{
+	form::PortableForm, MetaType, PortableRegistry, Registry, Type, TypeDef, TypeDefPrimitive,
+};
+use serde_json::{json, Map, Value};
+use sp_core::{crypto::Ss58Codec, hexdisplay::HexDisplay, U256};
+use sp_runtime::{generic::Era, AccountId32};
+
+/// A decoded call or event, split into the pallet, the variant and its fields.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Described {
+	pub pallet: String,
+	pub name: String,
+	pub args: Value,
+}
+
+/// Renders values of the runtime's types as JSON.
+pub struct Decoder {
+	registry: PortableRegistry,
+	call: u32,
+	event: u32,
+	signed_extra: u32,
+}
+
+impl Default for Decoder {
+	fn default() -> Self {
+		let mut registry = Registry::new();
+		let call = registry.register_type(&MetaType::new::<RuntimeCall>()).id();
+		let event = registry.register_type(&MetaType::new::<RuntimeEvent>()).id();
+		let signed_extra = registry.register_type(&MetaType::new::<SignedExtra>()).id();
+
+		Self { registry: registry.into(), call, event, signed_extra }
+	}
+}
+
+impl Decoder {
+	/// Describes a runtime call.
+	pub fn call(&self, call: &RuntimeCall) -> Described {
+		self.describe(self.call, &call.encode())
+	}
+
+	/// Describes a runtime event.
+	pub fn event(&self, event: &RuntimeEvent) -> Described {
+		self.describe(self.event, &event.encode())
+	}
+
+	/// The data of the signed extensions, keyed by the extensions' names.
+	///
+	/// Extensions without any data of their own are left out.
+	pub fn signed_extra(&self, extra: &SignedExtra) -> Value {
+		let encoded = extra.encode();
+		let input = &mut &encoded[..];
+		let mut fields = Map::new();
+
+		if let TypeDef::Tuple(tuple) = self.resolve(self.signed_extra).type_def() {
+			for ty in tuple.fields() {
+				let name = self.resolve(ty.id()).path().ident();
+				match self.value(ty.id(), input) {
+					Ok(Value::Null) => (),
+					Ok(value) => {
+						fields.insert(name.unwrap_or_default(), value);
+					},
+					Err(e) => return json!({ "error": e.to_string() }),
+				}
+			}
+		}
+
+		Value::Object(fields)
+	}
+
+	/// Encodes a call given in the same form as [`Self::call`] describes it, e.g.
+	/// `{"pallet": "TemplateModule", "name": "do_something", "args": {"something": 42}}`.
+	pub fn encode_call(&self, described: &Value) -> Result<RuntimeCall, String> {
+		let field =
+			|name| described[name].as_str().ok_or_else(|| format!("The call has no `{}`", name));
+		let (pallet, name) = (field("pallet")?, field("name")?);
+		let value = json!({ pallet: { name: described["args"] } });
+
+		let mut encoded = Vec::new();
+		self.encode(self.call, &value, &mut encoded)
+			.map_err(|e| format!("Invalid call {}::{}: {}", pallet, name, e))?;
+		RuntimeCall::decode(&mut &encoded[..]).map_err(|e| format!("Invalid call: {}", e))
+	}
+
+	/// Splits the outer enum into pallet and variant. Both levels are enums in every runtime.
+	fn describe(&self, ty: u32, encoded: &[u8]) -> Described {
+		match self.value(ty, &mut &encoded[..]) {
+			Ok(value) => {
+				let (pallet, inner) = single_entry(value);
+				let (name, args) = single_entry(inner);
+				Described { pallet, name, args }
+			},
+			Err(e) => Described {
+				pallet: String::new(),
+				name: String::new(),
+				args: json!({ "error": e.to_string() }),
+			},
+		}
+	}
+
+	fn resolve(&self, ty: u32) -> &Type<PortableForm> {
+		self.registry.resolve(ty).expect("types are taken from the registry; qed")
+	}
+
+	/// Decodes a value of the given type from `input`.
+	///
+	/// Enums become single entry objects, byte strings hex strings, and integers that don't fit
+	/// into 64 bits decimal strings.
+	fn value(&self, ty: u32, input: &mut &[u8]) -> Result<Value, codec::Error> {
+		let ty = self.resolve(ty);
+
+		let namespace = ty.path().namespace().first().map(String::as_str);
+		match (namespace, ty.path().ident().as_deref()) {
+			(Some("sp_core"), Some("AccountId32")) =>
+				return Ok(AccountId32::decode(input)?.to_ss58check().into()),
+			(Some("sp_runtime"), Some("Era")) =>
+				return Ok(match Era::decode(input)? {
+					Era::Immortal => "immortal".into(),
+					Era::Mortal(period, phase) => json!({ "period": period, "phase": phase }),
+				}),
+			_ => (),
+		}
+
+		Ok(match ty.type_def() {
+			TypeDef::Composite(composite) => self.fields(composite.fields(), input)?,
+			TypeDef::Variant(variant) => {
+				let index = u8::decode(input)?;
+				let variant = variant
+					.variants()
+					.iter()
+					.find(|v| v.index() == index)
+					.ok_or("Unknown variant index")?;
+				let mut entry = Map::new();
+				entry.insert(variant.name().clone(), self.fields(variant.fields(), input)?);
+				Value::Object(entry)
+			},
+			TypeDef::Sequence(sequence) => {
+				let len = Compact::<u32>::decode(input)?.0;
+				self.items(sequence.type_param().id(), len, input)?
+			},
+			TypeDef::Array(array) => self.items(array.type_param().id(), array.len(), input)?,
+			TypeDef::Tuple(tuple) => match tuple.fields() {
+				[] => Value::Null,
+				fields => Value::Array(
+					fields.iter().map(|ty| self.value(ty.id(), input)).collect::<Result<_, _>>()?,
+				),
+			},
+			TypeDef::Primitive(primitive) => primitive_value(primitive, input)?,
+			// Compact encoding is the same for all widths, as long as the value fits.
+			TypeDef::Compact(_) => number(Compact::<u128>::decode(input)?.0),
+			TypeDef::BitSequence(_) => return Err("Bit sequences are not supported".into()),
+		})
+	}
+
+	/// Named fields become an object, a single unnamed field its value and several an array.
+	fn fields(
+		&self,
+		fields: &[scale_info::Field<PortableForm>],
+		input: &mut &[u8],
+	) -> Result<Value, codec::Error> {
+		let mut named = Map::new();
+		let mut unnamed = Vec::new();
+		for field in fields {
+			let value = self.value(field.ty().id(), input)?;
+			match field.name() {
+				Some(name) => {
+					named.insert(name.clone(), value);
+				},
+				// `PhantomData` and other unit types carry nothing worth showing.
+				None if value.is_null() => (),
+				None => unnamed.push(value),
+			}
+		}
+
+		Ok(match (named.is_empty(), unnamed.len()) {
+			(false, _) => Value::Object(named),
+			(true, 0) => Value::Null,
+			(true, 1) => unnamed.remove(0),
+			(true, _) => Value::Array(unnamed),
+		})
+	}
+
+	fn items(&self, ty: u32, len: u32, input: &mut &[u8]) -> Result<Value, codec::Error> {
+		if let TypeDef::Primitive(TypeDefPrimitive::U8) = self.resolve(ty).type_def() {
+			// The length is untrusted, don't allocate more than the input holds.
+			let bytes = input.get(..len as usize).ok_or("Not enough data to fill buffer")?;
+			*input = &input[len as usize..];
+			return Ok(format!("0x{}", HexDisplay::from(&bytes)).into())
+		}
+
+		Ok(Value::Array((0..len).map(|_| self.value(ty, input)).collect::<Result<_, _>>()?))
+	}
+}
+
+impl Decoder {
+	/// Encodes `value`, given in the form [`Self::value`] decodes it to, as the given type.
+	///
+	/// Enums may also be given as just the variant's name if it has no fields.
+	fn encode(&self, ty: u32, value: &Value, output: &mut Vec<u8>) -> Result<(), String> {
+		let ty = self.resolve(ty);
+
+		let namespace = ty.path().namespace().first().map(String::as_str);
+		match (namespace, ty.path().ident().as_deref()) {
+			(Some("sp_core"), Some("AccountId32")) => {
+				let address = value.as_str().ok_or("Expected an SS58 address")?;
+				let account = AccountId32::from_ss58check(address)
+					.map_err(|e| format!("Invalid SS58 address {}: {:?}", address, e))?;
+				account.encode_to(output);
+				return Ok(())
+			},
+			(Some("sp_runtime"), Some("Era")) => {
+				let era = match value {
+					Value::String(s) if s == "immortal" => Era::Immortal,
+					_ => Era::Mortal(
+						unsigned(&value["period"])? as u64,
+						unsigned(&value["phase"])? as u64,
+					),
+				};
+				era.encode_to(output);
+				return Ok(())
+			},
+			_ => (),
+		}
+
+		match ty.type_def() {
+			TypeDef::Composite(composite) => self.encode_fields(composite.fields(), value, output),
+			TypeDef::Variant(variant) => {
+				let (name, fields) = match value {
+					Value::String(name) => (name, &Value::Null),
+					Value::Object(map) if map.len() == 1 =>
+						map.iter().next().expect("len is 1; qed"),
+					_ => return Err(format!("Expected a variant, got {}", value)),
+				};
+				let variant = variant
+					.variants()
+					.iter()
+					.find(|v| v.name() == name)
+					.ok_or_else(|| format!("Unknown variant {}", name))?;
+				output.push_byte(variant.index());
+				self.encode_fields(variant.fields(), fields, output)
+			},
+			TypeDef::Sequence(sequence) =>
+				self.encode_items(sequence.type_param().id(), None, value, output),
+			TypeDef::Array(array) =>
+				self.encode_items(array.type_param().id(), Some(array.len()), value, output),
+			TypeDef::Tuple(tuple) => match tuple.fields() {
+				[] => Ok(()),
+				fields => {
+					let items = value.as_array().ok_or("Expected an array")?;
+					if items.len() != fields.len() {
+						return Err(format!("Expected {} items, got {}", fields.len(), items.len()))
+					}
+					fields
+						.iter()
+						.zip(items)
+						.try_for_each(|(ty, item)| self.encode(ty.id(), item, output))
+				},
+			},
+			TypeDef::Primitive(primitive) => encode_primitive(primitive, value, output),
+			TypeDef::Compact(_) => {
+				Compact(unsigned(value)?).encode_to(output);
+				Ok(())
+			},
+			TypeDef::BitSequence(_) => Err("Bit sequences are not supported".into()),
+		}
+	}
+
+	/// The inverse of [`Self::fields`].
+	fn encode_fields(
+		&self,
+		fields: &[scale_info::Field<PortableForm>],
+		value: &Value,
+		output: &mut Vec<u8>,
+	) -> Result<(), String> {
+		if fields.iter().any(|field| field.name().is_some()) {
+			for field in fields {
+				let name = field.name().expect("fields are either all named or all unnamed; qed");
+				let value = value.get(name).ok_or_else(|| format!("Missing field `{}`", name))?;
+				self.encode(field.ty().id(), value, output)?;
+			}
+			return Ok(())
+		}
+
+		// Fields that encode to nothing were left out.
+		let fields: Vec<_> = fields.iter().filter(|field| !self.is_unit(field.ty().id())).collect();
+		match fields.as_slice() {
+			[] => Ok(()),
+			[field] => self.encode(field.ty().id(), value, output),
+			fields => {
+				let items = value.as_array().ok_or("Expected an array")?;
+				if items.len() != fields.len() {
+					return Err(format!("Expected {} items, got {}", fields.len(), items.len()))
+				}
+				fields
+					.iter()
+					.zip(items)
+					.try_for_each(|(field, item)| self.encode(field.ty().id(), item, output))
+			},
+		}
+	}
+
+	/// The inverse of [`Self::items`].
+	fn encode_items(
+		&self,
+		ty: u32,
+		len: Option<u32>,
+		value: &Value,
+		output: &mut Vec<u8>,
+	) -> Result<(), String> {
+		let is_bytes =
+			matches!(self.resolve(ty).type_def(), TypeDef::Primitive(TypeDefPrimitive::U8));
+		// Arrays have a fixed length, sequences are prefixed with theirs.
+		let prefix = |actual: usize, output: &mut Vec<u8>| match len {
+			Some(len) if len as usize != actual =>
+				Err(format!("Expected {} items, got {}", len, actual)),
+			Some(_) => Ok(()),
+			None => {
+				Compact(actual as u32).encode_to(output);
+				Ok(())
+			},
+		};
+
+		match value {
+			Value::String(hex) if is_bytes => {
+				let bytes =
+					sp_core::bytes::from_hex(hex).map_err(|e| format!("Invalid hex: {}", e))?;
+				prefix(bytes.len(), output)?;
+				output.extend_from_slice(&bytes);
+				Ok(())
+			},
+			Value::Array(items) => {
+				prefix(items.len(), output)?;
+				items.iter().try_for_each(|item| self.encode(ty, item, output))
+			},
+			_ => Err(format!("Expected an array, got {}", value)),
+		}
+	}
+
+	/// Whether values of the type encode to nothing, like `()` and `PhantomData`.
+	fn is_unit(&self, ty: u32) -> bool {
+		match self.resolve(ty).type_def() {
+			TypeDef::Tuple(tuple) => tuple.fields().is_empty(),
+			TypeDef::Composite(composite) => composite.fields().is_empty(),
+			_ => false,
+		}
+	}
+}
+
+fn encode_primitive(
+	primitive: &TypeDefPrimitive,
+	value: &Value,
+	output: &mut Vec<u8>,
+) -> Result<(), String> {
+	fn narrow<T: TryFrom<u128>>(n: u128) -> Result<T, String> {
+		T::try_from(n).map_err(|_| format!("{} is out of range", n))
+	}
+	fn signed(value: &Value) -> Result<i128, String> {
+		match value {
+			Value::Number(n) => n.as_i64().map(i128::from),
+			Value::String(s) => s.parse().ok(),
+			_ => None,
+		}
+		.ok_or_else(|| format!("Expected an integer, got {}", value))
+	}
+	fn narrow_signed<T: TryFrom<i128>>(n: i128) -> Result<T, String> {
+		T::try_from(n).map_err(|_| format!("{} is out of range", n))
+	}
+
+	match primitive {
+		TypeDefPrimitive::Bool => value.as_bool().ok_or("Expected a bool")?.encode_to(output),
+		TypeDefPrimitive::Char => {
+			let mut chars = value.as_str().ok_or("Expected a character")?.chars();
+			match (chars.next(), chars.next()) {
+				(Some(c), None) => (c as u32).encode_to(output),
+				_ => return Err("Expected a single character".into()),
+			}
+		},
+		TypeDefPrimitive::Str => value.as_str().ok_or("Expected a string")?.encode_to(output),
+		TypeDefPrimitive::U8 => narrow::<u8>(unsigned(value)?)?.encode_to(output),
+		TypeDefPrimitive::U16 => narrow::<u16>(unsigned(value)?)?.encode_to(output),
+		TypeDefPrimitive::U32 => narrow::<u32>(unsigned(value)?)?.encode_to(output),
+		TypeDefPrimitive::U64 => narrow::<u64>(unsigned(value)?)?.encode_to(output),
+		TypeDefPrimitive::U128 => unsigned(value)?.encode_to(output),
+		TypeDefPrimitive::U256 => value
+			.as_str()
+			.and_then(|s| U256::from_dec_str(s).ok())
+			.ok_or("Expected a decimal string")?
+			.encode_to(output),
+		TypeDefPrimitive::I8 => narrow_signed::<i8>(signed(value)?)?.encode_to(output),
+		TypeDefPrimitive::I16 => narrow_signed::<i16>(signed(value)?)?.encode_to(output),
+		TypeDefPrimitive::I32 => narrow_signed::<i32>(signed(value)?)?.encode_to(output),
+		TypeDefPrimitive::I64 => narrow_signed::<i64>(signed(value)?)?.encode_to(output),
+		TypeDefPrimitive::I128 => signed(value)?.encode_to(output),
+		TypeDefPrimitive::I256 => return Err("256 bit signed integers are not supported".into()),
+	}
+	Ok(())
+}
+
+/// An unsigned integer given as a JSON number, or as a decimal string if it doesn't fit into one.
+fn unsigned(value: &Value) -> Result<u128, String> {
+	match value {
+		Value::Number(n) => n.as_u64().map(u128::from),
+		Value::String(s) => s.parse().ok(),
+		_ => None,
+	}
+	.ok_or_else(|| format!("Expected an unsigned integer, got {}", value))
+}
+
+fn primitive_value(primitive: &TypeDefPrimitive, input: &mut &[u8]) -> Result<Value, codec::Error> {
+	Ok(match primitive {
+		TypeDefPrimitive::Bool => bool::decode(input)?.into(),
+		TypeDefPrimitive::Char => char::from_u32(u32::decode(input)?).ok_or("Invalid char")?.into(),
+		TypeDefPrimitive::Str => String::decode(input)?.into(),
+		TypeDefPrimitive::U8 => u8::decode(input)?.into(),
+		TypeDefPrimitive::U16 => u16::decode(input)?.into(),
+		TypeDefPrimitive::U32 => u32::decode(input)?.into(),
+		TypeDefPrimitive::U64 => u64::decode(input)?.into(),
+		TypeDefPrimitive::U128 => number(u128::decode(input)?),
+		TypeDefPrimitive::U256 => U256::decode(input)?.to_string().into(),
+		TypeDefPrimitive::I8 => i8::decode(input)?.into(),
+		TypeDefPrimitive::I16 => i16::decode(input)?.into(),
+		TypeDefPrimitive::I32 => i32::decode(input)?.into(),
+		TypeDefPrimitive::I64 => i64::decode(input)?.into(),
+		TypeDefPrimitive::I128 => i128::decode(input)?.to_string().into(),
+		TypeDefPrimitive::I256 => return Err("256 bit signed integers are not supported".into()),
+	})
+}
+
+/// A JSON number if `n` fits into 64 bits, a decimal string otherwise.
+fn number(n: u128) -> Value {
+	u64::try_from(n).map(Value::from).unwrap_or_else(|_| n.to_string().into())
+}
+
+/// The key and value of a single entry object, as produced for enums.
+fn single_entry(value: Value) -> (String, Value) {
+	match value {
+		Value::Object(map) if map.len() == 1 => map.into_iter().next().expect("len is 1; qed"),
+		Value::String(name) => (name, Value::Null),
+		other => (String::new(), other),
+	}
+}
+
+impl std::fmt::Display for Described {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}::{}", self.pallet, self.name)?;
+		if !self.args.is_null() {
+			write!(f, " {}", self.args)?;
+		}
+		Ok(())
+	}
+}
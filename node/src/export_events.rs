@@ -0,0 +1,177 @@
+//! The `export-events` subcommand, which writes decoded extrinsics and events as JSON Lines.
+
+use crate::{
+	decode::{Decoder, Described},
+	inspect::BlockInfo,
+	service::FullClient,
+};
+use node_template_runtime::{Balance, BlockNumber, Hash};
+use sc_cli::{CliConfiguration, ImportParams, SharedParams};
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use std::{
+	fs::{self, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf},
+};
+
+/// The `export-events` command.
+#[derive(Debug, clap::Parser)]
+pub struct ExportEventsCmd {
+	/// First block to export, unless the cursor file records a later position.
+	#[arg(long, default_value_t = 0)]
+	pub from: BlockNumber,
+
+	/// Last block to export. Defaults to the last finalized block.
+	#[arg(long)]
+	pub to: Option<BlockNumber>,
+
+	/// Append the JSON Lines to this file instead of writing them to stdout.
+	#[arg(long, value_name = "FILE")]
+	pub output: Option<PathBuf>,
+
+	/// File recording the last exported block, so that a new run continues after it.
+	#[arg(long, value_name = "FILE")]
+	pub cursor: Option<PathBuf>,
+
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+/// One line of the export.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Line<'a> {
+	/// `extrinsic` or `event`.
+	pub kind: &'static str,
+	pub block_number: BlockNumber,
+	pub block_hash: Hash,
+	/// The time the block was authored at, in milliseconds since the Unix epoch.
+	pub timestamp: Option<u64>,
+	/// The extrinsic, or the extrinsic that deposited the event.
+	pub extrinsic_index: Option<usize>,
+	pub pallet: &'a str,
+	/// The name of the call or event.
+	pub name: &'a str,
+	pub args: &'a serde_json::Value,
+	/// The SS58 address of the signer, for signed extrinsics.
+	pub signer: Option<&'a str>,
+	/// The fee paid, for signed extrinsics.
+	pub fee: Option<Balance>,
+}
+
+impl<'a> Line<'a> {
+	fn new(kind: &'static str, block: &BlockInfo, described: &'a Described) -> Self {
+		Self {
+			kind,
+			block_number: block.number,
+			block_hash: block.hash,
+			timestamp: block.timestamp,
+			extrinsic_index: None,
+			pallet: &described.pallet,
+			name: &described.name,
+			args: &described.args,
+			signer: None,
+			fee: None,
+		}
+	}
+}
+
+impl ExportEventsCmd {
+	/// Run the command.
+	pub fn run(&self, client: &FullClient) -> sc_cli::Result<()> {
+		let from = match self.cursor.as_deref().map(read_cursor).transpose()?.flatten() {
+			Some(last) => self.from.max(last + 1),
+			None => self.from,
+		};
+		let to = self.to.unwrap_or_else(|| client.info().finalized_number);
+
+		let mut output: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(io::BufWriter::new(
+				OpenOptions::new().create(true).append(true).open(path)?,
+			)),
+			None => Box::new(io::BufWriter::new(io::stdout().lock())),
+		};
+
+		let decoder = Decoder::default();
+		for number in from..=to {
+			let hash = client
+				.hash(number)
+				.map_err(|e| e.to_string())?
+				.ok_or_else(|| format!("Block #{} is not imported", number))?;
+			let block = BlockInfo::read(client, &decoder, hash)?;
+
+			write_block(&mut output, &block)?;
+			// The cursor must never get ahead of what has been written.
+			output.flush()?;
+			if let Some(cursor) = &self.cursor {
+				write_cursor(cursor, number)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportEventsCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+/// Writes the extrinsics of `block`, each followed by its events, then the block's own events.
+fn write_block(output: &mut impl Write, block: &BlockInfo) -> io::Result<()> {
+	for extrinsic in &block.extrinsics {
+		let line = Line {
+			extrinsic_index: extrinsic.index,
+			signer: extrinsic.signer.as_deref(),
+			fee: extrinsic.fee,
+			..Line::new("extrinsic", block, &extrinsic.call)
+		};
+		write_line(output, &line)?;
+
+		for event in &extrinsic.events {
+			let line =
+				Line { extrinsic_index: extrinsic.index, ..Line::new("event", block, event) };
+			write_line(output, &line)?;
+		}
+	}
+
+	for event in &block.events {
+		write_line(output, &Line::new("event", block, event))?;
+	}
+
+	Ok(())
+}
+
+fn write_line(output: &mut impl Write, line: &Line) -> io::Result<()> {
+	serde_json::to_writer(&mut *output, line)?;
+	output.write_all(b"\n")
+}
+
+/// The last exported block recorded in the cursor file, if there is one.
+fn read_cursor(path: &Path) -> Result<Option<BlockNumber>, String> {
+	match fs::read_to_string(path) {
+		Ok(content) => content
+			.trim()
+			.parse()
+			.map(Some)
+			.map_err(|e| format!("Invalid cursor in {}: {}", path.display(), e)),
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+		Err(e) => Err(format!("Error reading cursor {}: {}", path.display(), e)),
+	}
+}
+
+/// Records `number` as the last exported block, replacing the cursor file atomically.
+fn write_cursor(path: &Path, number: BlockNumber) -> io::Result<()> {
+	let tmp = path.with_extension("tmp");
+	fs::write(&tmp, number.to_string())?;
+	fs::rename(tmp, path)
+}
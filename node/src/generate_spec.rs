@@ -0,0 +1,250 @@
+//! The `generate-spec` subcommand, which turns a declarative network description into chain specs.
+//!
+//! A description is a TOML (`.toml`) or JSON (any other extension) file such as:
+//!
+//! ```toml
+//! name = "My Network"
+//! id = "my_network"
+//! chain_type = "Live"
+//! protocol_id = "mynet"
+//! sudo = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! consensus = "authorities"
+//! bootnodes = ["/dns/boot.example.com/tcp/30333/p2p/12D3KooW..."]
+//!
+//! [properties]
+//! tokenSymbol = "UNIT"
+//! tokenDecimals = 12
+//!
+//! [[authorities]]
+//! aura = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! grandpa = "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu"
+//!
+//! [[endowed]]
+//! account = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! balance = "1000000000000000000000"
+//! ```
+
+use crate::chain_spec::{self, BlockAuthorId, ChainSpec, ChainSpecBuilder, Consensus, Extensions};
+use node_template_runtime::{AccountId, Balance, SS58Prefix, EXISTENTIAL_DEPOSIT, WASM_BINARY};
+use sc_chain_spec::ChainSpec as _;
+use sc_service::{config::MultiaddrWithPeerId, ChainType, Properties};
+use serde::{Deserialize, Deserializer};
+use sp_core::Get;
+use sp_finality_grandpa::AuthorityId as GrandpaId;
+use std::{
+	collections::BTreeSet,
+	fs,
+	path::{Path, PathBuf},
+};
+
+/// The `generate-spec` command.
+#[derive(Debug, clap::Parser)]
+pub struct GenerateSpecCmd {
+	/// The network description, in TOML or JSON format.
+	#[arg(value_name = "FILE")]
+	pub description: PathBuf,
+
+	/// Directory to write the plain (`<id>.json`) and raw (`<id>-raw.json`) chain specs to.
+	#[arg(long, value_name = "DIR", default_value = ".")]
+	pub output_dir: PathBuf,
+}
+
+impl GenerateSpecCmd {
+	/// Run the command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let description = NetworkDescription::from_file(&self.description)?;
+		description.validate()?;
+		let chain_spec = description.into_chain_spec()?;
+
+		fs::create_dir_all(&self.output_dir)?;
+		for (raw, suffix) in [(false, ""), (true, "-raw")] {
+			let path = self.output_dir.join(format!("{}{}.json", chain_spec.id(), suffix));
+			fs::write(&path, chain_spec.as_json(raw)?)?;
+			println!("{}", path.display());
+		}
+
+		Ok(())
+	}
+}
+
+/// A network description, as read by the `generate-spec` command.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkDescription {
+	/// Human readable name of the network.
+	pub name: String,
+	/// Identifier of the network, also the name of its directory under `chains`.
+	pub id: String,
+	/// Defaults to `Live`.
+	#[serde(default = "default_chain_type")]
+	pub chain_type: ChainType,
+	#[serde(default)]
+	pub protocol_id: Option<String>,
+	/// How blocks are produced, `authorities` (the default) or `proofOfWork`.
+	#[serde(default)]
+	pub consensus: Consensus,
+	/// Block production and finality keys of the genesis authorities.
+	#[serde(default)]
+	pub authorities: Vec<Authority>,
+	/// The sudo account.
+	pub sudo: AccountId,
+	/// Accounts with a free balance at genesis.
+	#[serde(default)]
+	pub endowed: Vec<Endowment>,
+	/// Chain properties, such as `tokenSymbol` and `tokenDecimals`.
+	///
+	/// Missing token properties default to those of the built-in chain specs.
+	#[serde(default)]
+	pub properties: Properties,
+	#[serde(default)]
+	pub bootnodes: Vec<MultiaddrWithPeerId>,
+}
+
+/// The session keys of a genesis authority, given as SS58 encoded public keys.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Authority {
+	/// The Aura key, or the BABE key with the `babe` feature.
+	#[serde(alias = "aura", alias = "babe")]
+	pub block_author: BlockAuthorId,
+	pub grandpa: GrandpaId,
+}
+
+/// An account endowed at genesis.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Endowment {
+	pub account: AccountId,
+	/// An integer or, for amounts that do not fit into 64 bits, a decimal string.
+	#[serde(deserialize_with = "deserialize_balance")]
+	pub balance: Balance,
+}
+
+fn default_chain_type() -> ChainType {
+	ChainType::Live
+}
+
+fn deserialize_balance<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Balance, D::Error> {
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Repr {
+		Number(u64),
+		String(String),
+	}
+
+	match Repr::deserialize(deserializer)? {
+		Repr::Number(balance) => Ok(balance.into()),
+		Repr::String(balance) => balance.replace('_', "").parse().map_err(serde::de::Error::custom),
+	}
+}
+
+impl NetworkDescription {
+	/// Reads a description, as TOML if the file has a `.toml` extension and as JSON otherwise.
+	pub fn from_file(path: &Path) -> Result<Self, String> {
+		let content = fs::read_to_string(path)
+			.map_err(|e| format!("Error reading network description {}: {}", path.display(), e))?;
+
+		if path.extension().map_or(false, |ext| ext == "toml") {
+			toml::from_str(&content).map_err(|e| format!("Invalid network description: {}", e))
+		} else {
+			serde_json::from_str(&content)
+				.map_err(|e| format!("Invalid network description: {}", e))
+		}
+	}
+
+	/// Checks everything that deserialization alone does not.
+	pub fn validate(&self) -> Result<(), String> {
+		if self.name.trim().is_empty() {
+			return Err("`name` must not be empty".into())
+		}
+		if self.id.is_empty() ||
+			!self
+				.id
+				.chars()
+				.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+		{
+			return Err("`id` must consist of lowercase letters, digits and underscores".into())
+		}
+
+		if let Some(ss58_format) = self.properties.get("ss58Format") {
+			if ss58_format.as_u64() != Some(SS58Prefix::get().into()) {
+				return Err(format!(
+					"`ss58Format` must match the runtime's SS58 prefix {}",
+					SS58Prefix::get()
+				))
+			}
+		}
+
+		// Proof-of-work networks are the only ones that can do without authorities.
+		if self.authorities.is_empty() && self.consensus != Consensus::ProofOfWork {
+			return Err("At least one authority is required".into())
+		}
+		let mut block_authors = BTreeSet::new();
+		let mut grandpa_keys = BTreeSet::new();
+		for authority in &self.authorities {
+			if !block_authors.insert(&authority.block_author) {
+				return Err(format!("Duplicate block author key {}", authority.block_author))
+			}
+			if !grandpa_keys.insert(&authority.grandpa) {
+				return Err(format!("Duplicate GRANDPA key {}", authority.grandpa))
+			}
+		}
+
+		let mut accounts = BTreeSet::new();
+		let mut total_issuance: Balance = 0;
+		for Endowment { account, balance } in &self.endowed {
+			if !accounts.insert(account) {
+				return Err(format!("Account {} is endowed more than once", account))
+			}
+			if *balance < EXISTENTIAL_DEPOSIT {
+				return Err(format!(
+					"Balance of {} is below the existential deposit of {}",
+					account, EXISTENTIAL_DEPOSIT
+				))
+			}
+			total_issuance = total_issuance
+				.checked_add(*balance)
+				.ok_or_else(|| "Total issuance overflows".to_string())?;
+		}
+
+		Ok(())
+	}
+
+	/// Builds the chain spec described by `self`, which should have been validated first.
+	pub fn into_chain_spec(self) -> Result<ChainSpec, String> {
+		let wasm_binary = WASM_BINARY.ok_or_else(|| "Wasm binary not available".to_string())?;
+		let Self {
+			name,
+			id,
+			chain_type,
+			protocol_id,
+			consensus,
+			authorities,
+			sudo,
+			endowed,
+			properties,
+			bootnodes,
+		} = self;
+
+		let authorities: Vec<_> =
+			authorities.into_iter().map(|a| (a.block_author, a.grandpa)).collect();
+		let balances: Vec<_> = endowed.into_iter().map(|e| (e.account, e.balance)).collect();
+
+		let mut builder = ChainSpecBuilder::new(&name, &id, chain_type)
+			.boot_nodes(bootnodes)
+			.properties(properties)
+			.extensions(Extensions { consensus, ..Default::default() });
+		if let Some(protocol_id) = protocol_id {
+			builder = builder.protocol_id(&protocol_id);
+		}
+
+		Ok(builder.build(move || {
+			chain_spec::genesis_config(
+				wasm_binary,
+				authorities.clone(),
+				sudo.clone(),
+				balances.clone(),
+			)
+		}))
+	}
+}
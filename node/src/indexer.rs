@@ -0,0 +1,583 @@
+//! An optional SQLite index of the chain, for queries the node's own database can't answer, such
+//! as all transfers of an account. Enabled with `--indexer-db` and queried with the `indexer_*`
+//! RPC methods.
+//!
+//! The index follows the finalized chain. Best blocks are indexed as well, marked as not
+//! finalized, and dropped again when a reorg retracts them.
+
+use crate::{
+	decode::{Decoder, Described},
+	inspect::BlockInfo,
+	service::FullClient,
+};
+use futures::StreamExt;
+use node_template_runtime::{opaque::Block, Balance, BlockNumber, Hash};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use sc_client_api::{BlockImportNotification, BlockchainEvents, FinalityNotification};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Header as _;
+use std::{
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+const LOG_TARGET: &str = "indexer";
+
+/// Every table refers to its block, so that dropping a retracted block drops all its rows.
+const SCHEMA: &str = "
+	PRAGMA foreign_keys = ON;
+	PRAGMA journal_mode = WAL;
+
+	CREATE TABLE IF NOT EXISTS blocks (
+		hash TEXT PRIMARY KEY,
+		number INTEGER NOT NULL,
+		parent_hash TEXT NOT NULL,
+		timestamp INTEGER,
+		finalized INTEGER NOT NULL
+	);
+	CREATE INDEX IF NOT EXISTS blocks_number ON blocks (number);
+
+	CREATE TABLE IF NOT EXISTS extrinsics (
+		block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+		idx INTEGER NOT NULL,
+		hash TEXT NOT NULL,
+		signer TEXT,
+		pallet TEXT NOT NULL,
+		name TEXT NOT NULL,
+		args TEXT NOT NULL,
+		fee TEXT,
+		success INTEGER NOT NULL,
+		PRIMARY KEY (block_hash, idx)
+	);
+	CREATE INDEX IF NOT EXISTS extrinsics_signer ON extrinsics (signer);
+
+	CREATE TABLE IF NOT EXISTS events (
+		block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+		idx INTEGER NOT NULL,
+		extrinsic_idx INTEGER,
+		pallet TEXT NOT NULL,
+		name TEXT NOT NULL,
+		args TEXT NOT NULL,
+		PRIMARY KEY (block_hash, idx)
+	);
+	CREATE INDEX IF NOT EXISTS events_name ON events (pallet, name);
+
+	CREATE TABLE IF NOT EXISTS transfers (
+		block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+		event_idx INTEGER NOT NULL,
+		extrinsic_idx INTEGER,
+		sender TEXT NOT NULL,
+		recipient TEXT NOT NULL,
+		amount TEXT NOT NULL,
+		PRIMARY KEY (block_hash, event_idx)
+	);
+	CREATE INDEX IF NOT EXISTS transfers_sender ON transfers (sender);
+	CREATE INDEX IF NOT EXISTS transfers_recipient ON transfers (recipient);
+
+	CREATE TABLE IF NOT EXISTS template_events (
+		block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+		event_idx INTEGER NOT NULL,
+		extrinsic_idx INTEGER,
+		name TEXT NOT NULL,
+		who TEXT,
+		args TEXT NOT NULL,
+		PRIMARY KEY (block_hash, event_idx)
+	);
+	CREATE INDEX IF NOT EXISTS template_events_who ON template_events (who);
+";
+
+/// Where and how to index.
+#[derive(Clone, Debug)]
+pub struct IndexerConfig {
+	/// The SQLite database, which is created if it doesn't exist.
+	pub path: PathBuf,
+	/// Index all finalized blocks from genesis. Otherwise a new index starts at the current
+	/// finalized block, and an existing one where it left off.
+	pub backfill: bool,
+}
+
+/// How far the index reaches.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+	pub first_block: Option<BlockNumber>,
+	pub last_finalized: Option<BlockNumber>,
+	pub best_block: Option<BlockNumber>,
+}
+
+/// An indexed extrinsic.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedExtrinsic {
+	pub block_number: BlockNumber,
+	pub block_hash: Hash,
+	pub finalized: bool,
+	/// The time the block was authored at, in milliseconds since the Unix epoch.
+	pub timestamp: Option<u64>,
+	pub index: u32,
+	pub hash: Hash,
+	/// The SS58 address of the signer, for signed extrinsics.
+	pub signer: Option<String>,
+	pub pallet: String,
+	pub name: String,
+	pub args: Value,
+	/// The fee paid, for signed extrinsics.
+	pub fee: Option<Balance>,
+	pub success: bool,
+}
+
+/// An indexed event.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedEvent {
+	pub block_number: BlockNumber,
+	pub block_hash: Hash,
+	pub finalized: bool,
+	/// The extrinsic that deposited the event, if any.
+	pub extrinsic_index: Option<u32>,
+	pub pallet: String,
+	pub name: String,
+	pub args: Value,
+}
+
+/// An indexed `Balances::Transfer` event.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedTransfer {
+	pub block_number: BlockNumber,
+	pub block_hash: Hash,
+	pub finalized: bool,
+	pub extrinsic_index: Option<u32>,
+	/// The SS58 address of the sender.
+	pub from: String,
+	/// The SS58 address of the recipient.
+	pub to: String,
+	pub amount: Balance,
+}
+
+/// The SQLite database of the index, shared by the indexing task and the RPC methods.
+pub struct Database {
+	connection: Mutex<Connection>,
+}
+
+impl Database {
+	/// Opens the database at `path`, creating it if needed.
+	pub fn open(path: &Path) -> rusqlite::Result<Self> {
+		let connection = Connection::open(path)?;
+		connection.execute_batch(SCHEMA)?;
+		Ok(Self { connection: Mutex::new(connection) })
+	}
+
+	fn connection(&self) -> MutexGuard<Connection> {
+		// A panic while holding the lock rolls back the open transaction, if any.
+		self.connection.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	/// How far the index reaches.
+	pub fn status(&self) -> rusqlite::Result<Status> {
+		self.connection().query_row(
+			"SELECT MIN(number), MAX(CASE WHEN finalized THEN number END), MAX(number) FROM blocks",
+			[],
+			|row| {
+				Ok(Status {
+					first_block: row.get(0)?,
+					last_finalized: row.get(1)?,
+					best_block: row.get(2)?,
+				})
+			},
+		)
+	}
+
+	/// The latest extrinsics, only those signed by `signer` if given.
+	pub fn extrinsics(
+		&self,
+		signer: Option<&str>,
+		limit: u32,
+	) -> rusqlite::Result<Vec<IndexedExtrinsic>> {
+		let connection = self.connection();
+		let mut statement = connection.prepare_cached(
+			"SELECT b.number, b.hash, b.finalized, b.timestamp,
+				x.idx, x.hash, x.signer, x.pallet, x.name, x.args, x.fee, x.success
+			FROM extrinsics x JOIN blocks b ON b.hash = x.block_hash
+			WHERE ?1 IS NULL OR x.signer = ?1
+			ORDER BY b.number DESC, x.idx DESC LIMIT ?2",
+		)?;
+		let rows = statement.query_map(params![signer, limit], |row| {
+			Ok(IndexedExtrinsic {
+				block_number: row.get(0)?,
+				block_hash: hash(row, 1)?,
+				finalized: row.get(2)?,
+				timestamp: row.get(3)?,
+				index: row.get(4)?,
+				hash: hash(row, 5)?,
+				signer: row.get(6)?,
+				pallet: row.get(7)?,
+				name: row.get(8)?,
+				args: json(row, 9)?,
+				fee: row.get::<_, Option<String>>(10)?.map(|fee| balance(&fee)).transpose()?,
+				success: row.get(11)?,
+			})
+		})?;
+		rows.collect()
+	}
+
+	/// The latest events of `pallet`, only those named `name` if given.
+	pub fn events(
+		&self,
+		pallet: &str,
+		name: Option<&str>,
+		limit: u32,
+	) -> rusqlite::Result<Vec<IndexedEvent>> {
+		let connection = self.connection();
+		let mut statement = connection.prepare_cached(
+			"SELECT b.number, b.hash, b.finalized, e.extrinsic_idx, e.pallet, e.name, e.args
+			FROM events e JOIN blocks b ON b.hash = e.block_hash
+			WHERE e.pallet = ?1 AND (?2 IS NULL OR e.name = ?2)
+			ORDER BY b.number DESC, e.idx DESC LIMIT ?3",
+		)?;
+		let rows = statement.query_map(params![pallet, name, limit], |row| {
+			Ok(IndexedEvent {
+				block_number: row.get(0)?,
+				block_hash: hash(row, 1)?,
+				finalized: row.get(2)?,
+				extrinsic_index: row.get(3)?,
+				pallet: row.get(4)?,
+				name: row.get(5)?,
+				args: json(row, 6)?,
+			})
+		})?;
+		rows.collect()
+	}
+
+	/// The latest transfers from or to `account`.
+	pub fn transfers(&self, account: &str, limit: u32) -> rusqlite::Result<Vec<IndexedTransfer>> {
+		let connection = self.connection();
+		let mut statement = connection.prepare_cached(
+			"SELECT b.number, b.hash, b.finalized, t.extrinsic_idx, t.sender, t.recipient, t.amount
+			FROM transfers t JOIN blocks b ON b.hash = t.block_hash
+			WHERE t.sender = ?1 OR t.recipient = ?1
+			ORDER BY b.number DESC, t.event_idx DESC LIMIT ?2",
+		)?;
+		let rows = statement.query_map(params![account, limit], |row| {
+			Ok(IndexedTransfer {
+				block_number: row.get(0)?,
+				block_hash: hash(row, 1)?,
+				finalized: row.get(2)?,
+				extrinsic_index: row.get(3)?,
+				from: row.get(4)?,
+				to: row.get(5)?,
+				amount: balance(&row.get::<_, String>(6)?)?,
+			})
+		})?;
+		rows.collect()
+	}
+
+	/// The latest `TemplateModule` events, only those concerning `who` if given.
+	pub fn template_events(
+		&self,
+		who: Option<&str>,
+		limit: u32,
+	) -> rusqlite::Result<Vec<IndexedEvent>> {
+		let connection = self.connection();
+		let mut statement = connection.prepare_cached(
+			"SELECT b.number, b.hash, b.finalized, t.extrinsic_idx, t.name, t.args
+			FROM template_events t JOIN blocks b ON b.hash = t.block_hash
+			WHERE ?1 IS NULL OR t.who = ?1
+			ORDER BY b.number DESC, t.event_idx DESC LIMIT ?2",
+		)?;
+		let rows = statement.query_map(params![who, limit], |row| {
+			Ok(IndexedEvent {
+				block_number: row.get(0)?,
+				block_hash: hash(row, 1)?,
+				finalized: row.get(2)?,
+				extrinsic_index: row.get(3)?,
+				pallet: "TemplateModule".into(),
+				name: row.get(4)?,
+				args: json(row, 5)?,
+			})
+		})?;
+		rows.collect()
+	}
+
+	/// Whether the block with the given hash is indexed, and if so whether as finalized.
+	fn finalized(&self, hash: &Hash) -> rusqlite::Result<Option<bool>> {
+		self.connection()
+			.query_row(
+				"SELECT finalized FROM blocks WHERE hash = ?1",
+				[format!("{:?}", hash)],
+				|row| row.get(0),
+			)
+			.optional()
+	}
+
+	/// Adds `block` with all its extrinsics and events.
+	fn insert(&self, block: &BlockInfo, finalized: bool) -> rusqlite::Result<()> {
+		let mut connection = self.connection();
+		let tx = connection.transaction()?;
+		let block_hash = format!("{:?}", block.hash);
+
+		tx.execute(
+			"INSERT INTO blocks (hash, number, parent_hash, timestamp, finalized)
+			VALUES (?1, ?2, ?3, ?4, ?5)",
+			params![
+				block_hash,
+				block.number,
+				format!("{:?}", block.parent_hash),
+				block.timestamp,
+				finalized
+			],
+		)?;
+
+		let mut event_index = 0;
+		for extrinsic in &block.extrinsics {
+			let index = extrinsic.index.expect("extrinsics read from a block have an index; qed");
+			let success = !extrinsic
+				.events
+				.iter()
+				.any(|e| e.pallet == "System" && e.name == "ExtrinsicFailed");
+			tx.execute(
+				"INSERT INTO extrinsics
+					(block_hash, idx, hash, signer, pallet, name, args, fee, success)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+				params![
+					block_hash,
+					index,
+					format!("{:?}", extrinsic.hash),
+					extrinsic.signer,
+					extrinsic.call.pallet,
+					extrinsic.call.name,
+					extrinsic.call.args.to_string(),
+					extrinsic.fee.map(|fee| fee.to_string()),
+					success
+				],
+			)?;
+
+			for event in &extrinsic.events {
+				insert_event(&tx, &block_hash, event_index, Some(index), event)?;
+				event_index += 1;
+			}
+		}
+		for event in &block.events {
+			insert_event(&tx, &block_hash, event_index, None, event)?;
+			event_index += 1;
+		}
+
+		tx.commit()
+	}
+
+	/// Marks the block with the given hash finalized and drops the other blocks of the same
+	/// height, which can never be finalized anymore.
+	fn finalize(&self, number: BlockNumber, hash: &Hash) -> rusqlite::Result<()> {
+		let mut connection = self.connection();
+		let tx = connection.transaction()?;
+		let hash = format!("{:?}", hash);
+
+		tx.execute("UPDATE blocks SET finalized = 1 WHERE hash = ?1", [&hash])?;
+		tx.execute("DELETE FROM blocks WHERE number = ?1 AND hash != ?2", params![number, hash])?;
+
+		tx.commit()
+	}
+
+	/// Drops the block with the given hash, unless it is finalized.
+	fn retract(&self, hash: &Hash) -> rusqlite::Result<()> {
+		self.connection().execute(
+			"DELETE FROM blocks WHERE hash = ?1 AND NOT finalized",
+			[format!("{:?}", hash)],
+		)?;
+		Ok(())
+	}
+}
+
+/// Adds `event`, and the transfer or `TemplateModule` event it represents.
+fn insert_event(
+	tx: &Transaction,
+	block_hash: &str,
+	index: u32,
+	extrinsic_index: Option<usize>,
+	event: &Described,
+) -> rusqlite::Result<()> {
+	let args = event.args.to_string();
+	tx.execute(
+		"INSERT INTO events (block_hash, idx, extrinsic_idx, pallet, name, args)
+		VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+		params![block_hash, index, extrinsic_index, event.pallet, event.name, args],
+	)?;
+
+	match (event.pallet.as_str(), event.name.as_str()) {
+		("Balances", "Transfer") => {
+			let amount = match &event.args["amount"] {
+				// Amounts that don't fit into 64 bits are decoded as decimal strings.
+				Value::String(amount) => amount.clone(),
+				amount => amount.to_string(),
+			};
+			tx.execute(
+				"INSERT INTO transfers
+					(block_hash, event_idx, extrinsic_idx, sender, recipient, amount)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+				params![
+					block_hash,
+					index,
+					extrinsic_index,
+					event.args["from"].as_str(),
+					event.args["to"].as_str(),
+					amount
+				],
+			)?;
+		},
+		("TemplateModule", name) => {
+			tx.execute(
+				"INSERT INTO template_events (block_hash, event_idx, extrinsic_idx, name, who, args)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+				params![block_hash, index, extrinsic_index, name, event.args["who"].as_str(), args],
+			)?;
+		},
+		_ => (),
+	}
+
+	Ok(())
+}
+
+fn hash(row: &Row, index: usize) -> rusqlite::Result<Hash> {
+	row.get::<_, String>(index)?.parse().map_err(|e| {
+		rusqlite::Error::FromSqlConversionFailure(
+			index,
+			rusqlite::types::Type::Text,
+			format!("Invalid hash: {:?}", e).into(),
+		)
+	})
+}
+
+fn json(row: &Row, index: usize) -> rusqlite::Result<Value> {
+	serde_json::from_str(&row.get::<_, String>(index)?).map_err(|e| {
+		rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, e.into())
+	})
+}
+
+/// Balances are stored as decimal strings, as they don't fit into SQLite's integers.
+fn balance(text: &str) -> rusqlite::Result<Balance> {
+	text.parse().map_err(|e| {
+		rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+	})
+}
+
+enum Notification {
+	Imported(BlockImportNotification<Block>),
+	Finalized(FinalityNotification<Block>),
+}
+
+/// Indexes the chain into `database` until the node shuts down.
+///
+/// Blocks that can't be read, e.g. because their state is pruned, are skipped with a warning.
+/// Database errors stop the indexing, but not the node.
+pub async fn run(client: Arc<FullClient>, database: Arc<Database>, backfill: bool) {
+	if let Err(e) = follow(&client, &database, backfill).await {
+		log::error!(target: LOG_TARGET, "Indexing stopped: {}", e);
+	}
+}
+
+async fn follow(
+	client: &FullClient,
+	database: &Database,
+	backfill: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+	let decoder = Decoder::default();
+	// Subscribe before catching up, so that no block is missed in between.
+	let mut notifications = futures::stream::select(
+		client.import_notification_stream().map(Notification::Imported),
+		client.finality_notification_stream().map(Notification::Finalized),
+	);
+
+	let finalized = client.info().finalized_number;
+	let from = match database.status()?.last_finalized {
+		_ if backfill => 0,
+		Some(last) => last + 1,
+		None => finalized,
+	};
+	if from < finalized {
+		log::info!(target: LOG_TARGET, "Indexing finalized blocks #{} to #{}", from, finalized);
+	}
+	index_finalized(client, database, &decoder, from, finalized)?;
+
+	while let Some(notification) = notifications.next().await {
+		match notification {
+			Notification::Imported(notification) if notification.is_new_best => {
+				if let Some(route) = &notification.tree_route {
+					for block in route.retracted() {
+						database.retract(&block.hash)?;
+					}
+					for block in route.enacted() {
+						index_best(client, database, &decoder, block.hash)?;
+					}
+				}
+				index_best(client, database, &decoder, notification.hash)?;
+			},
+			Notification::Imported(_) => (),
+			Notification::Finalized(notification) => {
+				let from = database.status()?.last_finalized.map_or(0, |last| last + 1);
+				index_finalized(client, database, &decoder, from, *notification.header.number())?;
+			},
+		}
+	}
+
+	Ok(())
+}
+
+/// Indexes the finalized blocks `from..=to`, marking those already indexed as best blocks
+/// finalized.
+fn index_finalized(
+	client: &FullClient,
+	database: &Database,
+	decoder: &Decoder,
+	from: BlockNumber,
+	to: BlockNumber,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+	for number in from..=to {
+		let hash = client
+			.hash(number)?
+			.ok_or_else(|| format!("Block #{} is not imported", number))?;
+		match database.finalized(&hash)? {
+			Some(true) => continue,
+			Some(false) => (),
+			None => match read(client, decoder, number, hash) {
+				Some(info) => database.insert(&info, true)?,
+				None => continue,
+			},
+		}
+		database.finalize(number, &hash)?;
+	}
+
+	Ok(())
+}
+
+/// Indexes the best block with the given hash, unless it already is.
+fn index_best(
+	client: &FullClient,
+	database: &Database,
+	decoder: &Decoder,
+	hash: Hash,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+	if database.finalized(&hash)?.is_none() {
+		let number = client.number(hash)?.ok_or_else(|| format!("Unknown block {}", hash))?;
+		if let Some(info) = read(client, decoder, number, hash) {
+			database.insert(&info, false)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Reads the block, or logs why it can't be indexed.
+fn read(
+	client: &FullClient,
+	decoder: &Decoder,
+	number: BlockNumber,
+	hash: Hash,
+) -> Option<BlockInfo> {
+	// Old blocks' events are gone unless the node keeps their state, which `--indexer-backfill`
+	// requires, but the state of a block may still be pruned while it is being indexed.
+	BlockInfo::read(client, decoder, hash)
+		.map_err(|e| log::warn!(target: LOG_TARGET, "Skipping block #{} ({}): {}", number, hash, e))
+		.ok()
+}
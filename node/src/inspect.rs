@@ -0,0 +1,299 @@
+//! The `inspect` subcommand, which decodes blocks and extrinsics from the node's database.
+
+use crate::{
+	decode::{Decoder, Described},
+	service::FullClient,
+};
+use codec::{Decode, Encode};
+use frame_system::{EventRecord, Phase};
+use node_template_runtime::{
+	opaque::Block, Balance, BlockNumber, Hash, RuntimeCall, RuntimeEvent, TimestampCall,
+	UncheckedExtrinsic,
+};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, ImportParams, SharedParams};
+use sc_client_api::{BlockBackend, StorageProvider};
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, storage::StorageKey, twox_128};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{BlakeTwo256, Hash as _, Header as _},
+	MultiAddress,
+};
+use std::fmt;
+
+/// The `inspect` command.
+#[derive(Debug, clap::Parser)]
+pub struct InspectCmd {
+	#[command(subcommand)]
+	pub command: InspectSubCmd,
+
+	/// Print JSON instead of human-readable text.
+	#[arg(long)]
+	pub json: bool,
+
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+/// What to inspect.
+#[derive(Debug, clap::Subcommand)]
+pub enum InspectSubCmd {
+	/// Decode a block with all its extrinsics and events.
+	Block {
+		/// Number or hash of the block.
+		#[arg(value_name = "HASH or NUMBER")]
+		input: BlockNumberOrHash,
+	},
+
+	/// Decode an extrinsic.
+	Extrinsic {
+		/// `<block>:<index>`, where `<block>` is a block number or hash, or the hex encoded
+		/// extrinsic.
+		#[arg(value_name = "BLOCK:INDEX or HEX")]
+		input: String,
+	},
+}
+
+impl InspectCmd {
+	/// Run the command.
+	pub fn run(&self, client: &FullClient) -> sc_cli::Result<()> {
+		let decoder = Decoder::default();
+
+		match &self.command {
+			InspectSubCmd::Block { input } => {
+				let block = BlockInfo::read(client, &decoder, block_hash(client, input)?)?;
+				self.print(&block)
+			},
+			InspectSubCmd::Extrinsic { input } => {
+				let extrinsic = match input.split_once(':') {
+					Some((block, index)) => {
+						let index =
+							index.parse().map_err(|e| format!("Invalid extrinsic index: {}", e))?;
+						let block = BlockInfo::read(
+							client,
+							&decoder,
+							block_hash(client, &block.parse()?)?,
+						)?;
+						block.extrinsics.into_iter().nth(index).ok_or_else(|| {
+							format!("Block #{} has no extrinsic #{}", block.number, index)
+						})?
+					},
+					None => {
+						let encoded = sp_core::bytes::from_hex(input)
+							.map_err(|e| format!("Invalid extrinsic hex: {}", e))?;
+						let extrinsic = UncheckedExtrinsic::decode(&mut &encoded[..])
+							.map_err(|e| format!("Invalid extrinsic: {}", e))?;
+						ExtrinsicInfo::new(&decoder, None, &extrinsic, Vec::new())
+					},
+				};
+				self.print(&extrinsic)
+			},
+		}
+	}
+
+	fn print(&self, value: &(impl Serialize + fmt::Display)) -> sc_cli::Result<()> {
+		if self.json {
+			let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+			println!("{}", json);
+		} else {
+			print!("{}", value);
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for InspectCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+/// A decoded block.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockInfo {
+	pub number: BlockNumber,
+	pub hash: Hash,
+	pub parent_hash: Hash,
+	pub state_root: Hash,
+	/// The time the block was authored at, in milliseconds since the Unix epoch.
+	pub timestamp: Option<u64>,
+	pub extrinsics: Vec<ExtrinsicInfo>,
+	/// Events deposited outside of any extrinsic, during initialization or finalization.
+	pub events: Vec<Described>,
+}
+
+impl BlockInfo {
+	/// Reads and decodes the block with the given hash.
+	pub fn read(client: &FullClient, decoder: &Decoder, hash: Hash) -> Result<Self, String> {
+		let block = client
+			.block(&BlockId::Hash(hash))
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Block {} has no body", hash))?
+			.block;
+		let mut events = block_events(client, hash)?;
+		let mut timestamp = None;
+
+		let extrinsics = block
+			.extrinsics
+			.iter()
+			.enumerate()
+			.map(|(index, opaque)| {
+				let extrinsic = UncheckedExtrinsic::decode(&mut &opaque.encode()[..])
+					.map_err(|e| format!("Invalid extrinsic #{}: {}", index, e))?;
+				if let RuntimeCall::Timestamp(TimestampCall::set { now }) = &extrinsic.function {
+					timestamp = Some(*now);
+				}
+				let (own, rest): (Vec<_>, Vec<_>) = events
+					.drain(..)
+					.partition(|record| record.phase == Phase::ApplyExtrinsic(index as u32));
+				events = rest;
+
+				Ok(ExtrinsicInfo::new(
+					decoder,
+					Some(index),
+					&extrinsic,
+					own.into_iter().map(|record| record.event).collect(),
+				))
+			})
+			.collect::<Result<_, String>>()?;
+
+		Ok(Self {
+			number: *block.header.number(),
+			hash,
+			parent_hash: *block.header.parent_hash(),
+			state_root: *block.header.state_root(),
+			timestamp,
+			extrinsics,
+			events: events.iter().map(|record| decoder.event(&record.event)).collect(),
+		})
+	}
+}
+
+impl fmt::Display for BlockInfo {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "Block #{} {:?}", self.number, self.hash)?;
+		writeln!(f, "  parent: {:?}", self.parent_hash)?;
+		writeln!(f, "  state root: {:?}", self.state_root)?;
+		if let Some(timestamp) = self.timestamp {
+			writeln!(f, "  timestamp: {}", timestamp)?;
+		}
+		for event in &self.events {
+			writeln!(f, "  event: {}", event)?;
+		}
+		for extrinsic in &self.extrinsics {
+			write!(f, "{}", extrinsic)?;
+		}
+		Ok(())
+	}
+}
+
+/// A decoded extrinsic.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtrinsicInfo {
+	/// The position in the block, if the extrinsic was read from one.
+	pub index: Option<usize>,
+	pub hash: Hash,
+	/// The SS58 address of the signer, for signed extrinsics.
+	pub signer: Option<String>,
+	/// The data of the signed extensions, for signed extrinsics.
+	pub signed_extra: Option<serde_json::Value>,
+	pub call: Described,
+	/// The fee the signer paid, including the tip.
+	pub fee: Option<Balance>,
+	pub events: Vec<Described>,
+}
+
+impl ExtrinsicInfo {
+	/// Decodes `extrinsic`, which deposited `events`.
+	pub fn new(
+		decoder: &Decoder,
+		index: Option<usize>,
+		extrinsic: &UncheckedExtrinsic,
+		events: Vec<RuntimeEvent>,
+	) -> Self {
+		let (signer, signed_extra) = match &extrinsic.signature {
+			Some((address, _, extra)) => {
+				let signer = match address {
+					MultiAddress::Id(account) => account.to_ss58check(),
+					other => format!("{:?}", other),
+				};
+				(Some(signer), Some(decoder.signed_extra(extra)))
+			},
+			None => (None, None),
+		};
+
+		Self {
+			index,
+			hash: BlakeTwo256::hash_of(extrinsic),
+			signer,
+			signed_extra,
+			call: decoder.call(&extrinsic.function),
+			fee: events.iter().find_map(|event| match event {
+				RuntimeEvent::TransactionPayment(
+					pallet_transaction_payment::Event::TransactionFeePaid { actual_fee, .. },
+				) => Some(*actual_fee),
+				_ => None,
+			}),
+			events: events.iter().map(|event| decoder.event(event)).collect(),
+		}
+	}
+}
+
+impl fmt::Display for ExtrinsicInfo {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.index {
+			Some(index) => writeln!(f, "Extrinsic #{} {:?}", index, self.hash)?,
+			None => writeln!(f, "Extrinsic {:?}", self.hash)?,
+		}
+		if let Some(signer) = &self.signer {
+			writeln!(f, "  signer: {}", signer)?;
+		}
+		if let Some(serde_json::Value::Object(extra)) = &self.signed_extra {
+			for (extension, value) in extra {
+				writeln!(f, "  {}: {}", extension, value)?;
+			}
+		}
+		writeln!(f, "  call: {}", self.call)?;
+		if let Some(fee) = self.fee {
+			writeln!(f, "  fee: {}", fee)?;
+		}
+		for event in &self.events {
+			writeln!(f, "  event: {}", event)?;
+		}
+		Ok(())
+	}
+}
+
+/// The hash of the block with the given number or hash.
+pub fn block_hash(client: &FullClient, block: &BlockNumberOrHash) -> Result<Hash, String> {
+	let id = block.parse::<Block>()?;
+	client
+		.block_hash_from_id(&id)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("Unknown block {:?}", id))
+}
+
+/// The events deposited in the block with the given hash.
+pub fn block_events(
+	client: &FullClient,
+	hash: Hash,
+) -> Result<Vec<EventRecord<RuntimeEvent, Hash>>, String> {
+	let key = StorageKey([twox_128(b"System"), twox_128(b"Events")].concat());
+	let events = client.storage(&hash, &key).map_err(|e| e.to_string())?;
+
+	events
+		.map(|events| {
+			Decode::decode(&mut &events.0[..]).map_err(|e| format!("Invalid events: {}", e))
+		})
+		.unwrap_or_else(|| Ok(Vec::new()))
+}
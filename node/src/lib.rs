@@ -1,3 +1,22 @@
+//! Substrate Node Template CLI library.
+
+pub mod benchmarking;
 pub mod chain_spec;
+pub mod cli;
+pub mod command;
+pub mod decode;
+pub mod export_events;
+pub mod generate_spec;
+pub mod indexer;
+pub mod inspect;
+pub mod load;
+pub mod pow;
 pub mod rpc;
 pub mod service;
+pub mod snapshot;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod tx;
+pub mod tx_status;
+pub mod validator;
+pub mod warp_sync;
@@ -0,0 +1,423 @@
+//! The `load` subcommand, which submits transactions to a node at a target rate and reports how
+//! quickly they are included and finalized.
+
+use crate::{
+	benchmarking,
+	tx::{self, rpc_error},
+};
+use codec::Encode;
+use futures::StreamExt;
+use jsonrpsee::{
+	core::client::{ClientT, Subscription, SubscriptionClientT},
+	rpc_params,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use node_template_runtime::{
+	opaque::Block, pallet_template, AccountId, Balance, BalancesCall, BlockNumber, Hash, Header,
+	Index, RuntimeCall, EXISTENTIAL_DEPOSIT,
+};
+use sp_core::{crypto::Ss58Codec, sr25519, Bytes, Pair};
+use sp_runtime::{
+	generic::SignedBlock,
+	traits::{BlakeTwo256, Hash as _, Header as _},
+};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+/// The `load` command.
+#[derive(Debug, clap::Parser)]
+pub struct LoadCmd {
+	/// WebSocket RPC endpoint of the node to submit to.
+	#[arg(long, value_name = "URL", default_value = "ws://127.0.0.1:9944")]
+	pub rpc_url: String,
+
+	/// The secret URI of the account funding the senders.
+	#[arg(long, default_value = "//Alice")]
+	pub funder: String,
+
+	/// Number of sending accounts, derived from the funder as `<funder>//load//<index>`.
+	#[arg(long, default_value_t = 100)]
+	pub accounts: u32,
+
+	/// The amount each sending account is funded with.
+	#[arg(long, default_value_t = 1_000_000_000_000_000)]
+	pub fund: Balance,
+
+	/// Transactions to submit per second.
+	#[arg(long, value_name = "TPS", default_value_t = 100)]
+	pub rate: u32,
+
+	/// How long to submit transactions for, in seconds.
+	#[arg(long, value_name = "SECONDS", default_value_t = 60)]
+	pub duration: u64,
+
+	/// How long to wait for the submitted transactions to be finalized afterwards, in seconds.
+	#[arg(long, value_name = "SECONDS", default_value_t = 60)]
+	pub wait: u64,
+
+	/// The transactions to submit.
+	#[arg(long, value_enum, default_value_t = Workload::Transfer)]
+	pub workload: Workload,
+}
+
+/// The transactions the `load` command submits.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum Workload {
+	/// `Balances::transfer_keep_alive` of the existential deposit to the next sending account.
+	Transfer,
+	/// `TemplateModule::do_something`.
+	DoSomething,
+}
+
+/// A sending account.
+struct Sender {
+	pair: sr25519::Pair,
+	nonce: Index,
+}
+
+impl LoadCmd {
+	/// Run the command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		if self.accounts == 0 || self.rate == 0 {
+			return Err("`--accounts` and `--rate` must be positive".into())
+		}
+		let funder = sr25519::Pair::from_string(&self.funder, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+
+		sc_cli::build_runtime()?.block_on(async {
+			let rpc = Arc::new(
+				WsClientBuilder::default()
+					.build(&self.rpc_url)
+					.await
+					.map_err(|e| format!("Error connecting to {}: {}", self.rpc_url, e))?,
+			);
+			let genesis_hash: Hash = rpc
+				.request::<Option<Hash>>("chain_getBlockHash", rpc_params![0u32])
+				.await
+				.map_err(rpc_error("chain_getBlockHash"))?
+				.ok_or("The node has no genesis block")?;
+			let best = best_header(&rpc).await?;
+			let sign = |call, pair: &sr25519::Pair, nonce| {
+				let params =
+					benchmarking::signing_params(genesis_hash, nonce, best.number, best.hash());
+				tx::sign(call, pair, &params)
+			};
+
+			// Subscribe before submitting anything, so that no inclusion goes unnoticed.
+			let best_heads = rpc
+				.subscribe("chain_subscribeNewHeads", rpc_params![], "chain_unsubscribeNewHeads")
+				.await
+				.map_err(rpc_error("chain_subscribeNewHeads"))?;
+			let finalized_heads = rpc
+				.subscribe(
+					"chain_subscribeFinalizedHeads",
+					rpc_params![],
+					"chain_unsubscribeFinalizedHeads",
+				)
+				.await
+				.map_err(rpc_error("chain_subscribeFinalizedHeads"))?;
+			let tracker = Arc::new(Mutex::new(Tracker::default()));
+			let followers = [
+				tokio::spawn(follow_best(rpc.clone(), best_heads, tracker.clone())),
+				tokio::spawn(follow_finalized(rpc.clone(), finalized_heads, tracker.clone())),
+			];
+
+			// Fund the senders, and wait until they can pay.
+			let mut senders = Vec::new();
+			let mut nonce = account_nonce(&rpc, &funder.public().into()).await?;
+			for index in 0..self.accounts {
+				let pair =
+					sr25519::Pair::from_string(&format!("{}//load//{}", self.funder, index), None)
+						.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+				let call = RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+					dest: AccountId::from(pair.public()).into(),
+					value: self.fund,
+				});
+				submit(&rpc, &tracker, sign(call, &funder, nonce).encode())
+					.await
+					.map_err(|e| format!("Funding sender #{} failed: {}", index, e))?;
+				nonce += 1;
+				senders.push(Sender { pair, nonce: 0 });
+			}
+			println!("Funding {} senders", senders.len());
+			wait_for(&tracker, Duration::from_secs(self.wait), |tracker| tracker.pending() == 0)
+				.await
+				.map_err(|_| "The senders were not funded in time")?;
+			for sender in &mut senders {
+				sender.nonce = account_nonce(&rpc, &sender.pair.public().into()).await?;
+			}
+
+			*tracker.lock().expect("the tracker is never poisoned; qed") = Tracker::default();
+			let start = Instant::now();
+			let mut interval = tokio::time::interval(Duration::from_secs(1) / self.rate);
+			let mut submitted = 0u64;
+			let mut rejected = BTreeMap::<String, u64>::new();
+
+			while start.elapsed() < Duration::from_secs(self.duration) {
+				interval.tick().await;
+				let index = (submitted % senders.len() as u64) as usize;
+				let call = match self.workload {
+					Workload::Transfer => {
+						let dest = &senders[(index + 1) % senders.len()].pair;
+						RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+							dest: AccountId::from(dest.public()).into(),
+							value: EXISTENTIAL_DEPOSIT,
+						})
+					},
+					Workload::DoSomething =>
+						RuntimeCall::TemplateModule(pallet_template::Call::do_something {
+							something: submitted as u32,
+						}),
+				};
+				let sender = &mut senders[index];
+				submitted += 1;
+
+				match submit(&rpc, &tracker, sign(call, &sender.pair, sender.nonce).encode()).await
+				{
+					Ok(()) => sender.nonce += 1,
+					Err(e) => {
+						*rejected.entry(e).or_default() += 1;
+						// The nonce may have gone out of sync, e.g. if a transaction was dropped.
+						sender.nonce = account_nonce(&rpc, &sender.pair.public().into()).await?;
+					},
+				}
+			}
+			let submitting = start.elapsed();
+
+			let _ = wait_for(&tracker, Duration::from_secs(self.wait), |tracker| {
+				tracker.pending() == 0
+			})
+			.await;
+			for follower in followers {
+				follower.abort();
+			}
+
+			let tracker = tracker.lock().expect("the tracker is never poisoned; qed");
+			println!(
+				"{}",
+				Report { submitted, submitting, rejected: &rejected, tracker: &tracker, start }
+			);
+			Ok::<_, sc_cli::Error>(())
+		})
+	}
+}
+
+/// What became of the submitted transactions.
+#[derive(Default)]
+struct Tracker {
+	/// The transactions not finalized yet, with their submission time and when they were first
+	/// seen in a best block.
+	submitted: HashMap<Hash, (Instant, Option<Instant>)>,
+	/// When the transactions were first seen in a block.
+	inclusions: Vec<Instant>,
+	inclusion_latencies: Vec<Duration>,
+	finality_latencies: Vec<Duration>,
+}
+
+impl Tracker {
+	fn pending(&self) -> usize {
+		self.submitted.len()
+	}
+
+	fn included(&mut self, extrinsics: &[Hash], now: Instant) {
+		for hash in extrinsics {
+			if let Some((submitted, included @ None)) = self.submitted.get_mut(hash) {
+				*included = Some(now);
+				self.inclusions.push(now);
+				self.inclusion_latencies.push(now - *submitted);
+			}
+		}
+	}
+
+	fn finalized(&mut self, extrinsics: &[Hash], now: Instant) {
+		self.included(extrinsics, now);
+		for hash in extrinsics {
+			if let Some((submitted, _)) = self.submitted.remove(hash) {
+				self.finality_latencies.push(now - submitted);
+			}
+		}
+	}
+}
+
+/// The summary of a run.
+struct Report<'a> {
+	submitted: u64,
+	submitting: Duration,
+	rejected: &'a BTreeMap<String, u64>,
+	tracker: &'a Tracker,
+	start: Instant,
+}
+
+impl fmt::Display for Report<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let rejected = self.rejected.values().sum::<u64>();
+		let tracker = self.tracker;
+		writeln!(
+			f,
+			"Submitted {} transactions in {:.1}s ({:.1}/s), {} rejected by the pool",
+			self.submitted,
+			self.submitting.as_secs_f64(),
+			self.submitted as f64 / self.submitting.as_secs_f64(),
+			rejected
+		)?;
+		for (reason, count) in self.rejected {
+			writeln!(f, "  {}x {}", count, reason)?;
+		}
+		writeln!(
+			f,
+			"Included {}, finalized {}, still pending {}",
+			tracker.inclusion_latencies.len(),
+			tracker.finality_latencies.len(),
+			tracker.pending()
+		)?;
+		writeln!(f, "Inclusion latency: {}", Latencies(&tracker.inclusion_latencies))?;
+		writeln!(f, "Finality latency: {}", Latencies(&tracker.finality_latencies))?;
+
+		// From the start of the run to the last inclusion, as blocks include transactions in
+		// batches.
+		let tps = match tracker.inclusions.iter().max() {
+			Some(last) if *last > self.start =>
+				tracker.inclusions.len() as f64 / (*last - self.start).as_secs_f64(),
+			_ => 0.0,
+		};
+		write!(f, "Achieved TPS: {:.1}", tps)
+	}
+}
+
+/// Formats the distribution of some latencies.
+struct Latencies<'a>(&'a [Duration]);
+
+impl fmt::Display for Latencies<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.0.is_empty() {
+			return write!(f, "-")
+		}
+		let mut sorted = self.0.to_vec();
+		sorted.sort();
+		let percentile = |p: usize| sorted[(sorted.len() - 1) * p / 100].as_secs_f64();
+		let mean = sorted.iter().sum::<Duration>().as_secs_f64() / sorted.len() as f64;
+
+		write!(
+			f,
+			"min {:.2}s, mean {:.2}s, p50 {:.2}s, p95 {:.2}s, max {:.2}s",
+			percentile(0),
+			mean,
+			percentile(50),
+			percentile(95),
+			percentile(100)
+		)
+	}
+}
+
+/// Submits the encoded extrinsic and starts tracking it, or returns the pool's rejection.
+async fn submit(
+	rpc: &WsClient,
+	tracker: &Mutex<Tracker>,
+	extrinsic: Vec<u8>,
+) -> Result<(), String> {
+	let submitted = Instant::now();
+	let hash: Hash = rpc
+		.request("author_submitExtrinsic", rpc_params![Bytes(extrinsic)])
+		.await
+		.map_err(|e| match e {
+			jsonrpsee::core::Error::Call(e) => e.to_string(),
+			e => format!("`author_submitExtrinsic` failed: {}", e),
+		})?;
+	tracker
+		.lock()
+		.expect("the tracker is never poisoned; qed")
+		.submitted
+		.insert(hash, (submitted, None));
+	Ok(())
+}
+
+/// Waits until `condition` holds for the tracker, or until the timeout.
+async fn wait_for(
+	tracker: &Mutex<Tracker>,
+	timeout: Duration,
+	condition: impl Fn(&Tracker) -> bool,
+) -> Result<(), tokio::time::error::Elapsed> {
+	tokio::time::timeout(timeout, async {
+		while !condition(&tracker.lock().expect("the tracker is never poisoned; qed")) {
+			tokio::time::sleep(Duration::from_millis(100)).await;
+		}
+	})
+	.await
+}
+
+async fn account_nonce(rpc: &WsClient, account: &AccountId) -> Result<Index, String> {
+	rpc.request("system_accountNextIndex", rpc_params![account.to_ss58check()])
+		.await
+		.map_err(rpc_error("system_accountNextIndex"))
+}
+
+async fn best_header(rpc: &WsClient) -> Result<Header, String> {
+	rpc.request::<Option<Header>>("chain_getHeader", rpc_params![])
+		.await
+		.map_err(rpc_error("chain_getHeader"))?
+		.ok_or_else(|| "The node has no best block".into())
+}
+
+/// The hashes of the extrinsics in the block with the given hash.
+async fn extrinsic_hashes(rpc: &WsClient, hash: Hash) -> Result<Vec<Hash>, String> {
+	let block = rpc
+		.request::<Option<SignedBlock<Block>>>("chain_getBlock", rpc_params![hash])
+		.await
+		.map_err(rpc_error("chain_getBlock"))?
+		.ok_or_else(|| format!("Unknown block {}", hash))?;
+
+	Ok(block.block.extrinsics.iter().map(BlakeTwo256::hash_of).collect())
+}
+
+/// Records the inclusion of the tracked transactions in new best blocks.
+async fn follow_best(
+	rpc: Arc<WsClient>,
+	mut heads: Subscription<Header>,
+	tracker: Arc<Mutex<Tracker>>,
+) -> Result<(), String> {
+	while let Some(header) = heads.next().await {
+		let header = header.map_err(rpc_error("chain_subscribeNewHeads"))?;
+		let extrinsics = extrinsic_hashes(&rpc, header.hash()).await?;
+		tracker
+			.lock()
+			.expect("the tracker is never poisoned; qed")
+			.included(&extrinsics, Instant::now());
+	}
+
+	Ok(())
+}
+
+/// Records the finalization of the tracked transactions, including in the blocks finalized
+/// along with the announced ones.
+async fn follow_finalized(
+	rpc: Arc<WsClient>,
+	mut heads: Subscription<Header>,
+	tracker: Arc<Mutex<Tracker>>,
+) -> Result<(), String> {
+	let mut next: Option<BlockNumber> = None;
+	while let Some(header) = heads.next().await {
+		let header = header.map_err(rpc_error("chain_subscribeFinalizedHeads"))?;
+		for number in next.unwrap_or(header.number)..=header.number {
+			let hash = match number == header.number {
+				true => header.hash(),
+				false => rpc
+					.request::<Option<Hash>>("chain_getBlockHash", rpc_params![number])
+					.await
+					.map_err(rpc_error("chain_getBlockHash"))?
+					.ok_or_else(|| format!("Finalized block #{} is unknown", number))?,
+			};
+			let extrinsics = extrinsic_hashes(&rpc, hash).await?;
+			tracker
+				.lock()
+				.expect("the tracker is never poisoned; qed")
+				.finalized(&extrinsics, Instant::now());
+		}
+		next = Some(header.number + 1);
+	}
+
+	Ok(())
+}
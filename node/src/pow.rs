@@ -0,0 +1,105 @@
+//! A SHA3-256 proof-of-work algorithm for authority-less test networks.
+//!
+//! The difficulty is read from the runtime's `pallet_difficulty` through the
+//! [`DifficultyApi`], so all nodes agree on it without any off-chain coordination.
+
+use codec::{Decode, Encode};
+use node_template_runtime::pallet_difficulty::Difficulty;
+use sc_consensus_pow::{Error, PowAlgorithm};
+use sha3::{Digest, Sha3_256};
+use sp_api::ProvideRuntimeApi;
+use sp_consensus_pow::{DifficultyApi, Seal as RawSeal};
+use sp_core::{H256, U256};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::sync::Arc;
+
+/// Checks whether `hash` satisfies `difficulty`.
+///
+/// The hash, read as a number, must not overflow `U256` when multiplied by the difficulty.
+pub fn hash_meets_difficulty(hash: &H256, difficulty: Difficulty) -> bool {
+	let num_hash = U256::from(&hash[..]);
+	let (_, overflowed) = num_hash.overflowing_mul(U256::from(difficulty));
+
+	!overflowed
+}
+
+/// The seal attached to every proof-of-work block.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Debug)]
+pub struct Seal {
+	pub difficulty: Difficulty,
+	pub work: H256,
+	pub nonce: H256,
+}
+
+/// The preimage of a seal's `work`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Debug)]
+pub struct Compute {
+	pub difficulty: Difficulty,
+	pub pre_hash: H256,
+	pub nonce: H256,
+}
+
+impl Compute {
+	/// Hashes `self` into the seal it would produce.
+	pub fn compute(self) -> Seal {
+		let work = H256::from_slice(Sha3_256::digest(&self.encode()[..]).as_slice());
+
+		Seal { difficulty: self.difficulty, work, nonce: self.nonce }
+	}
+}
+
+/// SHA3-256 proof of work with the difficulty stored on chain.
+pub struct Sha3Algorithm<C> {
+	client: Arc<C>,
+}
+
+impl<C> Sha3Algorithm<C> {
+	/// Creates a new [`Self`] reading the difficulty through the given client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+// Manual implementation as `derive` would require `C: Clone`.
+impl<C> Clone for Sha3Algorithm<C> {
+	fn clone(&self) -> Self {
+		Self { client: self.client.clone() }
+	}
+}
+
+impl<B, C> PowAlgorithm<B> for Sha3Algorithm<C>
+where
+	B: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<B>,
+	C::Api: DifficultyApi<B, Difficulty>,
+{
+	type Difficulty = Difficulty;
+
+	fn difficulty(&self, parent: B::Hash) -> Result<Self::Difficulty, Error<B>> {
+		self.client.runtime_api().difficulty(&BlockId::Hash(parent)).map_err(|err| {
+			Error::Environment(format!("Fetching difficulty from runtime failed: {:?}", err))
+		})
+	}
+
+	fn verify(
+		&self,
+		_parent: &BlockId<B>,
+		pre_hash: &H256,
+		_pre_digest: Option<&[u8]>,
+		seal: &RawSeal,
+		difficulty: Self::Difficulty,
+	) -> Result<bool, Error<B>> {
+		let seal = match Seal::decode(&mut &seal[..]) {
+			Ok(seal) => seal,
+			Err(_) => return Ok(false),
+		};
+
+		if seal.difficulty != difficulty || !hash_meets_difficulty(&seal.work, difficulty) {
+			return Ok(false)
+		}
+
+		let compute = Compute { difficulty, pre_hash: *pre_hash, nonce: seal.nonce };
+
+		Ok(compute.compute() == seal)
+	}
+}
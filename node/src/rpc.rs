@@ -5,12 +5,26 @@
 
 #![warn(missing_docs)]
 
+pub mod account;
+pub mod dry_run;
+pub mod events;
+pub mod indexer;
+pub mod middleware;
+pub mod payment;
+pub mod tx_status;
+pub mod txpool;
+
 use std::sync::Arc;
 
 use jsonrpsee::RpcModule;
-use node_template_runtime::{opaque::Block, AccountId, Balance, Index};
+use node_template_runtime::{
+	apis::{AccountApi, DryRunApi, PaymentApi},
+	opaque::Block,
+	AccountId, Balance, Hash, Index,
+};
+use sc_client_api::{BlockchainEvents, StorageProvider};
 use sc_transaction_pool_api::TransactionPool;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiExt, CallApiAt, ProvideRuntimeApi};
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 
@@ -24,6 +38,12 @@ pub struct FullDeps<C, P> {
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Executor for the tasks feeding subscriptions.
+	pub subscription_executor: sc_rpc::SubscriptionTaskExecutor,
+	/// The chain index, if the node keeps one.
+	pub indexer: Option<Arc<crate::indexer::Database>>,
+	/// The transaction statuses, if the node records them.
+	pub tx_status: Option<Arc<crate::tx_status::Store>>,
 }
 
 /// Instantiate all full RPC extensions.
@@ -31,22 +51,46 @@ pub fn create_full<C, P>(
 	deps: FullDeps<C, P>,
 ) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
 where
-	C: ProvideRuntimeApi<Block>,
+	C: ProvideRuntimeApi<Block> + CallApiAt<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+	C: BlockchainEvents<Block> + StorageProvider<Block, sc_service::TFullBackend<Block>>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BlockBuilder<Block>,
-	P: TransactionPool + 'static,
+	C::Api: AccountApi<Block>,
+	C::Api: PaymentApi<Block>,
+	C::Api: DryRunApi<Block> + ApiExt<Block, StateBackend = <C as CallApiAt<Block>>::StateBackend>,
+	P: TransactionPool<Block = Block, Hash = Hash> + 'static,
 {
+	use self::{
+		account::{Account, AccountApiServer},
+		dry_run::{DryRun, DryRunApiServer},
+		events::{Events, EventsApiServer},
+		indexer::{Indexer, IndexerApiServer},
+		payment::{Payment, PaymentApiServer},
+		tx_status::{TxStatusApiServer, TxStatusRpc},
+		txpool::{TxPool, TxPoolApiServer},
+	};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
 	use substrate_frame_rpc_system::{System, SystemApiServer};
 
 	let mut module = RpcModule::new(());
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps { client, pool, deny_unsafe, subscription_executor, indexer, tx_status } = deps;
 
 	module.merge(System::new(client.clone(), pool.clone(), deny_unsafe).into_rpc())?;
-	module.merge(TransactionPayment::new(client).into_rpc())?;
+	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+	module.merge(Payment::new(client.clone()).into_rpc())?;
+	module.merge(Account::new(client.clone(), pool.clone()).into_rpc())?;
+	module.merge(TxPool::new(pool, deny_unsafe).into_rpc())?;
+	module.merge(DryRun::new(client.clone(), deny_unsafe).into_rpc())?;
+	module.merge(Events::new(client, subscription_executor.clone()).into_rpc())?;
+	if let Some(database) = indexer {
+		module.merge(Indexer::new(database).into_rpc())?;
+	}
+	if let Some(store) = tx_status {
+		module.merge(TxStatusRpc::new(store, subscription_executor).into_rpc())?;
+	}
 
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
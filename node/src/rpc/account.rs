@@ -0,0 +1,202 @@
+//! The `account_*` RPC methods, which summarize the balances and nonces of accounts.
+
+use codec::Encode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorCode, ErrorObject},
+};
+use node_template_runtime::{
+	apis::{
+		AccountApi as AccountRuntimeApi, AccountSummary as RuntimeSummary,
+		ExistentialDepositWarning,
+	},
+	opaque::Block,
+	AccountId, Balance, Hash, Index,
+};
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::generic::BlockId;
+use std::{collections::HashSet, sync::Arc};
+
+/// The most accounts a single batch may ask for.
+const MAX_BATCH: usize = 1000;
+
+/// The error code of failed runtime calls.
+const RUNTIME_ERROR: i32 = 1;
+
+/// The balances and nonce of an account.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSummary {
+	/// The balance that can be spent, unless it is frozen.
+	pub free: Balance,
+	/// The balance held by pallets, which can't be spent.
+	pub reserved: Balance,
+	/// The part of the free balance that can't be transferred.
+	pub frozen: Balance,
+	/// The locks freezing parts of the free balance.
+	pub locks: Vec<Lock>,
+	/// The named parts of the reserved balance.
+	pub reserves: Vec<Reserve>,
+	/// The nonce of the next transaction, after those ready in the node's transaction pool.
+	pub nonce: Index,
+	/// The least total balance an account must hold to exist.
+	pub existential_deposit: Balance,
+	/// `notExisting` or `freeBelowExistentialDeposit`, if either applies.
+	pub warning: Option<String>,
+}
+
+/// A balance lock.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lock {
+	/// The identifier of the lock, eight bytes.
+	pub id: Bytes,
+	/// The amount of the free balance the lock freezes.
+	pub amount: Balance,
+	/// What the locked amount can't be spent on: `Fee`, `Misc` or `All`.
+	pub reasons: String,
+}
+
+/// A named reserve.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reserve {
+	/// The identifier of the reserve, eight bytes.
+	pub id: Bytes,
+	/// The reserved amount.
+	pub amount: Balance,
+}
+
+/// Summaries of accounts.
+#[rpc(client, server)]
+pub trait AccountApi {
+	/// The summary of `account` at the block `at`, the best block by default.
+	#[method(name = "account_summary")]
+	fn summary(&self, account: AccountId, at: Option<Hash>) -> RpcResult<AccountSummary>;
+
+	/// The summaries of `accounts` at the block `at`, the best block by default, in the same
+	/// order.
+	#[method(name = "account_summaries")]
+	fn summaries(
+		&self,
+		accounts: Vec<AccountId>,
+		at: Option<Hash>,
+	) -> RpcResult<Vec<AccountSummary>>;
+}
+
+/// Provides the `account_*` RPC methods.
+pub struct Account<C, P> {
+	client: Arc<C>,
+	pool: Arc<P>,
+}
+
+impl<C, P> Account<C, P> {
+	/// Creates a new instance of the Account Rpc helper.
+	pub fn new(client: Arc<C>, pool: Arc<P>) -> Self {
+		Self { client, pool }
+	}
+}
+
+impl<C, P> AccountApiServer for Account<C, P>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: AccountRuntimeApi<Block>,
+	P: TransactionPool + 'static,
+{
+	fn summary(&self, account: AccountId, at: Option<Hash>) -> RpcResult<AccountSummary> {
+		let at = BlockId::Hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let summary = self
+			.client
+			.runtime_api()
+			.account_summary(&at, account.clone())
+			.map_err(runtime_error)?;
+
+		Ok(self.summarize(&self.provided_tags(), account, summary))
+	}
+
+	fn summaries(
+		&self,
+		accounts: Vec<AccountId>,
+		at: Option<Hash>,
+	) -> RpcResult<Vec<AccountSummary>> {
+		if accounts.len() > MAX_BATCH {
+			return Err(CallError::Custom(ErrorObject::owned(
+				ErrorCode::InvalidParams.code(),
+				format!("{} accounts exceed the maximum of {}", accounts.len(), MAX_BATCH),
+				None::<()>,
+			))
+			.into())
+		}
+		let at = BlockId::Hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let summaries = self
+			.client
+			.runtime_api()
+			.account_summaries(&at, accounts.clone())
+			.map_err(runtime_error)?;
+
+		let provided = self.provided_tags();
+		Ok(accounts
+			.into_iter()
+			.zip(summaries)
+			.map(|(account, summary)| self.summarize(&provided, account, summary))
+			.collect())
+	}
+}
+
+impl<C, P: TransactionPool> Account<C, P> {
+	/// The tags provided by the transactions ready in the pool.
+	fn provided_tags(&self) -> HashSet<Vec<u8>> {
+		self.pool.ready().flat_map(|tx| tx.provides().to_vec()).collect()
+	}
+
+	/// Converts the runtime's summary, counting the ready transactions of `account` in its nonce.
+	fn summarize(
+		&self,
+		provided: &HashSet<Vec<u8>>,
+		account: AccountId,
+		summary: RuntimeSummary,
+	) -> AccountSummary {
+		// `CheckNonce` makes every transaction provide the tag `(sender, nonce)`.
+		let mut nonce = summary.nonce;
+		while provided.contains(&(&account, nonce).encode()) {
+			nonce += 1;
+		}
+
+		AccountSummary {
+			free: summary.free,
+			reserved: summary.reserved,
+			frozen: summary.frozen,
+			locks: summary
+				.locks
+				.into_iter()
+				.map(|lock| Lock {
+					id: lock.id.to_vec().into(),
+					amount: lock.amount,
+					reasons: format!("{:?}", lock.reasons),
+				})
+				.collect(),
+			reserves: summary
+				.reserves
+				.into_iter()
+				.map(|reserve| Reserve { id: reserve.id.to_vec().into(), amount: reserve.amount })
+				.collect(),
+			nonce,
+			existential_deposit: summary.existential_deposit,
+			warning: summary.warning.map(|warning| match warning {
+				ExistentialDepositWarning::NotExisting => "notExisting".into(),
+				ExistentialDepositWarning::FreeBelowExistentialDeposit =>
+					"freeBelowExistentialDeposit".into(),
+			}),
+		}
+	}
+}
+
+fn runtime_error(e: sp_api::ApiError) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(RUNTIME_ERROR, "Runtime call failed", Some(e.to_string())))
+		.into()
+}
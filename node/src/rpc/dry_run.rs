@@ -0,0 +1,141 @@
+//! The `dryRun_*` RPC methods, which apply calls and extrinsics without committing their effects.
+
+use crate::decode::{Decoder, Described};
+use codec::Decode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorCode, ErrorObject},
+};
+use node_template_runtime::{
+	apis::{DryRunApi as DryRunRuntimeApi, DryRunResult},
+	opaque::Block,
+	AccountId, Hash, RuntimeCall,
+};
+use sc_rpc_api::DenyUnsafe;
+use serde::{Deserialize, Serialize};
+use sp_api::{ApiExt, CallApiAt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT, ApplyExtrinsicResult};
+use std::sync::Arc;
+
+/// The error code of failed dry runs.
+const RUNTIME_ERROR: i32 = 1;
+
+/// The effects of a dry run.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunOutcome {
+	/// Whether the extrinsic was valid and, if so, the result of its dispatch.
+	pub result: ApplyExtrinsicResult,
+	/// The events the call emitted.
+	pub events: Vec<Described>,
+	/// The actual weight consumed, in picoseconds of execution time.
+	pub weight: u64,
+	/// The keys of the top-level storage the call wrote to, in order.
+	pub touched_keys: Vec<Bytes>,
+}
+
+/// Dry runs of calls and extrinsics.
+///
+/// These are unsafe methods, as they execute arbitrary calls with any origin.
+#[rpc(client, server)]
+pub trait DryRunApi {
+	/// Dispatches the SCALE encoded `call` on top of the block `at`, the best block by default,
+	/// from the signed `origin`, or from Root if none is given.
+	#[method(name = "dryRun_call")]
+	fn call(
+		&self,
+		call: Bytes,
+		origin: Option<AccountId>,
+		at: Option<Hash>,
+	) -> RpcResult<DryRunOutcome>;
+
+	/// Applies the SCALE encoded signed `extrinsic` on top of the block `at`, the best block by
+	/// default.
+	#[method(name = "dryRun_extrinsic")]
+	fn extrinsic(&self, extrinsic: Bytes, at: Option<Hash>) -> RpcResult<DryRunOutcome>;
+}
+
+/// Provides the `dryRun_*` RPC methods.
+pub struct DryRun<C> {
+	client: Arc<C>,
+	deny_unsafe: DenyUnsafe,
+	decoder: Decoder,
+}
+
+impl<C> DryRun<C> {
+	/// Creates a new instance of the DryRun Rpc helper.
+	pub fn new(client: Arc<C>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { client, deny_unsafe, decoder: Decoder::default() }
+	}
+}
+
+impl<C> DryRun<C>
+where
+	C: ProvideRuntimeApi<Block> + CallApiAt<Block> + HeaderBackend<Block>,
+	C::Api: DryRunRuntimeApi<Block>
+		+ ApiExt<Block, StateBackend = <C as CallApiAt<Block>>::StateBackend>,
+{
+	/// Runs `dry_run` on top of the block `at`, and collects the keys it wrote before the changes
+	/// are dropped along with the runtime API instance.
+	fn dry_run(
+		&self,
+		at: Option<Hash>,
+		dry_run: impl FnOnce(&C::Api, &BlockId<Block>) -> Result<DryRunResult, sp_api::ApiError>,
+	) -> RpcResult<DryRunOutcome> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let api = self.client.runtime_api();
+		let result = dry_run(&api, &BlockId::Hash(at)).map_err(runtime_error)?;
+		let state = self.client.state_at(&BlockId::Hash(at)).map_err(runtime_error)?;
+		let changes = api.into_storage_changes(&state, at).map_err(runtime_error)?;
+
+		Ok(DryRunOutcome {
+			result: result.result,
+			events: result.events.iter().map(|event| self.decoder.event(event)).collect(),
+			weight: result.weight.ref_time(),
+			touched_keys: changes
+				.main_storage_changes
+				.into_iter()
+				.map(|(key, _)| key.into())
+				.collect(),
+		})
+	}
+}
+
+impl<C> DryRunApiServer for DryRun<C>
+where
+	C: ProvideRuntimeApi<Block> + CallApiAt<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DryRunRuntimeApi<Block>
+		+ ApiExt<Block, StateBackend = <C as CallApiAt<Block>>::StateBackend>,
+{
+	fn call(
+		&self,
+		call: Bytes,
+		origin: Option<AccountId>,
+		at: Option<Hash>,
+	) -> RpcResult<DryRunOutcome> {
+		self.deny_unsafe.check_if_safe()?;
+		let call = RuntimeCall::decode(&mut &call[..])
+			.map_err(|e| invalid_params(format!("Invalid call: {}", e)))?;
+		self.dry_run(at, |api, at| api.dry_run_call(at, origin, call))
+	}
+
+	fn extrinsic(&self, extrinsic: Bytes, at: Option<Hash>) -> RpcResult<DryRunOutcome> {
+		self.deny_unsafe.check_if_safe()?;
+		let extrinsic = <Block as BlockT>::Extrinsic::decode(&mut &extrinsic[..])
+			.map_err(|e| invalid_params(format!("Invalid extrinsic: {}", e)))?;
+		self.dry_run(at, |api, at| api.dry_run_extrinsic(at, extrinsic))
+	}
+}
+
+fn invalid_params(message: String) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(ErrorCode::InvalidParams.code(), message, None::<()>))
+		.into()
+}
+
+fn runtime_error(e: impl std::fmt::Display) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(RUNTIME_ERROR, "Dry run failed", Some(e.to_string())))
+		.into()
+}
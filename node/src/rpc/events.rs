@@ -0,0 +1,237 @@
+//! The `events_subscribe` RPC subscription, which streams decoded events as blocks are imported
+//! and finalized.
+
+use crate::decode::{Decoder, Described};
+use codec::Decode;
+use frame_system::{EventRecord, Phase};
+use futures::{future, stream, FutureExt, StreamExt};
+use jsonrpsee::{proc_macros::rpc, types::SubscriptionResult, SubscriptionSink};
+use node_template_runtime::{opaque::Block, AccountId, BlockNumber, Hash, RuntimeEvent};
+use sc_client_api::{BlockchainEvents, StorageProvider};
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, storage::StorageKey, twox_128};
+use std::sync::Arc;
+
+const LOG_TARGET: &str = "rpc::events";
+
+/// Which events a subscription receives. Every given field must match.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFilter {
+	/// The pallet that emitted the event, e.g. `TemplateModule`.
+	pub pallet: Option<String>,
+	/// The event variant, e.g. `SomethingStored`.
+	pub name: Option<String>,
+	/// An account any of the event's fields refers to.
+	pub account: Option<AccountId>,
+}
+
+/// A decoded event, sent once when its block becomes the best block and again when it is
+/// finalized. If a reorg takes the block out of the best chain before, the event is sent again
+/// as retracted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventNotification {
+	/// The hash of the block that emitted the event.
+	pub block_hash: Hash,
+	/// The number of the block that emitted the event.
+	pub block_number: BlockNumber,
+	/// The index of the extrinsic that emitted the event, none for events emitted while
+	/// initializing or finalizing the block.
+	pub extrinsic_index: Option<u32>,
+	/// The event's pallet, variant and fields.
+	#[serde(flatten)]
+	pub event: Described,
+	/// Whether the block has been finalized.
+	pub finalized: bool,
+	/// Whether the block has left the best chain, so that the event is void.
+	pub retracted: bool,
+}
+
+/// Subscriptions to events.
+#[rpc(client, server)]
+pub trait EventsApi {
+	/// Streams the events matching `filter`, all of them if none is given.
+	#[subscription(
+		name = "events_subscribe" => "events_event",
+		unsubscribe = "events_unsubscribe",
+		item = EventNotification,
+	)]
+	fn subscribe(&self, filter: Option<EventFilter>);
+}
+
+/// Provides the `events_subscribe` RPC subscription.
+pub struct Events<C> {
+	client: Arc<C>,
+	executor: SubscriptionTaskExecutor,
+	decoder: Arc<Decoder>,
+}
+
+impl<C> Events<C> {
+	/// Creates a new instance of the Events Rpc helper.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+		Self { client, executor, decoder: Arc::new(Decoder::default()) }
+	}
+}
+
+impl<C> EventsApiServer for Events<C>
+where
+	C: BlockchainEvents<Block>
+		+ HeaderBackend<Block>
+		+ StorageProvider<Block, sc_service::TFullBackend<Block>>
+		+ Send
+		+ Sync
+		+ 'static,
+{
+	fn subscribe(
+		&self,
+		mut sink: SubscriptionSink,
+		filter: Option<EventFilter>,
+	) -> SubscriptionResult {
+		let filter = Filter::new(filter.unwrap_or_default());
+		// A new best block on another fork retracts the blocks of the old best chain, and enacts
+		// those leading up to it.
+		let best = self
+			.client
+			.import_notification_stream()
+			.filter(|notification| future::ready(notification.is_new_best))
+			.map(|notification| {
+				let mut blocks = Vec::new();
+				if let Some(route) = &notification.tree_route {
+					blocks.extend(route.retracted().iter().map(|b| (b.hash, Reported::Retracted)));
+					blocks.extend(route.enacted().iter().map(|b| (b.hash, Reported::Best)));
+				}
+				blocks.push((notification.hash, Reported::Best));
+				blocks
+			});
+		// The finality notification only names the last block, the others finalized along with
+		// it are on the tree route.
+		let finalized = self.client.finality_notification_stream().map(|notification| {
+			notification
+				.tree_route
+				.iter()
+				.chain([&notification.hash])
+				.map(|hash| (*hash, Reported::Finalized))
+				.collect::<Vec<_>>()
+		});
+
+		let (client, decoder) = (self.client.clone(), self.decoder.clone());
+		let notifications = stream::select(best, finalized).flat_map(move |blocks| {
+			let notifications = blocks
+				.into_iter()
+				.flat_map(|(hash, reported)| {
+					block_notifications(&*client, &decoder, &filter, hash, reported)
+				})
+				.collect::<Vec<_>>();
+			stream::iter(notifications)
+		});
+
+		let fut = async move {
+			sink.pipe_from_stream(notifications).await;
+		};
+		self.executor.spawn("events-rpc-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
+}
+
+/// Why the events of a block are sent.
+#[derive(Clone, Copy, PartialEq)]
+enum Reported {
+	Best,
+	Finalized,
+	Retracted,
+}
+
+/// An [`EventFilter`] prepared for matching decoded events.
+struct Filter {
+	pallet: Option<String>,
+	name: Option<String>,
+	account: Option<String>,
+}
+
+impl Filter {
+	fn new(filter: EventFilter) -> Self {
+		Self {
+			pallet: filter.pallet,
+			name: filter.name,
+			account: filter.account.map(|account| account.to_ss58check()),
+		}
+	}
+
+	fn matches(&self, event: &Described) -> bool {
+		self.pallet.as_ref().map_or(true, |pallet| *pallet == event.pallet) &&
+			self.name.as_ref().map_or(true, |name| *name == event.name) &&
+			self.account.as_ref().map_or(true, |account| mentions(&event.args, account))
+	}
+}
+
+/// Whether any field in `value` is the SS58 encoded `account`, the form [`Decoder`] renders
+/// accounts in.
+fn mentions(value: &Value, account: &str) -> bool {
+	match value {
+		Value::String(string) => string == account,
+		Value::Array(items) => items.iter().any(|item| mentions(item, account)),
+		Value::Object(fields) => fields.values().any(|field| mentions(field, account)),
+		_ => false,
+	}
+}
+
+/// The events of the block with the given hash that match `filter`.
+fn block_notifications<C>(
+	client: &C,
+	decoder: &Decoder,
+	filter: &Filter,
+	hash: Hash,
+	reported: Reported,
+) -> Vec<EventNotification>
+where
+	C: HeaderBackend<Block> + StorageProvider<Block, sc_service::TFullBackend<Block>>,
+{
+	let (block_number, records) = match read_events(client, hash) {
+		Ok(read) => read,
+		Err(e) => {
+			log::warn!(target: LOG_TARGET, "Skipping the events of block {}: {}", hash, e);
+			return Vec::new()
+		},
+	};
+
+	records
+		.into_iter()
+		.filter_map(|record| {
+			let event = decoder.event(&record.event);
+			filter.matches(&event).then(|| EventNotification {
+				block_hash: hash,
+				block_number,
+				extrinsic_index: match record.phase {
+					Phase::ApplyExtrinsic(index) => Some(index),
+					_ => None,
+				},
+				event,
+				finalized: reported == Reported::Finalized,
+				retracted: reported == Reported::Retracted,
+			})
+		})
+		.collect()
+}
+
+/// The number of the block with the given hash, and its events.
+fn read_events<C>(
+	client: &C,
+	hash: Hash,
+) -> Result<(BlockNumber, Vec<EventRecord<RuntimeEvent, Hash>>), String>
+where
+	C: HeaderBackend<Block> + StorageProvider<Block, sc_service::TFullBackend<Block>>,
+{
+	let number = client.number(hash).map_err(|e| e.to_string())?.ok_or("The block is unknown")?;
+	let key = StorageKey([twox_128(b"System"), twox_128(b"Events")].concat());
+	let records = match client.storage(&hash, &key).map_err(|e| e.to_string())? {
+		Some(events) =>
+			Decode::decode(&mut &events.0[..]).map_err(|e| format!("Invalid events: {}", e))?,
+		None => Vec::new(),
+	};
+
+	Ok((number, records))
+}
@@ -0,0 +1,131 @@
+//! The `indexer_*` RPC methods, which query the index built with `--indexer-db`.
+
+use crate::indexer::{Database, IndexedEvent, IndexedExtrinsic, IndexedTransfer, Status};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorCode, ErrorObject},
+};
+use node_template_runtime::AccountId;
+use sp_core::crypto::Ss58Codec;
+use std::sync::Arc;
+
+/// The number of rows returned if the request doesn't give a limit.
+const DEFAULT_LIMIT: u32 = 100;
+
+/// The most rows a single request may return.
+const MAX_LIMIT: u32 = 1000;
+
+/// The error code of failed queries.
+const QUERY_ERROR: i32 = 1;
+
+/// Queries of the chain index.
+#[rpc(client, server)]
+pub trait IndexerApi {
+	/// How far the index reaches.
+	#[method(name = "indexer_status")]
+	fn status(&self) -> RpcResult<Status>;
+
+	/// The latest extrinsics, only those signed by `signer` if given.
+	#[method(name = "indexer_extrinsics")]
+	fn extrinsics(
+		&self,
+		signer: Option<AccountId>,
+		limit: Option<u32>,
+	) -> RpcResult<Vec<IndexedExtrinsic>>;
+
+	/// The latest events of `pallet`, only those named `name` if given.
+	#[method(name = "indexer_events")]
+	fn events(
+		&self,
+		pallet: String,
+		name: Option<String>,
+		limit: Option<u32>,
+	) -> RpcResult<Vec<IndexedEvent>>;
+
+	/// The latest balance transfers from or to `account`.
+	#[method(name = "indexer_transfers")]
+	fn transfers(&self, account: AccountId, limit: Option<u32>) -> RpcResult<Vec<IndexedTransfer>>;
+
+	/// The latest `TemplateModule` events, only those concerning `who` if given.
+	#[method(name = "indexer_templateEvents")]
+	fn template_events(
+		&self,
+		who: Option<AccountId>,
+		limit: Option<u32>,
+	) -> RpcResult<Vec<IndexedEvent>>;
+}
+
+/// Provides the `indexer_*` RPC methods.
+pub struct Indexer {
+	database: Arc<Database>,
+}
+
+impl Indexer {
+	/// Creates a new instance of the Indexer Rpc helper.
+	pub fn new(database: Arc<Database>) -> Self {
+		Self { database }
+	}
+}
+
+impl IndexerApiServer for Indexer {
+	fn status(&self) -> RpcResult<Status> {
+		self.database.status().map_err(query_error)
+	}
+
+	fn extrinsics(
+		&self,
+		signer: Option<AccountId>,
+		limit: Option<u32>,
+	) -> RpcResult<Vec<IndexedExtrinsic>> {
+		let signer = signer.map(|signer| signer.to_ss58check());
+		self.database
+			.extrinsics(signer.as_deref(), limit_or_default(limit)?)
+			.map_err(query_error)
+	}
+
+	fn events(
+		&self,
+		pallet: String,
+		name: Option<String>,
+		limit: Option<u32>,
+	) -> RpcResult<Vec<IndexedEvent>> {
+		self.database
+			.events(&pallet, name.as_deref(), limit_or_default(limit)?)
+			.map_err(query_error)
+	}
+
+	fn transfers(&self, account: AccountId, limit: Option<u32>) -> RpcResult<Vec<IndexedTransfer>> {
+		self.database
+			.transfers(&account.to_ss58check(), limit_or_default(limit)?)
+			.map_err(query_error)
+	}
+
+	fn template_events(
+		&self,
+		who: Option<AccountId>,
+		limit: Option<u32>,
+	) -> RpcResult<Vec<IndexedEvent>> {
+		let who = who.map(|who| who.to_ss58check());
+		self.database
+			.template_events(who.as_deref(), limit_or_default(limit)?)
+			.map_err(query_error)
+	}
+}
+
+fn limit_or_default(limit: Option<u32>) -> RpcResult<u32> {
+	match limit.unwrap_or(DEFAULT_LIMIT) {
+		limit if limit <= MAX_LIMIT => Ok(limit),
+		limit => Err(CallError::Custom(ErrorObject::owned(
+			ErrorCode::InvalidParams.code(),
+			format!("Limit {} exceeds the maximum of {}", limit, MAX_LIMIT),
+			None::<()>,
+		))
+		.into()),
+	}
+}
+
+fn query_error(e: rusqlite::Error) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(QUERY_ERROR, "Index query failed", Some(e.to_string())))
+		.into()
+}
@@ -0,0 +1,679 @@
+//! Access control and rate limiting in front of the node's RPC methods.
+//!
+//! The middleware serves WebSocket connections on its own interfaces. It checks every call
+//! against the interface's method allow and deny lists and the token buckets of the caller's IP
+//! address and of the method, then hands it to the node's RPC module in-process. Subscriptions
+//! are counted over all interfaces.
+//!
+//! The methods don't check whether they are safe when called in-process, so the interfaces deny
+//! the node's unsafe methods unless `--rpc-methods` allows them, as the node's own servers do.
+
+use futures::{
+	channel::mpsc,
+	io::{BufReader, BufWriter},
+	stream::{self, AbortHandle, Abortable, BoxStream, SelectAll},
+	FutureExt, SinkExt, Stream, StreamExt,
+};
+use jsonrpsee::{
+	core::server::rpc_module::{MethodCallback, MethodKind},
+	RpcModule,
+};
+use prometheus_endpoint::{register, CounterVec, Opts, PrometheusError, Registry, U64};
+use sc_service::config::RpcMethods;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use soketto::handshake::{server::Response, Server};
+use sp_core::traits::SpawnNamed;
+use std::{
+	collections::{HashMap, HashSet},
+	net::{IpAddr, SocketAddr},
+	path::Path,
+	pin::Pin,
+	sync::{
+		atomic::{AtomicU64, AtomicUsize, Ordering},
+		Arc, Mutex,
+	},
+	task::{Context, Poll},
+	time::Instant,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+const LOG_TARGET: &str = "rpc::middleware";
+
+/// The error code of calls to methods the interface doesn't allow.
+pub const METHOD_NOT_ALLOWED: i32 = -32010;
+
+/// The error code of calls exceeding a rate limit.
+pub const RATE_LIMITED: i32 = -32011;
+
+/// The error code of subscriptions exceeding the maximum.
+pub const TOO_MANY_SUBSCRIPTIONS: i32 = -32012;
+
+/// The IP addresses whose buckets are tracked before idle ones are forgotten.
+const MAX_TRACKED_IPS: usize = 65_536;
+
+/// The largest message a client may send, in bytes.
+const MAX_MESSAGE_SIZE: usize = 10 * 1024 * 1024;
+
+/// The messages queued for a client before it is disconnected for not keeping up.
+const MAX_QUEUED_MESSAGES: usize = 1024;
+
+/// The node's unsafe methods, which are denied unless `--rpc-methods` allows them.
+pub const UNSAFE_METHODS: &[&str] = &[
+	"author_hasKey",
+	"author_hasSessionKeys",
+	"author_insertKey",
+	"author_removeExtrinsic",
+	"author_rotateKeys",
+	"babe_epochAuthorship",
+	"dryRun_call",
+	"dryRun_extrinsic",
+	"offchain_localStorageGet",
+	"offchain_localStorageSet",
+	"state_getPairs",
+	"state_queryStorage",
+	"state_traceBlock",
+	"system_addLogFilter",
+	"system_addReservedPeer",
+	"system_dryRun",
+	"system_dryRunAt",
+	"system_networkState",
+	"system_peers",
+	"system_removeReservedPeer",
+	"system_resetLogFilter",
+	"system_unstable_networkState",
+	"txpool_inspect",
+];
+
+/// The middleware's configuration file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MiddlewareConfig {
+	/// The most subscriptions open at once, over all interfaces.
+	#[serde(default = "default_max_subscriptions")]
+	pub max_subscriptions: usize,
+	/// The rate limit of every client IP address.
+	pub per_ip: Option<RateLimit>,
+	/// The rate limits of individual methods, shared by all clients.
+	#[serde(default)]
+	pub methods: HashMap<String, RateLimit>,
+	/// The interfaces to listen on.
+	pub interfaces: Vec<InterfaceConfig>,
+}
+
+fn default_max_subscriptions() -> usize {
+	1024
+}
+
+/// A token bucket, which holds up to `burst` calls and refills at `rate` calls per second.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+	/// The calls refilled per second.
+	pub rate: f64,
+	/// The calls admitted at once after idling.
+	pub burst: u32,
+}
+
+/// An interface to listen on, with the methods it serves.
+///
+/// Patterns are method names, or prefixes followed by `*` such as `chain_*`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InterfaceConfig {
+	/// The address to listen on.
+	pub listen: SocketAddr,
+	/// The methods the interface serves, all of them if not given.
+	pub allow: Option<Vec<String>>,
+	/// Methods the interface doesn't serve, even if allowed.
+	#[serde(default)]
+	pub deny: Vec<String>,
+}
+
+impl MiddlewareConfig {
+	/// Reads the configuration from the TOML file at `path`.
+	pub fn load(path: &Path) -> Result<Self, String> {
+		let content = std::fs::read_to_string(path)
+			.map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+		let config: Self = toml::from_str(&content)
+			.map_err(|e| format!("Invalid RPC middleware configuration: {}", e))?;
+
+		if config.interfaces.is_empty() {
+			return Err("The RPC middleware configuration has no interfaces".into())
+		}
+		for (name, limit) in config
+			.per_ip
+			.iter()
+			.map(|limit| ("per_ip", limit))
+			.chain(config.methods.iter().map(|(method, limit)| (method.as_str(), limit)))
+		{
+			if !(limit.rate > 0.0) || limit.burst == 0 {
+				return Err(format!(
+					"The rate limit of {} must have a positive rate and burst",
+					name
+				))
+			}
+		}
+
+		Ok(config)
+	}
+}
+
+/// Starts serving the interfaces of `config`, with the methods of `module`, and returns the
+/// addresses they listen on.
+///
+/// Unsafe methods are served as `rpc_methods` allows, with `Auto` only on loopback interfaces.
+pub fn start(
+	config: MiddlewareConfig,
+	module: Arc<RpcModule<()>>,
+	rpc_methods: RpcMethods,
+	registry: Option<&Registry>,
+	spawner: impl SpawnNamed + Clone + 'static,
+) -> Result<Vec<SocketAddr>, String> {
+	let metrics = registry
+		.map(Metrics::register)
+		.transpose()
+		.map_err(|e| format!("Error registering the RPC middleware metrics: {}", e))?;
+	let shared = Arc::new(Shared {
+		known_methods: module.method_names().collect(),
+		unsubscriptions: module
+			.method_names()
+			.filter(|method| {
+				matches!(
+					module.method(method).map(MethodCallback::inner),
+					Some(MethodKind::Unsubscription(_))
+				)
+			})
+			.collect(),
+		module,
+		per_ip: config.per_ip.map(|limit| (limit, Mutex::new(HashMap::new()))),
+		methods: config
+			.methods
+			.into_iter()
+			.map(|(method, limit)| (method, (limit, Mutex::new(TokenBucket::full(&limit)))))
+			.collect(),
+		max_subscriptions: config.max_subscriptions,
+		subscriptions: Arc::new(AtomicUsize::new(0)),
+		connections: AtomicU64::new(0),
+		metrics,
+	});
+
+	let mut addresses = Vec::new();
+	for interface in config.interfaces {
+		let listener = std::net::TcpListener::bind(interface.listen)
+			.and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+			.map_err(|e| format!("Error listening on {}: {}", interface.listen, e))?;
+		let address = listener.local_addr().map_err(|e| e.to_string())?;
+		log::info!(target: LOG_TARGET, "RPC middleware listening on {}", address);
+		addresses.push(address);
+
+		let deny_unsafe = match rpc_methods {
+			RpcMethods::Safe => true,
+			RpcMethods::Unsafe => false,
+			RpcMethods::Auto => !address.ip().is_loopback(),
+		};
+		let interface = Arc::new(Interface {
+			name: interface.listen.to_string(),
+			allow: interface.allow,
+			deny: interface.deny,
+			deny_unsafe,
+		});
+		let serve = accept(listener, interface, shared.clone(), spawner.clone());
+		spawner.spawn("rpc-middleware", Some("rpc"), serve.boxed());
+	}
+
+	Ok(addresses)
+}
+
+/// Accepts connections on the interface.
+async fn accept(
+	listener: std::net::TcpListener,
+	interface: Arc<Interface>,
+	shared: Arc<Shared>,
+	spawner: impl SpawnNamed,
+) {
+	let listener = match TcpListener::from_std(listener) {
+		Ok(listener) => listener,
+		Err(e) => {
+			log::error!(target: LOG_TARGET, "Error listening on {}: {}", interface.name, e);
+			return
+		},
+	};
+
+	loop {
+		match listener.accept().await {
+			Ok((socket, peer)) => {
+				let (interface, shared) = (interface.clone(), shared.clone());
+				let connection = Connection {
+					id: shared.connections.fetch_add(1, Ordering::Relaxed),
+					ip: peer.ip(),
+					subscriptions: Default::default(),
+				};
+				let task = async move {
+					if let Err(e) = serve(socket, &connection, &interface, &shared).await {
+						log::debug!(
+							target: LOG_TARGET,
+							"Connection #{} from {} failed: {}",
+							connection.id,
+							peer,
+							e
+						);
+					}
+				};
+				spawner.spawn("rpc-middleware-connection", Some("rpc"), task.boxed());
+			},
+			Err(e) => log::warn!(target: LOG_TARGET, "Error accepting a connection: {}", e),
+		}
+	}
+}
+
+/// Serves the WebSocket connection of a client.
+async fn serve(
+	socket: TcpStream,
+	connection: &Connection,
+	interface: &Interface,
+	shared: &Shared,
+) -> Result<(), String> {
+	let mut server = Server::new(BufReader::new(BufWriter::new(socket.compat())));
+	let key = server.receive_request().await.map_err(|e| e.to_string())?.key();
+	server
+		.send_response(&Response::Accept { key, protocol: None })
+		.await
+		.map_err(|e| e.to_string())?;
+	let mut builder = server.into_builder();
+	builder.set_max_message_size(MAX_MESSAGE_SIZE);
+	let (mut sender, mut receiver) = builder.finish();
+
+	let (mut outgoing, mut outgoing_rx) = mpsc::channel::<String>(MAX_QUEUED_MESSAGES);
+	let (new_subscriptions, mut new_subscriptions_rx) = mpsc::unbounded::<Subscription>();
+
+	let write = async {
+		while let Some(message) = outgoing_rx.next().await {
+			sender.send_text(&message).await.map_err(|e| e.to_string())?;
+			sender.flush().await.map_err(|e| e.to_string())?;
+		}
+		Ok::<_, String>(())
+	};
+
+	let forward = {
+		let mut outgoing = outgoing.clone();
+		async move {
+			let mut subscriptions = SelectAll::new();
+			loop {
+				futures::select! {
+					subscription = new_subscriptions_rx.next() => match subscription {
+						Some(subscription) => subscriptions.push(subscription),
+						None => return Ok(()),
+					},
+					notification = subscriptions.select_next_some() => {
+						// Waiting for a slow client would only queue the notifications elsewhere.
+						match outgoing.try_send(notification) {
+							Err(e) if e.is_full() =>
+								return Err("The client doesn't keep up with its notifications"
+									.to_string()),
+							_ => (),
+						}
+					},
+				}
+			}
+		}
+	};
+
+	let read = async {
+		let mut message = Vec::new();
+		loop {
+			message.clear();
+			if let Err(e) = receiver.receive_data(&mut message).await {
+				return Err::<(), _>(e.to_string())
+			}
+			let (response, subscriptions) = shared.handle(interface, connection, &message).await;
+			// The response with the subscription ID goes out before any notification.
+			let _ = outgoing.send(response).await;
+			for subscription in subscriptions {
+				let _ = new_subscriptions.unbounded_send(subscription);
+			}
+		}
+	};
+
+	// Ending the connection drops its subscriptions.
+	futures::select! {
+		result = write.fuse() => result,
+		result = read.fuse() => result,
+		result = forward.fuse() => result,
+	}
+}
+
+/// A client's connection.
+struct Connection {
+	/// Tells the connection apart in logs.
+	id: u64,
+	ip: IpAddr,
+	/// The subscriptions the connection opened, by ID.
+	subscriptions: Arc<Mutex<HashMap<String, AbortHandle>>>,
+}
+
+impl Connection {
+	/// Ends the subscription with the given ID, if the connection opened it.
+	fn unsubscribe(&self, subscription: &Value) -> bool {
+		let mut subscriptions =
+			self.subscriptions.lock().expect("the subscriptions are never poisoned; qed");
+		subscriptions
+			.remove(&subscription.to_string())
+			.map(|handle| handle.abort())
+			.is_some()
+	}
+}
+
+/// An interface's method rules.
+struct Interface {
+	name: String,
+	allow: Option<Vec<String>>,
+	deny: Vec<String>,
+	deny_unsafe: bool,
+}
+
+impl Interface {
+	fn allows(&self, method: &str) -> bool {
+		let matches = |pattern: &String| match pattern.strip_suffix('*') {
+			Some(prefix) => method.starts_with(prefix),
+			None => pattern == method,
+		};
+
+		self.allow.as_ref().map_or(true, |allow| allow.iter().any(matches)) &&
+			!self.deny.iter().any(matches) &&
+			!(self.deny_unsafe && UNSAFE_METHODS.contains(&method))
+	}
+}
+
+/// Why a call was rejected.
+#[derive(Clone, Copy)]
+enum Rejection {
+	MethodNotAllowed,
+	IpRateLimited,
+	MethodRateLimited,
+	TooManySubscriptions,
+}
+
+impl Rejection {
+	/// The label of the rejection in the metrics.
+	fn reason(self) -> &'static str {
+		match self {
+			Self::MethodNotAllowed => "not_allowed",
+			Self::IpRateLimited => "ip_rate_limit",
+			Self::MethodRateLimited => "method_rate_limit",
+			Self::TooManySubscriptions => "subscription_limit",
+		}
+	}
+
+	fn response(self, id: &Value, method: &str) -> String {
+		let (code, message) = match self {
+			Self::MethodNotAllowed =>
+				(METHOD_NOT_ALLOWED, format!("Method {} is not allowed on this interface", method)),
+			Self::IpRateLimited =>
+				(RATE_LIMITED, "Too many requests from this IP address, try again later".into()),
+			Self::MethodRateLimited =>
+				(RATE_LIMITED, format!("Too many requests to {}, try again later", method)),
+			Self::TooManySubscriptions =>
+				(TOO_MANY_SUBSCRIPTIONS, "The maximum number of subscriptions is reached".into()),
+		};
+
+		error_response(id, code, &message)
+	}
+}
+
+/// The state shared by all interfaces.
+struct Shared {
+	module: Arc<RpcModule<()>>,
+	/// The methods of the module, the only ones named in metrics.
+	known_methods: HashSet<&'static str>,
+	/// The methods that end subscriptions.
+	unsubscriptions: HashSet<&'static str>,
+	per_ip: Option<(RateLimit, Mutex<HashMap<IpAddr, TokenBucket>>)>,
+	methods: HashMap<String, (RateLimit, Mutex<TokenBucket>)>,
+	max_subscriptions: usize,
+	subscriptions: Arc<AtomicUsize>,
+	/// The connections accepted so far.
+	connections: AtomicU64,
+	metrics: Option<Metrics>,
+}
+
+impl Shared {
+	/// Handles a message with a call or a batch of calls. Returns the response and the
+	/// subscriptions the calls opened.
+	async fn handle(
+		&self,
+		interface: &Interface,
+		connection: &Connection,
+		message: &[u8],
+	) -> (String, Vec<Subscription>) {
+		match serde_json::from_slice(message) {
+			Ok(Value::Array(calls)) if !calls.is_empty() => {
+				let mut responses = Vec::new();
+				let mut subscriptions = Vec::new();
+				for call in calls {
+					let (response, subscription) = self.call(interface, connection, call).await;
+					responses.push(response);
+					subscriptions.extend(subscription);
+				}
+				(format!("[{}]", responses.join(",")), subscriptions)
+			},
+			Ok(call @ Value::Object(_)) => {
+				let (response, subscription) = self.call(interface, connection, call).await;
+				(response, subscription.into_iter().collect())
+			},
+			Ok(_) => (error_response(&Value::Null, -32600, "Invalid request"), Vec::new()),
+			Err(_) => (error_response(&Value::Null, -32700, "Parse error"), Vec::new()),
+		}
+	}
+
+	async fn call(
+		&self,
+		interface: &Interface,
+		connection: &Connection,
+		call: Value,
+	) -> (String, Option<Subscription>) {
+		let id = call.get("id").cloned().unwrap_or(Value::Null);
+		let method = call.get("method").and_then(Value::as_str).unwrap_or_default();
+
+		if let Err(rejection) = self.admit(interface, connection.ip, method) {
+			self.rejected(interface, method, rejection);
+			return (rejection.response(&id, method), None)
+		}
+
+		// In the module, the subscriptions of all connections look like those of one. So that a
+		// connection can only end its own, the middleware ends them by dropping their channel.
+		if self.unsubscriptions.contains(method) {
+			let subscription = call.get("params").and_then(|params| params.get(0));
+			let ended = subscription.map_or(false, |id| connection.unsubscribe(id));
+			return (json!({ "jsonrpc": "2.0", "result": ended, "id": id }).to_string(), None)
+		}
+
+		let (response, mut notifications) =
+			match self.module.raw_json_request(&call.to_string()).await {
+				Ok(result) => result,
+				Err(e) => return (error_response(&id, -32603, &e.to_string()), None),
+			};
+
+		// Only calls that opened a subscription keep the channel open for its notifications.
+		let first = match notifications.try_next() {
+			Ok(None) => return (response, None),
+			Ok(Some(notification)) => Some(notification),
+			Err(_) => None,
+		};
+		let subscription_id = serde_json::from_str::<Value>(&response)
+			.ok()
+			.and_then(|response| response.get("result").cloned())
+			.unwrap_or_default();
+		match Slot::take(&self.subscriptions, self.max_subscriptions) {
+			Some(slot) => {
+				let (notifications, handle) =
+					stream::abortable(stream::iter(first).chain(notifications).boxed());
+				let registration = Registration::new(connection, &subscription_id, handle);
+				let subscription =
+					Subscription { notifications, _slot: slot, _registration: registration };
+				(response, Some(subscription))
+			},
+			// Dropping the channel ends the subscription.
+			None => {
+				self.rejected(interface, method, Rejection::TooManySubscriptions);
+				(Rejection::TooManySubscriptions.response(&id, method), None)
+			},
+		}
+	}
+
+	/// Checks the interface's rules and takes a token from the rate limits.
+	fn admit(&self, interface: &Interface, ip: IpAddr, method: &str) -> Result<(), Rejection> {
+		if !interface.allows(method) {
+			return Err(Rejection::MethodNotAllowed)
+		}
+
+		let now = Instant::now();
+		if let Some((limit, buckets)) = &self.per_ip {
+			let mut buckets = buckets.lock().expect("the buckets are never poisoned; qed");
+			if buckets.len() >= MAX_TRACKED_IPS && !buckets.contains_key(&ip) {
+				buckets.retain(|_, bucket| !bucket.is_full(limit, now));
+			}
+			let bucket = buckets.entry(ip).or_insert_with(|| TokenBucket::full(limit));
+			if !bucket.take(limit, now) {
+				return Err(Rejection::IpRateLimited)
+			}
+		}
+		if let Some((limit, bucket)) = self.methods.get(method) {
+			let mut bucket = bucket.lock().expect("the buckets are never poisoned; qed");
+			if !bucket.take(limit, now) {
+				return Err(Rejection::MethodRateLimited)
+			}
+		}
+
+		Ok(())
+	}
+
+	fn rejected(&self, interface: &Interface, method: &str, rejection: Rejection) {
+		log::debug!(target: LOG_TARGET, "Rejected {} on {}: {}", method, interface.name, rejection.reason());
+		if let Some(metrics) = &self.metrics {
+			let method = if self.known_methods.contains(method) { method } else { "unknown" };
+			metrics
+				.rejected
+				.with_label_values(&[&interface.name, method, rejection.reason()])
+				.inc();
+		}
+	}
+}
+
+/// The calls a token bucket still admits.
+struct TokenBucket {
+	tokens: f64,
+	updated: Instant,
+}
+
+impl TokenBucket {
+	fn full(limit: &RateLimit) -> Self {
+		Self { tokens: limit.burst as f64, updated: Instant::now() }
+	}
+
+	fn refill(&mut self, limit: &RateLimit, now: Instant) {
+		let refilled = now.saturating_duration_since(self.updated).as_secs_f64() * limit.rate;
+		self.tokens = (self.tokens + refilled).min(limit.burst as f64);
+		self.updated = now;
+	}
+
+	fn is_full(&mut self, limit: &RateLimit, now: Instant) -> bool {
+		self.refill(limit, now);
+		self.tokens >= limit.burst as f64
+	}
+
+	fn take(&mut self, limit: &RateLimit, now: Instant) -> bool {
+		self.refill(limit, now);
+		if self.tokens < 1.0 {
+			return false
+		}
+		self.tokens -= 1.0;
+		true
+	}
+}
+
+/// The notifications of an open subscription.
+struct Subscription {
+	notifications: Abortable<BoxStream<'static, String>>,
+	_slot: Slot,
+	_registration: Registration,
+}
+
+impl Stream for Subscription {
+	type Item = String;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<String>> {
+		self.notifications.poll_next_unpin(cx)
+	}
+}
+
+/// Counts towards the maximum number of subscriptions while it exists.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+	fn take(count: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+		count
+			.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < max).then(|| n + 1))
+			.ok()
+			.map(|_| Self(count.clone()))
+	}
+}
+
+impl Drop for Slot {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
+/// Lets the connection end the subscription while it exists.
+struct Registration {
+	id: String,
+	subscriptions: Arc<Mutex<HashMap<String, AbortHandle>>>,
+}
+
+impl Registration {
+	fn new(connection: &Connection, id: &Value, handle: AbortHandle) -> Self {
+		let id = id.to_string();
+		connection
+			.subscriptions
+			.lock()
+			.expect("the subscriptions are never poisoned; qed")
+			.insert(id.clone(), handle);
+		Self { id, subscriptions: connection.subscriptions.clone() }
+	}
+}
+
+impl Drop for Registration {
+	fn drop(&mut self) {
+		self.subscriptions
+			.lock()
+			.expect("the subscriptions are never poisoned; qed")
+			.remove(&self.id);
+	}
+}
+
+struct Metrics {
+	rejected: CounterVec<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			rejected: register(
+				CounterVec::new(
+					Opts::new(
+						"rpc_middleware_rejected_total",
+						"RPC calls rejected by the middleware",
+					),
+					&["interface", "method", "reason"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+fn error_response(id: &Value, code: i32, message: &str) -> String {
+	json!({ "jsonrpc": "2.0", "error": { "code": code, "message": message }, "id": id }).to_string()
+}
@@ -0,0 +1,103 @@
+//! The `payment_queryPayer` RPC method, which tells who pays the fee of a transaction, next to the
+//! `payment_queryInfo` and `payment_queryFeeDetails` methods of `pallet_transaction_payment_rpc`.
+
+use codec::Decode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorCode, ErrorObject},
+};
+use node_template_runtime::{
+	apis::PaymentApi as PaymentRuntimeApi, extensions::Payer, opaque::Block, Hash,
+};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, Bytes};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::sync::Arc;
+
+/// The error code of failed runtime calls, as used by `pallet_transaction_payment_rpc`.
+const RUNTIME_ERROR: i32 = 1;
+
+/// Who pays the fee of a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PaidBy {
+	/// The sender of the transaction.
+	Sender,
+	/// A sponsor of the call.
+	Sponsor,
+	/// Nobody, as the transaction is unsigned or feeless.
+	Nobody,
+}
+
+/// The party paying the fee of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeePayer {
+	/// Who pays.
+	pub paid_by: PaidBy,
+	/// The SS58 address of the paying account, unless nobody pays.
+	pub payer: Option<String>,
+}
+
+impl From<Payer> for FeePayer {
+	fn from(payer: Payer) -> Self {
+		match payer {
+			Payer::Sender(account) =>
+				Self { paid_by: PaidBy::Sender, payer: Some(account.to_ss58check()) },
+			Payer::Sponsor(account) =>
+				Self { paid_by: PaidBy::Sponsor, payer: Some(account.to_ss58check()) },
+			Payer::Nobody => Self { paid_by: PaidBy::Nobody, payer: None },
+		}
+	}
+}
+
+/// Who pays the fees of transactions.
+#[rpc(client, server)]
+pub trait PaymentApi {
+	/// The party paying the fee of the encoded extrinsic at the block `at`, the best block by
+	/// default.
+	#[method(name = "payment_queryPayer")]
+	fn query_payer(&self, encoded_xt: Bytes, at: Option<Hash>) -> RpcResult<FeePayer>;
+}
+
+/// Provides the `payment_queryPayer` RPC method.
+pub struct Payment<C> {
+	client: Arc<C>,
+}
+
+impl<C> Payment<C> {
+	/// Creates a new instance of the Payment Rpc helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> PaymentApiServer for Payment<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: PaymentRuntimeApi<Block>,
+{
+	fn query_payer(&self, encoded_xt: Bytes, at: Option<Hash>) -> RpcResult<FeePayer> {
+		let at = BlockId::Hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let extrinsic =
+			<Block as BlockT>::Extrinsic::decode(&mut &encoded_xt[..]).map_err(|e| {
+				CallError::Custom(ErrorObject::owned(
+					ErrorCode::InvalidParams.code(),
+					"Unable to query the payer",
+					Some(e.to_string()),
+				))
+			})?;
+
+		let payer = self.client.runtime_api().payer(&at, extrinsic).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				RUNTIME_ERROR,
+				"Unable to query the payer",
+				Some(e.to_string()),
+			))
+		})?;
+		Ok(payer.into())
+	}
+}
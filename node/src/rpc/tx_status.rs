@@ -0,0 +1,100 @@
+//! The `tx_status` RPC method and subscription, which report what the node recorded about a
+//! transaction, also after the client disconnected.
+
+use crate::tx_status::{Store, TxStatus};
+use futures::{future, stream, FutureExt, StreamExt};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::{
+		error::{CallError, ErrorObject},
+		SubscriptionResult,
+	},
+	SubscriptionSink,
+};
+use node_template_runtime::Hash;
+use sc_rpc::SubscriptionTaskExecutor;
+use std::sync::Arc;
+
+const LOG_TARGET: &str = "rpc::tx_status";
+
+/// The error code of failed queries.
+const QUERY_ERROR: i32 = 1;
+
+/// Transaction statuses.
+#[rpc(client, server)]
+pub trait TxStatusApi {
+	/// The status of the transaction with the given hash, none if the node hasn't seen it.
+	#[method(name = "tx_status")]
+	fn status(&self, hash: Hash) -> RpcResult<Option<TxStatus>>;
+
+	/// Streams the status of the transaction with the given hash, starting with the current one
+	/// if the node has seen it. Ends once the transaction is finalized, dropped or invalid.
+	#[subscription(
+		name = "tx_subscribeStatus" => "tx_statusUpdate",
+		unsubscribe = "tx_unsubscribeStatus",
+		item = TxStatus,
+	)]
+	fn subscribe_status(&self, hash: Hash);
+}
+
+/// Provides the `tx_status` RPC method and subscription.
+pub struct TxStatusRpc {
+	store: Arc<Store>,
+	executor: SubscriptionTaskExecutor,
+}
+
+impl TxStatusRpc {
+	/// Creates a new instance of the TxStatus Rpc helper.
+	pub fn new(store: Arc<Store>, executor: SubscriptionTaskExecutor) -> Self {
+		Self { store, executor }
+	}
+}
+
+impl TxStatusApiServer for TxStatusRpc {
+	fn status(&self, hash: Hash) -> RpcResult<Option<TxStatus>> {
+		self.store.status(&hash).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				QUERY_ERROR,
+				"Transaction status query failed",
+				Some(e.to_string()),
+			))
+			.into()
+		})
+	}
+
+	fn subscribe_status(&self, mut sink: SubscriptionSink, hash: Hash) -> SubscriptionResult {
+		// Subscribe before reading the current status, so that no change is missed in between.
+		let updates = self.store.subscribe(hash);
+		let current = self.store.status(&hash).unwrap_or_else(|e| {
+			log::warn!(target: LOG_TARGET, "Error reading the status of {}: {}", hash, e);
+			None
+		});
+
+		let mut last = None;
+		let statuses = stream::iter(current)
+			.chain(updates)
+			// The update racing with the read of the current status may repeat it.
+			.filter(move |status| {
+				let repeated = last.as_ref() == Some(status);
+				last = Some(status.clone());
+				future::ready(!repeated)
+			})
+			.boxed();
+		// Ends right after the first final status.
+		let statuses = stream::unfold((statuses, false), |(mut statuses, ended)| async move {
+			if ended {
+				return None
+			}
+			let status = statuses.next().await?;
+			let ended = status.state.is_final();
+			Some((status, (statuses, ended)))
+		});
+
+		let fut = async move {
+			sink.pipe_from_stream(statuses.boxed()).await;
+		};
+		self.executor.spawn("tx-status-rpc-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
+}
@@ -0,0 +1,106 @@
+//! The `txpool_inspect` RPC method, which lists the transactions in the node's pool.
+
+use codec::{Decode, Encode};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use node_template_runtime::{opaque::Block, Hash, UncheckedExtrinsic};
+use sc_rpc_api::DenyUnsafe;
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
+use serde::{Deserialize, Serialize};
+use sp_core::{crypto::Ss58Codec, Bytes};
+use sp_runtime::MultiAddress;
+use std::sync::Arc;
+
+/// The transactions in the pool.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolInspection {
+	/// The transactions that can be included in the next block, best first.
+	pub ready: Vec<PooledTransaction>,
+	/// The transactions waiting for others, e.g. for those with lower nonces.
+	pub future: Vec<PooledTransaction>,
+}
+
+/// A transaction in the pool.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PooledTransaction {
+	/// The hash of the transaction.
+	pub hash: Hash,
+	/// The SS58 address of the sender, for signed transactions.
+	pub sender: Option<String>,
+	/// The priority the runtime assigned, higher is included first.
+	pub priority: u64,
+	/// The number of blocks the transaction stays valid for.
+	pub longevity: u64,
+	/// The tags of the transactions this one depends on.
+	pub requires: Vec<Bytes>,
+	/// The tags this transaction provides to others.
+	pub provides: Vec<Bytes>,
+	/// Whether the transaction is gossiped to other nodes.
+	pub propagate: bool,
+}
+
+impl PooledTransaction {
+	fn new<T: InPoolTransaction<Hash = Hash>>(transaction: &T) -> Self
+	where
+		T::Transaction: Encode,
+	{
+		let sender = UncheckedExtrinsic::decode(&mut &transaction.data().encode()[..])
+			.ok()
+			.and_then(|extrinsic| extrinsic.signature)
+			.map(|(address, _, _)| match address {
+				MultiAddress::Id(account) => account.to_ss58check(),
+				other => format!("{:?}", other),
+			});
+
+		Self {
+			hash: *transaction.hash(),
+			sender,
+			priority: *transaction.priority(),
+			longevity: *transaction.longevity(),
+			requires: transaction.requires().iter().cloned().map(Bytes).collect(),
+			provides: transaction.provides().iter().cloned().map(Bytes).collect(),
+			propagate: transaction.is_propagable(),
+		}
+	}
+}
+
+/// Inspection of the transaction pool.
+///
+/// This is an unsafe method, as it exposes every transaction the node holds.
+#[rpc(client, server)]
+pub trait TxPoolApi {
+	/// Lists the ready and future transactions in the pool.
+	#[method(name = "txpool_inspect")]
+	fn inspect(&self) -> RpcResult<PoolInspection>;
+}
+
+/// Provides the `txpool_inspect` RPC method.
+pub struct TxPool<P> {
+	pool: Arc<P>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<P> TxPool<P> {
+	/// Creates a new instance of the TxPool Rpc helper.
+	pub fn new(pool: Arc<P>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { pool, deny_unsafe }
+	}
+}
+
+impl<P> TxPoolApiServer for TxPool<P>
+where
+	P: TransactionPool<Block = Block, Hash = Hash> + 'static,
+{
+	fn inspect(&self) -> RpcResult<PoolInspection> {
+		self.deny_unsafe.check_if_safe()?;
+		Ok(PoolInspection {
+			ready: self
+				.pool
+				.ready()
+				.map(|transaction| PooledTransaction::new(&*transaction))
+				.collect(),
+			future: self.pool.futures().iter().map(PooledTransaction::new).collect(),
+		})
+	}
+}
@@ -1,15 +1,36 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
+use crate::{
+	chain_spec,
+	indexer::{self, IndexerConfig},
+	pow,
+	rpc::{
+		middleware::{self, MiddlewareConfig},
+		FullDeps,
+	},
+	tx_status,
+	warp_sync::CheckpointedWarpSync,
+};
+use codec::Encode;
+use futures::{future::BoxFuture, FutureExt};
+use jsonrpsee::RpcModule;
 use node_template_runtime::{self, opaque::Block, RuntimeApi};
 use sc_client_api::BlockBackend;
+#[cfg(not(feature = "babe"))]
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
+#[cfg(feature = "babe")]
+use sc_consensus_babe::SlotProportion;
 pub use sc_executor::NativeElseWasmExecutor;
 use sc_finality_grandpa::SharedVoterState;
 use sc_keystore::LocalKeystore;
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
+use sc_network::NetworkService;
+use sc_service::{error::Error as ServiceError, Configuration, RpcHandlers, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryWorker};
+#[cfg(not(feature = "babe"))]
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
-use std::{sync::Arc, time::Duration};
+use sp_core::H256;
+use sp_runtime::traits::Block as BlockT;
+use std::{future::Future, sync::Arc, time::Duration};
 
 // Our native executor instance.
 pub struct ExecutorDispatch;
@@ -31,30 +52,41 @@ impl sc_executor::NativeExecutionDispatch for ExecutorDispatch {
 	}
 }
 
-pub(crate) type FullClient =
+pub type FullClient =
 	sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispatch>>;
-type FullBackend = sc_service::TFullBackend<Block>;
+pub type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
-
-pub fn new_partial(
+pub type FullPool = sc_transaction_pool::FullPool<Block, FullClient>;
+type FullGrandpaBlockImport =
+	sc_finality_grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>;
+
+/// The block import that sits in front of GRANDPA's, shared by the import queue and the
+/// authoring task.
+#[cfg(not(feature = "babe"))]
+type FullBlockImport = FullGrandpaBlockImport;
+#[cfg(feature = "babe")]
+type FullBlockImport =
+	sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>;
+
+/// What the authoring task needs to know about the block production engine.
+#[cfg(not(feature = "babe"))]
+type ConsensusLink = sc_consensus_aura::SlotDuration;
+#[cfg(feature = "babe")]
+type ConsensusLink = sc_consensus_babe::BabeLink<Block>;
+
+/// Builds the components shared by every consensus engine, everything but the import queue.
+///
+/// Commands that only read the database can use these regardless of the consensus engine.
+pub fn new_partial_base(
 	config: &Configuration,
 ) -> Result<
 	sc_service::PartialComponents<
 		FullClient,
 		FullBackend,
 		FullSelectChain,
-		sc_consensus::DefaultImportQueue<Block, FullClient>,
-		sc_transaction_pool::FullPool<Block, FullClient>,
-		(
-			sc_finality_grandpa::GrandpaBlockImport<
-				FullBackend,
-				Block,
-				FullClient,
-				FullSelectChain,
-			>,
-			sc_finality_grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
-			Option<Telemetry>,
-		),
+		(),
+		FullPool,
+		Option<Telemetry>,
 	>,
 	ServiceError,
 > {
@@ -103,36 +135,121 @@ pub fn new_partial(
 		client.clone(),
 	);
 
-	let (grandpa_block_import, grandpa_link) = sc_finality_grandpa::block_import(
-		client.clone(),
-		&(client.clone() as Arc<_>),
-		select_chain.clone(),
-		telemetry.as_ref().map(|x| x.handle()),
-	)?;
+	Ok(sc_service::PartialComponents {
+		client,
+		backend,
+		task_manager,
+		import_queue: (),
+		keystore_container,
+		select_chain,
+		transaction_pool,
+		other: telemetry,
+	})
+}
 
-	let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+pub fn new_partial(
+	config: &Configuration,
+) -> Result<
+	sc_service::PartialComponents<
+		FullClient,
+		FullBackend,
+		FullSelectChain,
+		sc_consensus::DefaultImportQueue<Block, FullClient>,
+		FullPool,
+		(
+			FullBlockImport,
+			sc_finality_grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
+			ConsensusLink,
+			Option<Telemetry>,
+		),
+	>,
+	ServiceError,
+> {
+	let sc_service::PartialComponents {
+		client,
+		backend,
+		task_manager,
+		keystore_container,
+		select_chain,
+		transaction_pool,
+		other: telemetry,
+		..
+	} = new_partial_base(config)?;
 
-	let import_queue =
-		sc_consensus_aura::import_queue::<AuraPair, _, _, _, _, _>(ImportQueueParams {
-			block_import: grandpa_block_import.clone(),
-			justification_import: Some(Box::new(grandpa_block_import.clone())),
-			client: client.clone(),
-			create_inherent_data_providers: move |_, ()| async move {
+	let (grandpa_block_import, grandpa_link) =
+		sc_finality_grandpa::block_import_with_authority_set_hard_forks(
+			client.clone(),
+			&(client.clone() as Arc<_>),
+			select_chain.clone(),
+			chain_spec::grandpa_hard_forks(&*config.chain_spec),
+			telemetry.as_ref().map(|x| x.handle()),
+		)?;
+
+	#[cfg(not(feature = "babe"))]
+	let (block_import, consensus_link, import_queue) = {
+		let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+
+		let import_queue =
+			sc_consensus_aura::import_queue::<AuraPair, _, _, _, _, _>(ImportQueueParams {
+				block_import: grandpa_block_import.clone(),
+				justification_import: Some(Box::new(grandpa_block_import.clone())),
+				client: client.clone(),
+				create_inherent_data_providers: move |_, ()| async move {
+					let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+					let slot =
+						sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+							*timestamp,
+							slot_duration,
+						);
+
+					Ok((slot, timestamp))
+				},
+				spawner: &task_manager.spawn_essential_handle(),
+				registry: config.prometheus_registry(),
+				check_for_equivocation: Default::default(),
+				telemetry: telemetry.as_ref().map(|x| x.handle()),
+			})?;
+
+		(grandpa_block_import, slot_duration, import_queue)
+	};
+
+	#[cfg(feature = "babe")]
+	let (block_import, consensus_link, import_queue) = {
+		let justification_import = grandpa_block_import.clone();
+
+		let (block_import, babe_link) = sc_consensus_babe::block_import(
+			sc_consensus_babe::configuration(&*client)?,
+			grandpa_block_import,
+			client.clone(),
+		)?;
+
+		let slot_duration = babe_link.config().slot_duration();
+
+		let import_queue = sc_consensus_babe::import_queue(
+			babe_link.clone(),
+			block_import.clone(),
+			Some(Box::new(justification_import)),
+			client.clone(),
+			select_chain.clone(),
+			move |_, ()| async move {
 				let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
 
 				let slot =
-					sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+					sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
 						*timestamp,
 						slot_duration,
 					);
 
 				Ok((slot, timestamp))
 			},
-			spawner: &task_manager.spawn_essential_handle(),
-			registry: config.prometheus_registry(),
-			check_for_equivocation: Default::default(),
-			telemetry: telemetry.as_ref().map(|x| x.handle()),
-		})?;
+			&task_manager.spawn_essential_handle(),
+			config.prometheus_registry(),
+			telemetry.as_ref().map(|x| x.handle()),
+		)?;
+
+		(block_import, babe_link, import_queue)
+	};
 
 	Ok(sc_service::PartialComponents {
 		client,
@@ -142,7 +259,7 @@ pub fn new_partial(
 		keystore_container,
 		select_chain,
 		transaction_pool,
-		other: (grandpa_block_import, grandpa_link, telemetry),
+		other: (block_import, grandpa_link, consensus_link, telemetry),
 	})
 }
 
@@ -153,8 +270,139 @@ fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
 	Err("Remote Keystore not supported.")
 }
 
-/// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+/// Handles to the components of a running full node.
+pub struct NewFullBase {
+	/// The task manager of the node. Dropping it shuts the node down.
+	pub task_manager: TaskManager,
+	/// The client instance of the node.
+	pub client: Arc<FullClient>,
+	/// The transaction pool of the node.
+	pub transaction_pool: Arc<FullPool>,
+	/// The network service of the node.
+	pub network: Arc<NetworkService<Block, <Block as BlockT>::Hash>>,
+	/// The RPC handlers of the node, which can be queried in-process.
+	pub rpc_handlers: RpcHandlers,
+}
+
+/// Creates an RPC module to merge into the node's own, see [`FullNodeBuilder::with_rpc`].
+type RpcHook = Box<
+	dyn Fn(
+		FullDeps<FullClient, FullPool>,
+	) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>,
+>;
+
+/// Creates a background task from the node's handles, see [`FullNodeBuilder::with_task`].
+type TaskHook = Box<dyn FnOnce(&NewFullBase) -> BoxFuture<'static, ()>>;
+
+/// Builds a full node, optionally extended with more RPC methods and background tasks.
+///
+/// This is what [`new_full`] uses, but it hands out all the node's handles, which makes the node
+/// embeddable, e.g. in integration tests.
+pub struct FullNodeBuilder {
+	config: Configuration,
+	rpc_hooks: Vec<RpcHook>,
+	task_hooks: Vec<(&'static str, TaskHook)>,
+	indexer: Option<IndexerConfig>,
+	tx_status: bool,
+	rpc_middleware: Option<MiddlewareConfig>,
+}
+
+impl FullNodeBuilder {
+	/// Creates a new [`Self`] for the given configuration.
+	pub fn new(config: Configuration) -> Self {
+		Self {
+			config,
+			rpc_hooks: Vec::new(),
+			task_hooks: Vec::new(),
+			indexer: None,
+			tx_status: false,
+			rpc_middleware: None,
+		}
+	}
+
+	/// Merges the RPC module created by `hook` into the node's RPC server.
+	///
+	/// The hook is called for every RPC server the node starts.
+	pub fn with_rpc<F>(mut self, hook: F) -> Self
+	where
+		F: Fn(
+				FullDeps<FullClient, FullPool>,
+			) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
+			+ 'static,
+	{
+		self.rpc_hooks.push(Box::new(hook));
+		self
+	}
+
+	/// Spawns the task created by `hook` once the node has been started.
+	pub fn with_task<F, Fut>(mut self, name: &'static str, hook: F) -> Self
+	where
+		F: FnOnce(&NewFullBase) -> Fut + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		self.task_hooks.push((name, Box::new(move |base| hook(base).boxed())));
+		self
+	}
+
+	/// Indexes the chain into a SQLite database, which the `indexer_*` RPC methods query.
+	pub fn with_indexer(mut self, config: IndexerConfig) -> Self {
+		self.indexer = Some(config);
+		self
+	}
+
+	/// Records the statuses of transactions into a SQLite database in the chain's data directory,
+	/// which the `tx_*` RPC methods query.
+	pub fn with_tx_status(mut self) -> Self {
+		self.tx_status = true;
+		self
+	}
+
+	/// Serves the node's RPC methods on the middleware's interfaces as well, with their access
+	/// lists and rate limits.
+	pub fn with_rpc_middleware(mut self, config: MiddlewareConfig) -> Self {
+		self.rpc_middleware = Some(config);
+		self
+	}
+
+	/// Builds and starts the node.
+	pub fn build(self) -> Result<NewFullBase, ServiceError> {
+		new_full_base(self)
+	}
+}
+
+/// Builds a new service for a full client, which keeps a chain index if `indexer` is given,
+/// records transaction statuses if `tx_status` is set and serves the RPC middleware if
+/// `rpc_middleware` is given.
+pub fn new_full(
+	config: Configuration,
+	indexer: Option<IndexerConfig>,
+	tx_status: bool,
+	rpc_middleware: Option<MiddlewareConfig>,
+) -> Result<TaskManager, ServiceError> {
+	let mut builder = FullNodeBuilder::new(config);
+	if let Some(indexer) = indexer {
+		builder = builder.with_indexer(indexer);
+	}
+	if tx_status {
+		builder = builder.with_tx_status();
+	}
+	if let Some(rpc_middleware) = rpc_middleware {
+		builder = builder.with_rpc_middleware(rpc_middleware);
+	}
+
+	builder.build().map(|NewFullBase { task_manager, .. }| task_manager)
+}
+
+fn new_full_base(
+	FullNodeBuilder {
+		mut config,
+		rpc_hooks,
+		task_hooks,
+		indexer,
+		tx_status,
+		rpc_middleware,
+	}: FullNodeBuilder,
+) -> Result<NewFullBase, ServiceError> {
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -163,7 +411,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		mut keystore_container,
 		select_chain,
 		transaction_pool,
-		other: (block_import, grandpa_link, mut telemetry),
+		other: (block_import, grandpa_link, consensus_link, mut telemetry),
 	} = new_partial(&config)?;
 
 	if let Some(url) = &config.keystore_remote {
@@ -181,14 +429,45 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		&config.chain_spec,
 	);
 
+	let indexer = indexer
+		.map(|IndexerConfig { path, backfill }| match indexer::Database::open(&path) {
+			Ok(database) => Ok((Arc::new(database), backfill)),
+			Err(e) => Err(ServiceError::Other(format!(
+				"Error opening the index {}: {}",
+				path.display(),
+				e
+			))),
+		})
+		.transpose()?;
+
+	let tx_status = tx_status
+		.then(|| {
+			let path = config.data_path.join(tx_status::DATABASE_FILE);
+			std::fs::create_dir_all(&config.data_path)
+				.map_err(|e| e.to_string())
+				.and_then(|()| tx_status::Store::open(&path).map_err(|e| e.to_string()))
+				.map(Arc::new)
+				.map_err(|e| {
+					ServiceError::Other(format!(
+						"Error opening the transaction statuses {}: {}",
+						path.display(),
+						e
+					))
+				})
+		})
+		.transpose()?;
+
 	config
 		.network
 		.extra_sets
 		.push(sc_finality_grandpa::grandpa_peers_set_config(grandpa_protocol_name.clone()));
-	let warp_sync = Arc::new(sc_finality_grandpa::warp_proof::NetworkProvider::new(
-		backend.clone(),
-		grandpa_link.shared_authority_set().clone(),
-		Vec::default(),
+	let warp_sync = Arc::new(CheckpointedWarpSync::new(
+		sc_finality_grandpa::warp_proof::NetworkProvider::new(
+			backend.clone(),
+			grandpa_link.shared_authority_set().clone(),
+			chain_spec::grandpa_hard_forks(&*config.chain_spec),
+		),
+		chain_spec::checkpoint(&*config.chain_spec),
 	));
 
 	let (network, system_rpc_tx, tx_handler_controller, network_starter) =
@@ -217,19 +496,36 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let name = config.network.node_name.clone();
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
+	let rpc_methods = config.rpc_methods;
 
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
-
-		Box::new(move |deny_unsafe, _| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
-			crate::rpc::create_full(deps).map_err(Into::into)
+		let database = indexer.as_ref().map(|(database, _)| database.clone());
+		let tx_status = tx_status.clone();
+
+		Box::new(move |deny_unsafe, subscription_executor| -> Result<_, ServiceError> {
+			let deps = || FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				subscription_executor: subscription_executor.clone(),
+				indexer: database.clone(),
+				tx_status: tx_status.clone(),
+			};
+
+			let mut module = crate::rpc::create_full(deps())?;
+			for hook in &rpc_hooks {
+				module
+					.merge(hook(deps())?)
+					.map_err(|e| ServiceError::Application(Box::new(e)))?;
+			}
+
+			Ok(module)
 		})
 	};
 
-	let _rpc_handlers = sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+	let rpc_handlers = sc_service::spawn_tasks(sc_service::SpawnTasksParams {
 		network: network.clone(),
 		client: client.clone(),
 		keystore: keystore_container.sync_keystore(),
@@ -243,21 +539,34 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		telemetry: telemetry.as_mut(),
 	})?;
 
+	if let Some(rpc_middleware) = rpc_middleware {
+		middleware::start(
+			rpc_middleware,
+			rpc_handlers.handle(),
+			rpc_methods,
+			prometheus_registry.as_ref(),
+			task_manager.spawn_handle(),
+		)
+		.map_err(ServiceError::Other)?;
+	}
+
 	if role.is_authority() {
 		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
 			client.clone(),
-			transaction_pool,
+			transaction_pool.clone(),
 			prometheus_registry.as_ref(),
 			telemetry.as_ref().map(|x| x.handle()),
 		);
 
-		let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+		#[cfg(not(feature = "babe"))]
+		let slot_duration = consensus_link;
 
+		#[cfg(not(feature = "babe"))]
 		let aura = sc_consensus_aura::start_aura::<AuraPair, _, _, _, _, _, _, _, _, _, _>(
 			StartAuraParams {
 				slot_duration,
-				client,
+				client: client.clone(),
 				select_chain,
 				block_import,
 				proposer_factory,
@@ -285,9 +594,51 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 
 		// the AURA authoring task is considered essential, i.e. if it
 		// fails we take down the service with it.
+		#[cfg(not(feature = "babe"))]
 		task_manager
 			.spawn_essential_handle()
 			.spawn_blocking("aura", Some("block-authoring"), aura);
+
+		#[cfg(feature = "babe")]
+		let babe = {
+			let slot_duration = consensus_link.config().slot_duration();
+
+			sc_consensus_babe::start_babe(sc_consensus_babe::BabeParams {
+				keystore: keystore_container.sync_keystore(),
+				client: client.clone(),
+				select_chain,
+				env: proposer_factory,
+				block_import,
+				sync_oracle: network.clone(),
+				justification_sync_link: network.clone(),
+				create_inherent_data_providers: move |_, ()| async move {
+					let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+					let slot =
+						sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+							*timestamp,
+							slot_duration,
+						);
+
+					Ok((slot, timestamp))
+				},
+				force_authoring,
+				backoff_authoring_blocks,
+				babe_link: consensus_link,
+				block_proposal_slot_portion: SlotProportion::new(0.5),
+				max_block_proposal_slot_portion: None,
+				telemetry: telemetry.as_ref().map(|x| x.handle()),
+			})?
+		};
+
+		// the BABE authoring task is considered essential, i.e. if it
+		// fails we take down the service with it.
+		#[cfg(feature = "babe")]
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"babe-proposer",
+			Some("block-authoring"),
+			babe,
+		);
 	}
 
 	if enable_grandpa {
@@ -317,7 +668,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		let grandpa_config = sc_finality_grandpa::GrandpaParams {
 			config: grandpa_config,
 			link: grandpa_link,
-			network,
+			network: network.clone(),
 			voting_rule: sc_finality_grandpa::VotingRulesBuilder::default().build(),
 			prometheus_registry,
 			shared_voter_state: SharedVoterState::empty(),
@@ -333,6 +684,254 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		);
 	}
 
+	if let Some(store) = tx_status {
+		task_manager.spawn_handle().spawn_blocking(
+			"tx-status",
+			None,
+			tx_status::run(client.clone(), transaction_pool.clone(), store),
+		);
+	}
+
+	if let Some((database, backfill)) = indexer {
+		// Indexing blocks on SQLite, so it gets a thread of its own.
+		task_manager.spawn_handle().spawn_blocking(
+			"indexer",
+			None,
+			indexer::run(client.clone(), database, backfill),
+		);
+	}
+
+	network_starter.start_network();
+
+	let base = NewFullBase { task_manager, client, transaction_pool, network, rpc_handlers };
+	for (name, hook) in task_hooks {
+		let task = hook(&base);
+		base.task_manager.spawn_handle().spawn(name, None, task);
+	}
+
+	Ok(base)
+}
+
+/// Wraps the client in a block import that checks the proof-of-work seal and the inherents.
+fn pow_block_import(
+	client: Arc<FullClient>,
+	select_chain: FullSelectChain,
+) -> sc_consensus::BoxBlockImport<Block, sp_api::TransactionFor<FullClient, Block>> {
+	Box::new(sc_consensus_pow::PowBlockImport::new(
+		client.clone(),
+		client.clone(),
+		pow::Sha3Algorithm::new(client),
+		// Check inherents right from genesis.
+		0,
+		select_chain,
+		move |_, ()| async move { Ok(sp_timestamp::InherentDataProvider::from_system_time()) },
+	))
+}
+
+/// Like [`new_partial`], but for authority-less proof-of-work chains.
+pub fn new_partial_pow(
+	config: &Configuration,
+) -> Result<
+	sc_service::PartialComponents<
+		FullClient,
+		FullBackend,
+		FullSelectChain,
+		sc_consensus::DefaultImportQueue<Block, FullClient>,
+		FullPool,
+		Option<Telemetry>,
+	>,
+	ServiceError,
+> {
+	let sc_service::PartialComponents {
+		client,
+		backend,
+		task_manager,
+		keystore_container,
+		select_chain,
+		transaction_pool,
+		other: telemetry,
+		..
+	} = new_partial_base(config)?;
+
+	let import_queue = sc_consensus_pow::import_queue(
+		pow_block_import(client.clone(), select_chain.clone()),
+		None,
+		pow::Sha3Algorithm::new(client.clone()),
+		&task_manager.spawn_essential_handle(),
+		config.prometheus_registry(),
+	)?;
+
+	Ok(sc_service::PartialComponents {
+		client,
+		backend,
+		task_manager,
+		import_queue,
+		keystore_container,
+		select_chain,
+		transaction_pool,
+		other: telemetry,
+	})
+}
+
+/// Builds a new service for a full client of a proof-of-work chain.
+///
+/// Blocks are only mined if `mining_threads` is set, with that many threads. There is no
+/// finality gadget: the heaviest chain wins.
+pub fn new_full_pow(
+	config: Configuration,
+	mining_threads: Option<usize>,
+	rpc_middleware: Option<MiddlewareConfig>,
+) -> Result<TaskManager, ServiceError> {
+	let sc_service::PartialComponents {
+		client,
+		backend,
+		mut task_manager,
+		import_queue,
+		keystore_container,
+		select_chain,
+		transaction_pool,
+		other: mut telemetry,
+	} = new_partial_pow(&config)?;
+
+	let (network, system_rpc_tx, tx_handler_controller, network_starter) =
+		sc_service::build_network(sc_service::BuildNetworkParams {
+			config: &config,
+			client: client.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			import_queue,
+			block_announce_validator_builder: None,
+			warp_sync: None,
+		})?;
+
+	if config.offchain_worker.enabled {
+		sc_service::build_offchain_workers(
+			&config,
+			task_manager.spawn_handle(),
+			client.clone(),
+			network.clone(),
+		);
+	}
+
+	let prometheus_registry = config.prometheus_registry().cloned();
+	let rpc_methods = config.rpc_methods;
+
+	let rpc_extensions_builder = {
+		let client = client.clone();
+		let pool = transaction_pool.clone();
+
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				subscription_executor,
+				// Both need finality, which `command::run` refuses them for.
+				indexer: None,
+				tx_status: None,
+			};
+			crate::rpc::create_full(deps).map_err(Into::into)
+		})
+	};
+
+	let rpc_handlers = sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+		network: network.clone(),
+		client: client.clone(),
+		keystore: keystore_container.sync_keystore(),
+		task_manager: &mut task_manager,
+		transaction_pool: transaction_pool.clone(),
+		rpc_builder: rpc_extensions_builder,
+		backend,
+		system_rpc_tx,
+		tx_handler_controller,
+		config,
+		telemetry: telemetry.as_mut(),
+	})?;
+
+	if let Some(rpc_middleware) = rpc_middleware {
+		middleware::start(
+			rpc_middleware,
+			rpc_handlers.handle(),
+			rpc_methods,
+			prometheus_registry.as_ref(),
+			task_manager.spawn_handle(),
+		)
+		.map_err(ServiceError::Other)?;
+	}
+
+	if let Some(threads) = mining_threads {
+		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool,
+			prometheus_registry.as_ref(),
+			telemetry.as_ref().map(|x| x.handle()),
+		);
+
+		let (worker, worker_task) = sc_consensus_pow::start_mining_worker(
+			pow_block_import(client.clone(), select_chain.clone()),
+			client.clone(),
+			select_chain,
+			pow::Sha3Algorithm::new(client),
+			proposer_factory,
+			network.clone(),
+			network,
+			None,
+			move |_, ()| async move { Ok(sp_timestamp::InherentDataProvider::from_system_time()) },
+			// Rebuild the block template at least this often, even if no block was imported.
+			Duration::from_secs(10),
+			// The time the proposer may spend filling a block.
+			Duration::from_secs(2),
+		);
+
+		// the mining worker builds the block templates and imports the sealed blocks, so it is
+		// considered essential.
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"pow",
+			Some("block-authoring"),
+			worker_task,
+		);
+
+		for index in 0..threads {
+			let worker = worker.clone();
+			// Give every thread its own part of the nonce space.
+			let mut nonce = (index as u64) << 48;
+
+			task_manager.spawn_handle().spawn_blocking(
+				"pow-miner",
+				Some("block-authoring"),
+				async move {
+					loop {
+						let metadata = match worker.metadata() {
+							Some(metadata) => metadata,
+							None => {
+								std::thread::sleep(Duration::from_millis(500));
+								continue
+							},
+						};
+						let version = worker.version();
+
+						// Stop as soon as the worker has a new template for us.
+						while worker.version() == version {
+							nonce = nonce.wrapping_add(1);
+							let seal = pow::Compute {
+								difficulty: metadata.difficulty,
+								pre_hash: metadata.pre_hash,
+								nonce: H256::from_low_u64_be(nonce),
+							}
+							.compute();
+
+							if pow::hash_meets_difficulty(&seal.work, seal.difficulty) {
+								worker.submit(seal.encode()).await;
+								break
+							}
+						}
+					}
+				},
+			);
+		}
+	}
+
 	network_starter.start_network();
 	Ok(task_manager)
 }
@@ -0,0 +1,419 @@
+//! The `snapshot` subcommands, which dump the state at a finalized block into a file and
+//! bootstrap a fresh database from it.
+//!
+//! A snapshot starts with [`MAGIC`], followed by frames: the [`SnapshotHeader`], the state in
+//! chunks, and an end marker counting them. Each frame is the length of its zstd compressed
+//! SCALE encoding as a little endian `u32`, the compressed bytes, and the BLAKE2-256 hash of the
+//! uncompressed encoding.
+
+use crate::{inspect, service::FullClient};
+use codec::{Decode, Encode};
+use node_template_runtime::{opaque::Block, Hash, Header};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, ImportParams, SharedParams};
+use sc_client_api::{BlockBackend, StorageProvider};
+use sc_consensus::{
+	BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult, ImportedState, StateAction,
+	StorageChanges,
+};
+use sp_api::{ProvideRuntimeApi, TransactionFor};
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_core::{
+	hashing::blake2_256,
+	storage::{well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX, ChildInfo},
+};
+use sp_finality_grandpa::{AuthorityList, GrandpaApi, SetId};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as _},
+	Justifications, OpaqueExtrinsic,
+};
+use sp_state_machine::{KeyValueStates, KeyValueStorageLevel};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs::{self, File},
+	io::{self, BufReader, BufWriter, Read, Write},
+	path::PathBuf,
+};
+
+/// Identifies snapshot files, and the version of their format.
+const MAGIC: &[u8; 8] = b"ntsnap\x00\x01";
+
+/// The zstd compression level of the frames.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Frames larger than this are rejected rather than allocated.
+const MAX_FRAME_SIZE: u32 = 1 << 30;
+
+/// The `snapshot` command.
+#[derive(Debug, clap::Subcommand)]
+pub enum SnapshotCmd {
+	/// Dump the state at a finalized block into a snapshot file.
+	Create(CreateCmd),
+
+	/// Bootstrap a fresh database from a snapshot file.
+	Restore(RestoreCmd),
+}
+
+/// The `snapshot create` command.
+#[derive(Debug, clap::Parser)]
+pub struct CreateCmd {
+	/// The snapshot file to write.
+	#[arg(value_name = "FILE")]
+	pub output: PathBuf,
+
+	/// Number or hash of the finalized block to snapshot. Defaults to the last finalized block.
+	#[arg(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// The uncompressed size of a chunk of state, in bytes.
+	#[arg(long, value_name = "BYTES", default_value_t = 16 << 20)]
+	pub chunk_size: usize,
+
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+/// The `snapshot restore` command.
+#[derive(Debug, clap::Parser)]
+pub struct RestoreCmd {
+	/// The snapshot file to read.
+	#[arg(value_name = "FILE")]
+	pub input: PathBuf,
+
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+/// The block a snapshot was taken at.
+#[derive(Debug, Encode, Decode)]
+pub struct SnapshotHeader {
+	/// Snapshots can only be restored into databases of the same chain.
+	pub genesis_hash: Hash,
+	pub header: Header,
+	pub body: Vec<OpaqueExtrinsic>,
+	pub justifications: Option<Justifications>,
+	/// The GRANDPA authority set in charge after the block.
+	pub grandpa_set_id: SetId,
+	pub grandpa_authorities: AuthorityList,
+}
+
+/// Storage entries of the top trie, or of the child trie with the given prefixed key.
+#[derive(Debug, Default, Encode, Decode)]
+pub struct Chunk {
+	pub child: Option<Vec<u8>>,
+	pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+#[derive(Debug, Encode, Decode)]
+enum Frame {
+	Header(SnapshotHeader),
+	Chunk(Chunk),
+	End { chunks: u32, entries: u64 },
+}
+
+impl CreateCmd {
+	/// Run the command.
+	pub fn run(&self, client: &FullClient) -> sc_cli::Result<()> {
+		let info = client.info();
+		let hash = match &self.at {
+			Some(at) => inspect::block_hash(client, at)?,
+			None => info.finalized_hash,
+		};
+		let id = BlockId::Hash(hash);
+		let header = client
+			.header(id)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Unknown block {}", hash))?;
+		let number = *header.number();
+		if number > info.finalized_number ||
+			client.hash(number).map_err(|e| e.to_string())? != Some(hash)
+		{
+			return Err(format!("Block #{} {} is not finalized", number, hash).into())
+		}
+
+		let block = client
+			.block(&id)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Block {} has no body", hash))?;
+		let runtime_api = client.runtime_api();
+		let snapshot_header = SnapshotHeader {
+			genesis_hash: info.genesis_hash,
+			header,
+			body: block.block.extrinsics().to_vec(),
+			justifications: block.justifications,
+			grandpa_set_id: runtime_api.current_set_id(&id).map_err(|e| e.to_string())?,
+			grandpa_authorities: runtime_api.grandpa_authorities(&id).map_err(|e| e.to_string())?,
+		};
+
+		// Written next to the output first, so that an interrupted run leaves no partial snapshot.
+		let tmp = self.output.with_extension("tmp");
+		let mut writer = ChunkWriter::new(BufWriter::new(File::create(&tmp)?), self.chunk_size);
+		writer.output.write_all(MAGIC)?;
+		write_frame(&mut writer.output, &Frame::Header(snapshot_header))?;
+
+		let mut children = Vec::new();
+		for key in client.storage_keys_iter(&hash, None, None).map_err(|e| e.to_string())? {
+			// Child tries are dumped on their own, their roots follow from their content.
+			if let Some(child) = key.0.strip_prefix(DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+				children.push(ChildInfo::new_default(child));
+				continue
+			}
+			let value = client.storage(&hash, &key).map_err(|e| e.to_string())?;
+			writer.push(key.0, value.map(|value| value.0).unwrap_or_default())?;
+		}
+		for child_info in children {
+			writer.start_child(child_info.prefixed_storage_key().into_inner())?;
+			for key in client
+				.child_storage_keys_iter(&hash, child_info.clone(), None, None)
+				.map_err(|e| e.to_string())?
+			{
+				let value =
+					client.child_storage(&hash, &child_info, &key).map_err(|e| e.to_string())?;
+				writer.push(key.0, value.map(|value| value.0).unwrap_or_default())?;
+			}
+		}
+		let (chunks, entries) = writer.finish()?;
+
+		fs::rename(tmp, &self.output)?;
+		println!(
+			"Wrote block #{} {:?} with {} storage entries in {} chunks to {}",
+			number,
+			hash,
+			entries,
+			chunks,
+			self.output.display()
+		);
+		Ok(())
+	}
+}
+
+impl RestoreCmd {
+	/// Run the command, importing the snapshot through `block_import`, so that the consensus
+	/// engines pick up their state from it.
+	pub fn run<BI>(&self, client: &FullClient, mut block_import: BI) -> sc_cli::Result<()>
+	where
+		BI: BlockImport<Block, Transaction = TransactionFor<FullClient, Block>>,
+		BI::Error: std::fmt::Display,
+	{
+		let info = client.info();
+		if info.best_number != 0 {
+			return Err(format!(
+				"Snapshots can only be restored into a fresh database, this one has blocks up to #{}",
+				info.best_number
+			)
+			.into())
+		}
+
+		let mut input = BufReader::new(File::open(&self.input)?);
+		let mut magic = [0; 8];
+		input.read_exact(&mut magic)?;
+		if &magic != MAGIC {
+			return Err(format!("{} is not a snapshot", self.input.display()).into())
+		}
+		let header = match read_frame(&mut input, 0)? {
+			Frame::Header(header) => header,
+			_ => return Err("The snapshot does not start with a header".into()),
+		};
+		if header.genesis_hash != info.genesis_hash {
+			return Err(format!(
+				"The snapshot is of a different chain, with genesis {:?}",
+				header.genesis_hash
+			)
+			.into())
+		}
+
+		let mut top = Vec::new();
+		let mut children = BTreeMap::<_, Vec<_>>::new();
+		let (mut chunks, mut entries) = (0u32, 0u64);
+		loop {
+			match read_frame(&mut input, chunks + 1)? {
+				Frame::Chunk(chunk) => {
+					chunks += 1;
+					entries += chunk.entries.len() as u64;
+					match chunk.child {
+						Some(child) => children.entry(child).or_default().extend(chunk.entries),
+						None => top.extend(chunk.entries),
+					}
+				},
+				Frame::End { chunks: expected_chunks, entries: expected_entries } => {
+					if (chunks, entries) != (expected_chunks, expected_entries) {
+						return Err(format!(
+							"The snapshot has {} chunks with {} entries, expected {} with {}",
+							chunks, entries, expected_chunks, expected_entries
+						)
+						.into())
+					}
+					break
+				},
+				Frame::Header(_) => return Err("The snapshot has a second header".into()),
+			}
+		}
+
+		// The client only distinguishes child tries from the top one by their parent keys and
+		// roots, which it recomputes anyway.
+		let mut levels = vec![KeyValueStorageLevel {
+			state_root: Vec::new(),
+			parent_storage_keys: Vec::new(),
+			key_values: top,
+		}];
+		levels.extend(children.into_iter().map(|(child, key_values)| KeyValueStorageLevel {
+			state_root: vec![0],
+			parent_storage_keys: vec![child],
+			key_values,
+		}));
+
+		let hash = header.header.hash();
+		let number = *header.header.number();
+		let mut params = BlockImportParams::new(BlockOrigin::File, header.header);
+		params.body = Some(header.body);
+		params.justifications = header.justifications;
+		params.state_action = StateAction::ApplyChanges(StorageChanges::Import(ImportedState {
+			block: hash,
+			state: KeyValueStates(levels),
+		}));
+		params.finalized = true;
+		params.fork_choice = Some(ForkChoiceStrategy::Custom(true));
+
+		match futures::executor::block_on(block_import.import_block(params, HashMap::new())) {
+			Ok(ImportResult::Imported(_)) => (),
+			Ok(result) => return Err(format!("Importing the snapshot failed: {:?}", result).into()),
+			Err(e) => return Err(format!("Importing the snapshot failed: {}", e).into()),
+		}
+
+		// GRANDPA took its authority set from the imported state, which must be the one the
+		// snapshot was taken with.
+		let id = BlockId::Hash(hash);
+		let runtime_api = client.runtime_api();
+		let set_id = runtime_api.current_set_id(&id).map_err(|e| e.to_string())?;
+		let authorities = runtime_api.grandpa_authorities(&id).map_err(|e| e.to_string())?;
+		if (set_id, &authorities) != (header.grandpa_set_id, &header.grandpa_authorities) {
+			return Err(format!(
+				"The state of block #{} has GRANDPA set {}, but the snapshot records set {}",
+				number, set_id, header.grandpa_set_id
+			)
+			.into())
+		}
+
+		println!(
+			"Restored block #{} {:?} with {} storage entries, GRANDPA set {}",
+			number, hash, entries, set_id
+		);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for CreateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+impl CliConfiguration for RestoreCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+/// Collects storage entries into chunks of roughly `chunk_size` bytes and writes them out.
+struct ChunkWriter<W> {
+	output: W,
+	chunk_size: usize,
+	chunk: Chunk,
+	size: usize,
+	chunks: u32,
+	entries: u64,
+}
+
+impl<W: Write> ChunkWriter<W> {
+	fn new(output: W, chunk_size: usize) -> Self {
+		Self { output, chunk_size, chunk: Chunk::default(), size: 0, chunks: 0, entries: 0 }
+	}
+
+	fn push(&mut self, key: Vec<u8>, value: Vec<u8>) -> io::Result<()> {
+		self.size += key.len() + value.len();
+		self.chunk.entries.push((key, value));
+		if self.size >= self.chunk_size {
+			self.flush()?;
+		}
+		Ok(())
+	}
+
+	/// Writes the pending entries, and puts the following ones into the given child trie.
+	fn start_child(&mut self, child: Vec<u8>) -> io::Result<()> {
+		self.flush()?;
+		self.chunk.child = Some(child);
+		Ok(())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		if self.chunk.entries.is_empty() {
+			return Ok(())
+		}
+		let child = self.chunk.child.clone();
+		let chunk = std::mem::replace(&mut self.chunk, Chunk { child, entries: Vec::new() });
+		self.chunks += 1;
+		self.entries += chunk.entries.len() as u64;
+		self.size = 0;
+		write_frame(&mut self.output, &Frame::Chunk(chunk))
+	}
+
+	/// Writes the end marker, returning the number of chunks and entries written.
+	fn finish(mut self) -> io::Result<(u32, u64)> {
+		self.flush()?;
+		write_frame(&mut self.output, &Frame::End { chunks: self.chunks, entries: self.entries })?;
+		self.output.flush()?;
+		Ok((self.chunks, self.entries))
+	}
+}
+
+fn write_frame(output: &mut impl Write, frame: &Frame) -> io::Result<()> {
+	let encoded = frame.encode();
+	let compressed = zstd::encode_all(&encoded[..], COMPRESSION_LEVEL)?;
+	output.write_all(&(compressed.len() as u32).to_le_bytes())?;
+	output.write_all(&compressed)?;
+	output.write_all(&blake2_256(&encoded))
+}
+
+/// Reads the next frame, the `index`th of the snapshot, verifying its checksum.
+fn read_frame(input: &mut impl Read, index: u32) -> Result<Frame, String> {
+	let read = |input: &mut dyn Read, buf: &mut [u8]| {
+		input.read_exact(buf).map_err(|e| match e.kind() {
+			io::ErrorKind::UnexpectedEof =>
+				format!("The snapshot is truncated in frame #{}", index),
+			_ => format!("Error reading frame #{}: {}", index, e),
+		})
+	};
+
+	let mut len = [0; 4];
+	read(input, &mut len)?;
+	let len = u32::from_le_bytes(len);
+	if len > MAX_FRAME_SIZE {
+		return Err(format!("Frame #{} is too large: {} bytes", index, len))
+	}
+	let mut compressed = vec![0; len as usize];
+	read(input, &mut compressed)?;
+	let mut checksum = [0; 32];
+	read(input, &mut checksum)?;
+
+	let encoded = zstd::decode_all(&compressed[..])
+		.map_err(|e| format!("Frame #{} is corrupted: {}", index, e))?;
+	if blake2_256(&encoded) != checksum {
+		return Err(format!("Frame #{} is corrupted: checksum mismatch", index))
+	}
+	Frame::decode(&mut &encoded[..]).map_err(|e| format!("Frame #{} is invalid: {}", index, e))
+}
@@ -0,0 +1,106 @@
+//! Utilities to run a development node in-process, e.g. from integration tests.
+
+use crate::{
+	cli::Cli,
+	service::{FullClient, FullNodeBuilder, NewFullBase},
+};
+use futures::StreamExt;
+use node_template_runtime::BlockNumber;
+use sc_cli::SubstrateCli;
+use sc_client_api::BlockchainEvents;
+use sc_service::{Configuration, RpcHandlers};
+use serde_json::Value;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Header;
+use std::time::Duration;
+
+/// How long the `wait_for_*` functions wait for the node to make progress before panicking.
+pub const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Creates the configuration of a `--dev` node with temporary storage and random ports.
+///
+/// The node's tasks are spawned on the runtime behind `tokio_handle`.
+pub fn dev_config(tokio_handle: tokio::runtime::Handle) -> sc_cli::Result<Configuration> {
+	let cli = Cli::from_iter([
+		"node-template",
+		"--dev",
+		"--tmp",
+		"--port",
+		"0",
+		"--rpc-port",
+		"0",
+		"--ws-port",
+		"0",
+		"--no-prometheus",
+		"--no-telemetry",
+	]);
+
+	cli.create_configuration(&cli.run, tokio_handle)
+}
+
+/// Starts a development node, see [`dev_config`].
+///
+/// Use [`FullNodeBuilder`] with [`dev_config`] directly to extend the node.
+pub fn start_dev_node(tokio_handle: tokio::runtime::Handle) -> sc_cli::Result<NewFullBase> {
+	FullNodeBuilder::new(dev_config(tokio_handle)?)
+		.build()
+		.map_err(sc_cli::Error::Service)
+}
+
+/// Waits until the node has imported `count` more blocks, and returns the best block number.
+///
+/// Panics if that takes longer than [`TIMEOUT`].
+pub async fn wait_for_blocks(client: &FullClient, count: usize) -> BlockNumber {
+	let mut imported = client.import_notification_stream().take(count);
+	tokio::time::timeout(TIMEOUT, async { while imported.next().await.is_some() {} })
+		.await
+		.expect("the node did not import blocks in time");
+
+	client.info().best_number
+}
+
+/// Waits until the node has finalized the block with the given number.
+///
+/// Panics if that takes longer than [`TIMEOUT`].
+pub async fn wait_for_finalized(client: &FullClient, number: BlockNumber) {
+	let mut finalized = client.finality_notification_stream();
+	tokio::time::timeout(TIMEOUT, async {
+		while client.info().finalized_number < number {
+			match finalized.next().await {
+				Some(notification) if *notification.header.number() >= number => return,
+				Some(_) => continue,
+				None => return,
+			}
+		}
+	})
+	.await
+	.expect("the node did not finalize blocks in time")
+}
+
+/// Calls `method` with `params` through the node's RPC handlers.
+///
+/// Returns the call's `result`, or its `error` as the error.
+pub async fn rpc_call(
+	rpc_handlers: &RpcHandlers,
+	method: &str,
+	params: Value,
+) -> Result<Value, Value> {
+	let request = serde_json::json!({
+		"jsonrpc": "2.0",
+		"id": 1,
+		"method": method,
+		"params": params,
+	});
+
+	let (response, _) = rpc_handlers
+		.rpc_query(&request.to_string())
+		.await
+		.map_err(|e| Value::String(e.to_string()))?;
+	let mut response: Value =
+		serde_json::from_str(&response).map_err(|e| Value::String(e.to_string()))?;
+
+	match response.get_mut("result") {
+		Some(result) => Ok(result.take()),
+		None => Err(response["error"].take()),
+	}
+}
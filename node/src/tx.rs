@@ -0,0 +1,384 @@
+//! Offline construction and signing of transactions, and the `tx` subcommand built on it.
+
+use codec::{DecodeAll, Encode};
+use jsonrpsee::{
+	core::client::ClientT,
+	rpc_params,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use node_template_runtime::{
+	self as runtime, pallet_template, AccountId, Balance, BalancesCall, BlockNumber, Hash, Header,
+	Index, RuntimeCall, SignedExtra, SignedPayload, UncheckedExtrinsic,
+};
+use sp_core::{crypto::Ss58Codec, hexdisplay::HexDisplay, sr25519, Bytes, Pair};
+use sp_runtime::generic::Era;
+use std::path::Path;
+
+/// Everything besides the call and the signer that goes into a signed transaction.
+#[derive(Clone, Debug)]
+pub struct SigningParams {
+	/// The hash of the chain's genesis block.
+	pub genesis_hash: Hash,
+	/// The spec version of the runtime the transaction is for.
+	pub spec_version: u32,
+	/// The transaction version of the runtime the transaction is for.
+	pub transaction_version: u32,
+	/// The signer's account nonce.
+	pub nonce: Index,
+	/// The transaction's lifetime.
+	pub era: Era,
+	/// The hash of the block `era` starts at, or the genesis hash for immortal transactions.
+	pub era_block_hash: Hash,
+	/// Tip for the block author.
+	pub tip: Balance,
+	/// The sponsor paying the fee instead of the signer.
+	pub sponsor: Option<AccountId>,
+	/// The metadata hash the transaction commits to, if any.
+	pub metadata_hash: Option<[u8; 32]>,
+}
+
+impl SigningParams {
+	/// Params for the native runtime's versions and metadata hash, with the lifetime `era`
+	/// starting at the block `era_block_hash`, without tip and paid by the signer.
+	pub fn new(genesis_hash: Hash, nonce: Index, era: Era, era_block_hash: Hash) -> Self {
+		Self {
+			genesis_hash,
+			spec_version: runtime::VERSION.spec_version,
+			transaction_version: runtime::VERSION.transaction_version,
+			nonce,
+			era,
+			era_block_hash,
+			tip: 0,
+			sponsor: None,
+			metadata_hash: runtime::extensions::METADATA_HASH,
+		}
+	}
+
+	/// Sets the tip for the block author.
+	pub fn tip(mut self, tip: Balance) -> Self {
+		self.tip = tip;
+		self
+	}
+
+	/// Has `sponsor` pay the fee, if it sponsors the call.
+	pub fn sponsor(mut self, sponsor: Option<AccountId>) -> Self {
+		self.sponsor = sponsor;
+		self
+	}
+
+	/// Commits to `metadata_hash`, or to none.
+	pub fn metadata_hash(mut self, metadata_hash: Option<[u8; 32]>) -> Self {
+		self.metadata_hash = metadata_hash;
+		self
+	}
+}
+
+/// The signed extensions of the runtime, for a transaction with the given params.
+pub fn signed_extra(params: &SigningParams) -> SignedExtra {
+	(
+		frame_system::CheckNonZeroSender::<runtime::Runtime>::new(),
+		frame_system::CheckSpecVersion::<runtime::Runtime>::new(),
+		frame_system::CheckTxVersion::<runtime::Runtime>::new(),
+		frame_system::CheckGenesis::<runtime::Runtime>::new(),
+		runtime::extensions::CheckEra::from(params.era),
+		runtime::extensions::CheckNonce::from(params.nonce),
+		frame_system::CheckWeight::<runtime::Runtime>::new(),
+		runtime::extensions::ChargeSponsoredTransactionPayment {
+			tip: params.tip,
+			sponsor: params.sponsor.clone(),
+		},
+		runtime::extensions::PrioritizeCalls,
+		runtime::extensions::CheckMetadataHash::new(params.metadata_hash.is_some()),
+	)
+}
+
+/// Signs `call` as `signer`, without access to a node.
+pub fn sign(
+	call: RuntimeCall,
+	signer: &sr25519::Pair,
+	params: &SigningParams,
+) -> UncheckedExtrinsic {
+	let extra = signed_extra(params);
+	let raw_payload = SignedPayload::from_raw(
+		call.clone(),
+		extra.clone(),
+		(
+			(),
+			params.spec_version,
+			params.transaction_version,
+			params.genesis_hash,
+			params.era_block_hash,
+			(),
+			(),
+			(),
+			(),
+			params.metadata_hash,
+		),
+	);
+	let signature = raw_payload.using_encoded(|e| signer.sign(e));
+
+	UncheckedExtrinsic::new_signed(
+		call,
+		AccountId::from(signer.public()).into(),
+		runtime::Signature::Sr25519(signature),
+		extra,
+	)
+}
+
+/// The `tx` command.
+#[derive(Debug, clap::Parser)]
+pub struct TxCmd {
+	#[command(subcommand)]
+	pub call: CallCmd,
+
+	/// The secret URI of the sr25519 signer, or the path of a file holding it.
+	#[arg(long)]
+	pub suri: String,
+
+	/// The signer's account nonce. Fetched from `--rpc-url` if omitted.
+	#[arg(long)]
+	pub nonce: Option<Index>,
+
+	/// Tip for the block author.
+	#[arg(long, default_value_t = 0)]
+	pub tip: Balance,
+
+	/// The SS58 address of a sponsor paying the fee instead of the signer.
+	///
+	/// Sponsored transactions can't have a tip.
+	#[arg(long, conflicts_with = "tip")]
+	pub sponsor: Option<AccountId>,
+
+	/// Number of blocks the transaction stays valid for, rounded to a power of two.
+	///
+	/// At most the runtime's `MaxMortality`, as it rejects longer periods and immortal
+	/// transactions.
+	#[arg(
+		long,
+		value_name = "BLOCKS",
+		default_value_t = 64,
+		value_parser = clap::value_parser!(u64).range(1..=runtime::MaxMortality::get())
+	)]
+	pub mortality: u64,
+
+	/// Number of the block the mortality period starts at.
+	///
+	/// Defaults to the finalized block of `--rpc-url`.
+	#[arg(long, requires = "era_block_hash")]
+	pub era_block_number: Option<BlockNumber>,
+
+	/// Hash of the block the mortality period starts at.
+	#[arg(long, requires = "era_block_number")]
+	pub era_block_hash: Option<Hash>,
+
+	/// The genesis hash of the chain. Fetched from `--rpc-url` if omitted.
+	#[arg(long)]
+	pub genesis_hash: Option<Hash>,
+
+	/// The spec version of the chain's runtime. Defaults to the one of this binary.
+	#[arg(long)]
+	pub spec_version: Option<u32>,
+
+	/// The transaction version of the chain's runtime. Defaults to the one of this binary.
+	#[arg(long)]
+	pub transaction_version: Option<u32>,
+
+	/// The metadata hash of the chain's runtime. Fetched from `--rpc-url` if omitted, and defaults
+	/// to the one of this binary without it.
+	#[arg(long, value_name = "HASH", conflicts_with = "no_metadata_hash")]
+	pub metadata_hash: Option<Hash>,
+
+	/// Don't commit to a metadata hash.
+	#[arg(long)]
+	pub no_metadata_hash: bool,
+
+	/// WebSocket RPC endpoint of a node to fetch missing parameters from, and to submit to.
+	#[arg(long, value_name = "URL")]
+	pub rpc_url: Option<String>,
+
+	/// Submit the signed transaction to `--rpc-url` instead of only printing it.
+	#[arg(long, requires = "rpc_url")]
+	pub submit: bool,
+}
+
+/// The call a transaction built by the `tx` command dispatches.
+#[derive(Debug, clap::Subcommand)]
+pub enum CallCmd {
+	/// Transfer funds with `Balances::transfer`.
+	Transfer {
+		/// The SS58 address of the recipient.
+		#[arg(long)]
+		dest: AccountId,
+		/// The amount to transfer.
+		#[arg(long)]
+		value: Balance,
+		/// Use `transfer_keep_alive`, which never reaps the sender's account.
+		#[arg(long)]
+		keep_alive: bool,
+	},
+
+	/// Store a value with `TemplateModule::do_something`.
+	DoSomething {
+		/// The value to store.
+		#[arg(long)]
+		value: u32,
+	},
+
+	/// Call `TemplateModule::cause_error`.
+	CauseError,
+
+	/// Any SCALE encoded `RuntimeCall`.
+	Raw {
+		/// The encoded call, in hex.
+		call: String,
+	},
+}
+
+impl CallCmd {
+	/// The runtime call described by `self`.
+	pub fn to_call(&self) -> Result<RuntimeCall, String> {
+		Ok(match self {
+			Self::Transfer { dest, value, keep_alive: false } =>
+				RuntimeCall::Balances(BalancesCall::transfer {
+					dest: dest.clone().into(),
+					value: *value,
+				}),
+			Self::Transfer { dest, value, keep_alive: true } =>
+				RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+					dest: dest.clone().into(),
+					value: *value,
+				}),
+			Self::DoSomething { value } =>
+				RuntimeCall::TemplateModule(pallet_template::Call::do_something {
+					something: *value,
+				}),
+			Self::CauseError => RuntimeCall::TemplateModule(pallet_template::Call::cause_error {}),
+			Self::Raw { call } => {
+				let encoded = sp_core::bytes::from_hex(call)
+					.map_err(|e| format!("Invalid call hex: {}", e))?;
+				RuntimeCall::decode_all(&mut &encoded[..])
+					.map_err(|e| format!("Invalid call: {}", e))?
+			},
+		})
+	}
+}
+
+impl TxCmd {
+	/// Run the command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let call = self.call.to_call()?;
+		let signer = sr25519::Pair::from_string(&read_suri(&self.suri)?, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+
+		sc_cli::build_runtime()?.block_on(async {
+			let rpc = match &self.rpc_url {
+				Some(url) => Some(
+					WsClientBuilder::default()
+						.build(url)
+						.await
+						.map_err(|e| format!("Error connecting to {}: {}", url, e))?,
+				),
+				None => None,
+			};
+
+			let params = self.signing_params(&signer, rpc.as_ref()).await?;
+			let extrinsic = sign(call, &signer, &params);
+			println!("0x{}", HexDisplay::from(&extrinsic.encode()));
+
+			if let (true, Some(rpc)) = (self.submit, &rpc) {
+				let hash: Hash = rpc
+					.request("author_submitExtrinsic", rpc_params![Bytes(extrinsic.encode())])
+					.await
+					.map_err(rpc_error("author_submitExtrinsic"))?;
+				println!("Submitted {:?}", hash);
+			}
+
+			Ok::<_, sc_cli::Error>(())
+		})
+	}
+
+	/// Takes the params from the command line, and everything missing from the node, if any.
+	async fn signing_params(
+		&self,
+		signer: &sr25519::Pair,
+		rpc: Option<&WsClient>,
+	) -> Result<SigningParams, String> {
+		let missing = |name: &str| format!("`--{}` is required without `--rpc-url`", name);
+
+		let genesis_hash = match (self.genesis_hash, rpc) {
+			(Some(hash), _) => hash,
+			(None, Some(rpc)) => rpc
+				.request::<Option<Hash>>("chain_getBlockHash", rpc_params![0u32])
+				.await
+				.map_err(rpc_error("chain_getBlockHash"))?
+				.ok_or("The node has no genesis block")?,
+			(None, None) => return Err(missing("genesis-hash")),
+		};
+		let nonce = match (self.nonce, rpc) {
+			(Some(nonce), _) => nonce,
+			(None, Some(rpc)) => rpc
+				.request("system_accountNextIndex", rpc_params![signer.public().to_ss58check()])
+				.await
+				.map_err(rpc_error("system_accountNextIndex"))?,
+			(None, None) => return Err(missing("nonce")),
+		};
+
+		let (number, hash) = match (self.era_block_number, self.era_block_hash, rpc) {
+			(Some(number), Some(hash), _) => (number, hash),
+			(_, _, Some(rpc)) => {
+				let hash: Hash = rpc
+					.request("chain_getFinalizedHead", rpc_params![])
+					.await
+					.map_err(rpc_error("chain_getFinalizedHead"))?;
+				let header = rpc
+					.request::<Option<Header>>("chain_getHeader", rpc_params![hash])
+					.await
+					.map_err(rpc_error("chain_getHeader"))?
+					.ok_or("The node does not know its finalized block")?;
+				(header.number, hash)
+			},
+			_ => return Err(missing("era-block-number")),
+		};
+		let era = Era::mortal(self.mortality, number.into());
+
+		let mut params = SigningParams::new(genesis_hash, nonce, era, hash)
+			.tip(self.tip)
+			.sponsor(self.sponsor.clone());
+		params.spec_version = self.spec_version.unwrap_or(params.spec_version);
+		params.transaction_version = self.transaction_version.unwrap_or(params.transaction_version);
+		params.metadata_hash = match (self.no_metadata_hash, self.metadata_hash, rpc) {
+			(true, _, _) => None,
+			(false, Some(hash), _) => Some(hash.0),
+			(false, None, Some(rpc)) => {
+				let result: Bytes = rpc
+					.request(
+						"state_call",
+						rpc_params!["MetadataHashApi_metadata_hash", Bytes(Vec::new())],
+					)
+					.await
+					.map_err(rpc_error("state_call"))?;
+				Option::<[u8; 32]>::decode_all(&mut &result[..])
+					.map_err(|e| format!("Invalid metadata hash: {}", e))?
+			},
+			(false, None, None) => params.metadata_hash,
+		};
+
+		Ok(params)
+	}
+}
+
+/// Reads the secret URI from the file at `suri`, if there is one.
+fn read_suri(suri: &str) -> Result<String, String> {
+	if Path::new(suri).is_file() {
+		std::fs::read_to_string(suri)
+			.map(|content| content.trim_end().to_string())
+			.map_err(|e| format!("Error reading {}: {}", suri, e))
+	} else {
+		Ok(suri.to_string())
+	}
+}
+
+/// Names the RPC `method` in its errors.
+pub fn rpc_error(method: &'static str) -> impl FnOnce(jsonrpsee::core::Error) -> String {
+	move |e| format!("`{}` failed: {}", method, e)
+}
@@ -0,0 +1,542 @@
+//! A persistent record of the transactions the node has seen, for the `tx_status` RPC methods.
+//!
+//! A transaction is recorded when the node's pool accepts it, or when it shows up signed in a best
+//! block. Its record follows it into a block and to finality, back into the pool if a reorg
+//! retracts the block, or out of the pool if it is dropped or turns invalid. Transactions that
+//! reached a final state are forgotten after [`RETENTION`].
+
+use crate::{
+	decode::{Decoder, Described},
+	inspect::{BlockInfo, ExtrinsicInfo},
+	service::{FullClient, FullPool},
+};
+use codec::{Decode, Encode};
+use futures::{channel::mpsc, StreamExt};
+use node_template_runtime::{opaque::Block, Balance, BlockNumber, Hash};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use sc_client_api::{BlockImportNotification, BlockchainEvents, FinalityNotification};
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::{
+	generic::BlockId,
+	traits::Block as BlockT,
+	transaction_validity::{TransactionSource, TransactionValidityError},
+};
+use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
+use std::{
+	collections::HashSet,
+	path::Path,
+	sync::{Arc, Mutex, MutexGuard, PoisonError},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const LOG_TARGET: &str = "tx-status";
+
+/// The database in the chain's data directory.
+pub const DATABASE_FILE: &str = "tx-status.sqlite";
+
+/// The files SQLite keeps next to the database in WAL mode.
+const WAL_SUFFIXES: [&str; 2] = ["-wal", "-shm"];
+
+/// How long transactions are remembered once finalized, dropped or invalid.
+pub const RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+const SCHEMA: &str = "
+	PRAGMA journal_mode = WAL;
+
+	CREATE TABLE IF NOT EXISTS transactions (
+		hash TEXT PRIMARY KEY,
+		state TEXT NOT NULL,
+		extrinsic BLOB,
+		pooled_at INTEGER,
+		block_hash TEXT,
+		block_number INTEGER,
+		idx INTEGER,
+		success INTEGER,
+		error TEXT,
+		fee TEXT,
+		reason TEXT,
+		updated_at INTEGER NOT NULL
+	);
+	CREATE INDEX IF NOT EXISTS transactions_state ON transactions (state);
+	CREATE INDEX IF NOT EXISTS transactions_block_hash ON transactions (block_hash);
+";
+
+/// Where a transaction is in its lifecycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TxState {
+	/// Waiting in the node's pool.
+	Pooled,
+	/// In the best chain, but not finalized yet.
+	Included,
+	/// In a finalized block.
+	Finalized,
+	/// Removed from the pool while still valid, e.g. replaced or evicted.
+	Dropped,
+	/// Removed from the pool as invalid.
+	Invalid,
+}
+
+impl TxState {
+	/// Whether the transaction won't change its state anymore, in the common case.
+	pub fn is_final(self) -> bool {
+		matches!(self, Self::Finalized | Self::Dropped | Self::Invalid)
+	}
+
+	fn as_str(self) -> &'static str {
+		match self {
+			Self::Pooled => "pooled",
+			Self::Included => "included",
+			Self::Finalized => "finalized",
+			Self::Dropped => "dropped",
+			Self::Invalid => "invalid",
+		}
+	}
+
+	fn parse(state: &str) -> Option<Self> {
+		[Self::Pooled, Self::Included, Self::Finalized, Self::Dropped, Self::Invalid]
+			.into_iter()
+			.find(|known| known.as_str() == state)
+	}
+}
+
+/// What the node knows about a transaction.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxStatus {
+	pub hash: Hash,
+	pub state: TxState,
+	/// When the pool accepted the transaction, in milliseconds since the Unix epoch. None if the
+	/// node only saw it in a block.
+	pub pooled_at: Option<u64>,
+	/// The block including the transaction, once included.
+	pub block_hash: Option<Hash>,
+	pub block_number: Option<BlockNumber>,
+	/// The position of the transaction in its block.
+	pub extrinsic_index: Option<u32>,
+	/// Whether the call was dispatched successfully, once included.
+	pub success: Option<bool>,
+	/// The dispatch error of failed calls.
+	pub error: Option<Value>,
+	/// The fee paid, including the tip.
+	pub fee: Option<Balance>,
+	/// Why the transaction was dropped or is invalid.
+	pub reason: Option<String>,
+	/// When the state last changed, in milliseconds since the Unix epoch.
+	pub updated_at: u64,
+}
+
+/// The SQLite database of transaction statuses, shared by the tracking task and the RPC methods.
+pub struct Store {
+	connection: Mutex<Connection>,
+	subscribers: Mutex<Vec<(Hash, mpsc::UnboundedSender<TxStatus>)>>,
+}
+
+impl Store {
+	/// Opens the database at `path`, creating it if needed.
+	pub fn open(path: &Path) -> rusqlite::Result<Self> {
+		let connection = Connection::open(path)?;
+		connection.execute_batch(SCHEMA)?;
+		Ok(Self { connection: Mutex::new(connection), subscribers: Mutex::new(Vec::new()) })
+	}
+
+	fn connection(&self) -> MutexGuard<Connection> {
+		self.connection.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	/// The status of the transaction with the given hash, if the node has seen it.
+	pub fn status(&self, hash: &Hash) -> rusqlite::Result<Option<TxStatus>> {
+		self.connection()
+			.query_row(
+				"SELECT hash, state, pooled_at, block_hash, block_number, idx, success, error, fee,
+					reason, updated_at
+				FROM transactions WHERE hash = ?1",
+				[format!("{:?}", hash)],
+				status,
+			)
+			.optional()
+	}
+
+	/// Streams the statuses the transaction with the given hash moves through from now on.
+	pub fn subscribe(&self, hash: Hash) -> mpsc::UnboundedReceiver<TxStatus> {
+		let (sender, receiver) = mpsc::unbounded();
+		let mut subscribers = self.subscribers.lock().unwrap_or_else(PoisonError::into_inner);
+		subscribers.retain(|(_, sender)| !sender.is_closed());
+		subscribers.push((hash, sender));
+		receiver
+	}
+
+	/// Runs `update` on the transaction with the given hash, and notifies its subscribers if its
+	/// status changed.
+	fn update(
+		&self,
+		hash: &Hash,
+		update: impl FnOnce(&Connection, &str) -> rusqlite::Result<usize>,
+	) -> rusqlite::Result<()> {
+		let before = self.status(hash)?;
+		if update(&self.connection(), &format!("{:?}", hash))? == 0 {
+			return Ok(())
+		}
+		let after = match self.status(hash)? {
+			Some(after) if Some(&after) != before.as_ref() => after,
+			_ => return Ok(()),
+		};
+
+		self.subscribers.lock().unwrap_or_else(PoisonError::into_inner).retain(
+			|(subscribed, sender)| {
+				*subscribed != *hash || sender.unbounded_send(after.clone()).is_ok()
+			},
+		);
+		Ok(())
+	}
+
+	/// Records that the pool accepted `extrinsic`, unless the transaction is known already. A
+	/// dropped or invalid transaction is pooled again.
+	fn pooled(&self, hash: &Hash, extrinsic: &[u8]) -> rusqlite::Result<()> {
+		self.update(hash, |connection, hash| {
+			connection.execute(
+				"INSERT INTO transactions (hash, state, extrinsic, pooled_at, updated_at)
+				VALUES (?1, 'pooled', ?2, ?3, ?3)
+				ON CONFLICT (hash) DO UPDATE SET
+					state = 'pooled',
+					extrinsic = excluded.extrinsic,
+					pooled_at = excluded.pooled_at,
+					reason = NULL,
+					updated_at = excluded.updated_at
+				WHERE state IN ('dropped', 'invalid')",
+				params![hash, extrinsic, now()],
+			)
+		})
+	}
+
+	/// Records that `extrinsic` is in the given block. A finalized transaction stays in its
+	/// finalized block.
+	fn included(
+		&self,
+		block_hash: &Hash,
+		block_number: BlockNumber,
+		extrinsic: &ExtrinsicInfo,
+		finalized: bool,
+	) -> rusqlite::Result<()> {
+		let state = if finalized { TxState::Finalized } else { TxState::Included };
+		let index = extrinsic.index.expect("extrinsics read from a block have an index; qed");
+		let failure = failure(&extrinsic.events);
+		self.update(&extrinsic.hash, |connection, hash| {
+			connection.execute(
+				"INSERT INTO transactions
+					(hash, state, block_hash, block_number, idx, success, error, fee, updated_at)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+				ON CONFLICT (hash) DO UPDATE SET
+					state = excluded.state,
+					extrinsic = CASE WHEN ?10 THEN NULL ELSE extrinsic END,
+					block_hash = excluded.block_hash,
+					block_number = excluded.block_number,
+					idx = excluded.idx,
+					success = excluded.success,
+					error = excluded.error,
+					fee = excluded.fee,
+					reason = NULL,
+					updated_at = excluded.updated_at
+				WHERE state != 'finalized'",
+				params![
+					hash,
+					state.as_str(),
+					format!("{:?}", block_hash),
+					block_number,
+					index,
+					failure.is_none(),
+					failure.map(|error| error.to_string()),
+					extrinsic.fee.map(|fee| fee.to_string()),
+					now(),
+					finalized
+				],
+			)
+		})
+	}
+
+	/// Moves the transactions of a retracted block back into the pool.
+	fn retracted(&self, block_hash: &Hash) -> rusqlite::Result<()> {
+		let hashes = {
+			let connection = self.connection();
+			let mut statement = connection.prepare_cached(
+				"SELECT hash FROM transactions WHERE block_hash = ?1 AND state = 'included'",
+			)?;
+			let rows = statement.query_map([format!("{:?}", block_hash)], |row| hash(row, 0))?;
+			rows.collect::<rusqlite::Result<Vec<_>>>()?
+		};
+
+		for hash in hashes {
+			self.update(&hash, |connection, hash| {
+				connection.execute(
+					"UPDATE transactions SET state = 'pooled', block_hash = NULL,
+						block_number = NULL, idx = NULL, success = NULL, error = NULL, fee = NULL,
+						updated_at = ?2
+					WHERE hash = ?1 AND state = 'included'",
+					params![hash, now()],
+				)
+			})?;
+		}
+		Ok(())
+	}
+
+	/// Records that the pooled transaction left the pool without being included.
+	fn removed(&self, hash: &Hash, state: TxState, reason: &str) -> rusqlite::Result<()> {
+		self.update(hash, |connection, hash| {
+			connection.execute(
+				"UPDATE transactions SET state = ?2, extrinsic = NULL, reason = ?3, updated_at = ?4
+				WHERE hash = ?1 AND state = 'pooled'",
+				params![hash, state.as_str(), reason, now()],
+			)
+		})
+	}
+
+	/// The transactions the pool should hold, with their encoding if the pool accepted them.
+	fn pooled_transactions(&self) -> rusqlite::Result<Vec<(Hash, Option<Vec<u8>>)>> {
+		let connection = self.connection();
+		let mut statement = connection
+			.prepare_cached("SELECT hash, extrinsic FROM transactions WHERE state = 'pooled'")?;
+		let rows = statement.query_map([], |row| Ok((hash(row, 0)?, row.get(1)?)))?;
+		rows.collect()
+	}
+
+	/// Forgets the transactions that reached a final state more than [`RETENTION`] ago.
+	fn prune(&self) -> rusqlite::Result<()> {
+		let before = now().saturating_sub(RETENTION.as_millis() as u64);
+		self.connection().execute(
+			"DELETE FROM transactions
+			WHERE state IN ('finalized', 'dropped', 'invalid') AND updated_at < ?1",
+			[before],
+		)?;
+		Ok(())
+	}
+}
+
+fn status(row: &Row) -> rusqlite::Result<TxStatus> {
+	let state = row.get::<_, String>(1)?;
+	Ok(TxStatus {
+		hash: hash(row, 0)?,
+		state: TxState::parse(&state).ok_or_else(|| {
+			rusqlite::Error::FromSqlConversionFailure(
+				1,
+				rusqlite::types::Type::Text,
+				format!("Invalid state {}", state).into(),
+			)
+		})?,
+		pooled_at: row.get(2)?,
+		block_hash: row.get::<_, Option<String>>(3)?.map(|_| hash(row, 3)).transpose()?,
+		block_number: row.get(4)?,
+		extrinsic_index: row.get(5)?,
+		success: row.get(6)?,
+		error: row
+			.get::<_, Option<String>>(7)?
+			.map(|error| {
+				serde_json::from_str(&error).map_err(|e| {
+					rusqlite::Error::FromSqlConversionFailure(
+						7,
+						rusqlite::types::Type::Text,
+						e.into(),
+					)
+				})
+			})
+			.transpose()?,
+		fee: row
+			.get::<_, Option<String>>(8)?
+			.map(|fee| {
+				fee.parse().map_err(|e| {
+					rusqlite::Error::FromSqlConversionFailure(
+						8,
+						rusqlite::types::Type::Text,
+						Box::new(e),
+					)
+				})
+			})
+			.transpose()?,
+		reason: row.get(9)?,
+		updated_at: row.get(10)?,
+	})
+}
+
+fn hash(row: &Row, index: usize) -> rusqlite::Result<Hash> {
+	row.get::<_, String>(index)?.parse().map_err(|e| {
+		rusqlite::Error::FromSqlConversionFailure(
+			index,
+			rusqlite::types::Type::Text,
+			format!("Invalid hash: {:?}", e).into(),
+		)
+	})
+}
+
+/// The dispatch error of an extrinsic that deposited `events`, if it failed.
+fn failure(events: &[Described]) -> Option<Value> {
+	events
+		.iter()
+		.find(|event| event.pallet == "System" && event.name == "ExtrinsicFailed")
+		.map(|event| event.args["dispatch_error"].clone())
+}
+
+/// Milliseconds since the Unix epoch.
+fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+enum Notification {
+	Pooled(Hash),
+	Imported(BlockImportNotification<Block>),
+	Finalized(FinalityNotification<Block>),
+}
+
+/// Removes the database from the chain's data directory, e.g. when the chain is purged.
+pub fn remove(data_path: &Path) -> std::io::Result<()> {
+	let path = data_path.join(DATABASE_FILE);
+	for suffix in [""].into_iter().chain(WAL_SUFFIXES) {
+		let mut file = path.clone().into_os_string();
+		file.push(suffix);
+		match std::fs::remove_file(&file) {
+			Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+			_ => (),
+		}
+	}
+
+	Ok(())
+}
+
+/// Records the statuses of transactions into `store` until the node shuts down.
+///
+/// Errors are logged, and the notification that caused them is skipped.
+pub async fn run(client: Arc<FullClient>, pool: Arc<FullPool>, store: Arc<Store>) {
+	let decoder = Decoder::default();
+	let mut missing = HashSet::new();
+	let mut notifications = futures::stream::select(
+		futures::stream::select(
+			pool.import_notification_stream().map(Notification::Pooled),
+			client.import_notification_stream().map(Notification::Imported),
+		),
+		client.finality_notification_stream().map(Notification::Finalized),
+	);
+
+	while let Some(notification) = notifications.next().await {
+		if let Err(e) = record(&client, &pool, &store, &decoder, &mut missing, notification) {
+			log::warn!(target: LOG_TARGET, "Error recording transaction statuses: {}", e);
+		}
+	}
+}
+
+/// Records the statuses the notification changes.
+fn record(
+	client: &FullClient,
+	pool: &FullPool,
+	store: &Store,
+	decoder: &Decoder,
+	missing: &mut HashSet<Hash>,
+	notification: Notification,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+	match notification {
+		Notification::Pooled(hash) =>
+			if let Some(transaction) = pool.ready_transaction(&hash) {
+				store.pooled(&hash, &transaction.data().encode())?;
+			},
+		Notification::Imported(notification) if notification.is_new_best => {
+			if let Some(route) = &notification.tree_route {
+				for block in route.retracted() {
+					store.retracted(&block.hash)?;
+				}
+				for block in route.enacted() {
+					record_block(client, store, decoder, block.hash, false)?;
+				}
+			}
+			record_block(client, store, decoder, notification.hash, false)?;
+			check_pool(client, pool, store, notification.hash, missing)?;
+		},
+		Notification::Imported(_) => (),
+		Notification::Finalized(notification) => {
+			for hash in notification.tree_route.iter().chain([&notification.hash]) {
+				record_block(client, store, decoder, *hash, true)?;
+			}
+			store.prune()?;
+		},
+	}
+
+	Ok(())
+}
+
+/// Records the signed extrinsics of the block with the given hash as included.
+fn record_block(
+	client: &FullClient,
+	store: &Store,
+	decoder: &Decoder,
+	hash: Hash,
+	finalized: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+	// The block's state may be pruned already, e.g. when the node catches up.
+	let block = match BlockInfo::read(client, decoder, hash) {
+		Ok(block) => block,
+		Err(e) => {
+			log::warn!(target: LOG_TARGET, "Skipping the transactions of block {}: {}", hash, e);
+			return Ok(())
+		},
+	};
+	for extrinsic in block.extrinsics.iter().filter(|extrinsic| extrinsic.signer.is_some()) {
+		store.included(&block.hash, block.number, extrinsic, finalized)?;
+	}
+
+	Ok(())
+}
+
+/// Records the transactions that are new in the pool's future queue, and those that left the pool
+/// without being included. The latter are validated again to tell invalid ones from dropped ones.
+///
+/// The pool may prune a transaction before the block including it is recorded here, so a
+/// transaction only counts as removed once it is missing on two checks in a row. `missing` holds
+/// the transactions missing on the previous check.
+fn check_pool(
+	client: &FullClient,
+	pool: &FullPool,
+	store: &Store,
+	best: Hash,
+	missing: &mut HashSet<Hash>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+	let futures = pool.futures();
+	for transaction in &futures {
+		store.pooled(transaction.hash(), &transaction.data().encode())?;
+	}
+	let futures = futures.iter().map(|transaction| *transaction.hash()).collect::<HashSet<_>>();
+
+	let mut still_missing = HashSet::new();
+	for (hash, extrinsic) in store.pooled_transactions()? {
+		if futures.contains(&hash) || pool.ready_transaction(&hash).is_some() {
+			continue
+		}
+		if !missing.contains(&hash) {
+			still_missing.insert(hash);
+			continue
+		}
+
+		let extrinsic = match extrinsic {
+			Some(extrinsic) => <Block as BlockT>::Extrinsic::decode(&mut &extrinsic[..])?,
+			// Only transactions seen in a retracted block lack their encoding.
+			None => {
+				store.removed(&hash, TxState::Dropped, "Removed from the pool")?;
+				continue
+			},
+		};
+		let validity = client.runtime_api().validate_transaction(
+			&BlockId::Hash(best),
+			TransactionSource::External,
+			extrinsic,
+			best,
+		)?;
+		match validity {
+			Err(TransactionValidityError::Invalid(e)) =>
+				store.removed(&hash, TxState::Invalid, &format!("{:?}", e))?,
+			Err(TransactionValidityError::Unknown(e)) =>
+				store.removed(&hash, TxState::Dropped, &format!("{:?}", e))?,
+			Ok(_) => store.removed(&hash, TxState::Dropped, "Removed from the pool")?,
+		}
+	}
+	*missing = still_missing;
+
+	Ok(())
+}
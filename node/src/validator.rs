@@ -0,0 +1,199 @@
+//! The `validator` subcommands, which set up and verify the session keys of an authority.
+
+use crate::{chain_spec::BlockAuthorId, service::FullClient};
+use codec::Encode;
+use node_template_runtime::opaque::{Block, SessionKeys};
+use sc_cli::{CliConfiguration, KeystoreParams, SharedParams};
+use sc_keystore::LocalKeystore;
+use sc_service::{config::KeystoreConfig, Configuration};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	crypto::{KeyTypeId, Ss58Codec},
+	ed25519,
+	hexdisplay::HexDisplay,
+	sr25519, Pair,
+};
+use sp_finality_grandpa::{AuthorityId as GrandpaId, GrandpaApi};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{generic::BlockId, RuntimeAppPublic};
+
+/// Session key management for authorities.
+#[derive(Debug, clap::Subcommand)]
+pub enum ValidatorCmd {
+	/// Insert the session keys derived from one secret into the keystore and print them.
+	Init(InitCmd),
+
+	/// Check that the keystore holds session keys of the current authorities.
+	Check(CheckCmd),
+}
+
+/// The `validator init` command.
+#[derive(Debug, clap::Parser)]
+pub struct InitCmd {
+	/// The secret URI, e.g. a phrase or `//Alice`, to derive the session keys from.
+	///
+	/// A new secret phrase is generated if none is given.
+	#[arg(long)]
+	pub suri: Option<String>,
+
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[clap(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+impl InitCmd {
+	/// Run the command.
+	pub fn run(&self, config: &Configuration) -> sc_cli::Result<()> {
+		let (path, password) = match &config.keystore {
+			KeystoreConfig::Path { path, password } => (path, password.clone()),
+			KeystoreConfig::InMemory =>
+				return Err("`validator init` requires a keystore on disk".into()),
+		};
+		let keystore = LocalKeystore::open(path, password)?;
+
+		let suri = match &self.suri {
+			Some(suri) => suri.clone(),
+			None => {
+				let (_, phrase, _) = sr25519::Pair::generate_with_phrase(None);
+				println!("Generated secret phrase: {}", phrase);
+				println!("Store it safely, it is the only way to restore these keys.");
+				phrase
+			},
+		};
+
+		let author = sr25519::Pair::from_string(&suri, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?
+			.public();
+		let grandpa = ed25519::Pair::from_string(&suri, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?
+			.public();
+
+		SyncCryptoStore::insert_unknown(&keystore, BlockAuthorId::ID, &suri, author.as_ref())
+			.map_err(|_| "Failed to insert the block author key")?;
+		SyncCryptoStore::insert_unknown(&keystore, GrandpaId::ID, &suri, grandpa.as_ref())
+			.map_err(|_| "Failed to insert the GRANDPA key")?;
+
+		let session_keys = SessionKeys {
+			#[cfg(not(feature = "babe"))]
+			aura: author.into(),
+			#[cfg(feature = "babe")]
+			babe: author.into(),
+			grandpa: grandpa.into(),
+		};
+
+		println!("{} key: {}", key_type_name(BlockAuthorId::ID), author.to_ss58check());
+		println!("{} key: {}", key_type_name(GrandpaId::ID), grandpa.to_ss58check());
+		println!("Session keys: 0x{}", HexDisplay::from(&session_keys.encode()));
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for InitCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+}
+
+/// The `validator check` command.
+#[derive(Debug, clap::Parser)]
+pub struct CheckCmd {
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[clap(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+impl CheckCmd {
+	/// Run the command against the authorities of the best block.
+	pub fn run(&self, client: &FullClient, keystore: SyncCryptoStorePtr) -> sc_cli::Result<()> {
+		let at = BlockId::Hash(client.info().best_hash);
+
+		let authors = block_authors(client, &at)?;
+		let local_authors = SyncCryptoStore::sr25519_public_keys(&*keystore, BlockAuthorId::ID)
+			.into_iter()
+			.map(BlockAuthorId::from)
+			.filter(|key| authors.contains(key))
+			.collect::<Vec<_>>();
+
+		let grandpa_authorities = client
+			.runtime_api()
+			.grandpa_authorities(&at)
+			.map_err(|e| format!("Error reading the GRANDPA authorities: {}", e))?;
+		let local_grandpa = SyncCryptoStore::ed25519_public_keys(&*keystore, GrandpaId::ID)
+			.into_iter()
+			.map(GrandpaId::from)
+			.filter(|key| grandpa_authorities.iter().any(|(authority, _)| authority == key))
+			.collect::<Vec<_>>();
+
+		for key in &local_authors {
+			println!("{} authority: {}", key_type_name(BlockAuthorId::ID), key);
+		}
+		for key in &local_grandpa {
+			println!("{} authority: {}", key_type_name(GrandpaId::ID), key);
+		}
+
+		match (local_authors.is_empty(), local_grandpa.is_empty()) {
+			(false, false) => Ok(()),
+			(true, _) => Err(format!(
+				"The keystore holds none of the {} current {} authority keys",
+				authors.len(),
+				key_type_name(BlockAuthorId::ID),
+			)
+			.into()),
+			(false, true) => Err(format!(
+				"The keystore holds none of the {} current {} authority keys",
+				grandpa_authorities.len(),
+				key_type_name(GrandpaId::ID),
+			)
+			.into()),
+		}
+	}
+}
+
+impl CliConfiguration for CheckCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+}
+
+/// The current block production authorities.
+#[cfg(not(feature = "babe"))]
+fn block_authors(client: &FullClient, at: &BlockId<Block>) -> Result<Vec<BlockAuthorId>, String> {
+	use sp_consensus_aura::AuraApi;
+
+	client
+		.runtime_api()
+		.authorities(at)
+		.map_err(|e| format!("Error reading the Aura authorities: {}", e))
+}
+
+/// The current block production authorities.
+#[cfg(feature = "babe")]
+fn block_authors(client: &FullClient, at: &BlockId<Block>) -> Result<Vec<BlockAuthorId>, String> {
+	use sp_consensus_babe::BabeApi;
+
+	let epoch = client
+		.runtime_api()
+		.current_epoch(at)
+		.map_err(|e| format!("Error reading the BABE authorities: {}", e))?;
+
+	Ok(epoch.authorities.into_iter().map(|(authority, _)| authority).collect())
+}
+
+/// The four-letter name of a key type, as used by `key insert`.
+fn key_type_name(key_type: KeyTypeId) -> String {
+	String::from_utf8_lossy(&key_type.0).into()
+}
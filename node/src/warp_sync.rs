@@ -0,0 +1,51 @@
+//! Warp sync starting at the chain spec's checkpoint instead of genesis.
+
+use crate::chain_spec::Checkpoint;
+use node_template_runtime::{opaque::Block, Hash};
+use sc_network_common::sync::warp::{EncodedProof, VerificationResult, WarpSyncProvider};
+use sp_finality_grandpa::{AuthorityList, SetId};
+use std::error::Error;
+
+/// A [`WarpSyncProvider`] that trusts a [`Checkpoint`].
+///
+/// Warp sync starts at genesis, with the genesis authority set. Proofs starting before the
+/// checkpoint's authority set are not verified: warp sync continues from the checkpoint's block,
+/// with its authority set, and only the proofs from there on are verified by `inner`. Proofs
+/// served to other nodes are those of `inner`.
+pub struct CheckpointedWarpSync<P> {
+	inner: P,
+	checkpoint: Option<Checkpoint>,
+}
+
+impl<P> CheckpointedWarpSync<P> {
+	/// Creates a new [`Self`], trusting `checkpoint` if there is one.
+	pub fn new(inner: P, checkpoint: Option<Checkpoint>) -> Self {
+		Self { inner, checkpoint }
+	}
+}
+
+impl<P: WarpSyncProvider<Block>> WarpSyncProvider<Block> for CheckpointedWarpSync<P> {
+	fn generate(&self, start: Hash) -> Result<EncodedProof, Box<dyn Error + Send + Sync>> {
+		self.inner.generate(start)
+	}
+
+	fn verify(
+		&self,
+		proof: &EncodedProof,
+		set_id: SetId,
+		authorities: AuthorityList,
+	) -> Result<VerificationResult<Block>, Box<dyn Error + Send + Sync>> {
+		match &self.checkpoint {
+			Some(checkpoint) if set_id < checkpoint.set_id => Ok(VerificationResult::Partial(
+				checkpoint.set_id,
+				checkpoint.authorities.clone(),
+				checkpoint.header.hash(),
+			)),
+			_ => self.inner.verify(proof, set_id, authorities),
+		}
+	}
+
+	fn current_authorities(&self) -> AuthorityList {
+		self.inner.current_authorities()
+	}
+}
@@ -0,0 +1,44 @@
+use node_template::rpc::account::AccountApiClient;
+use node_template_runtime::{BalancesCall, RuntimeCall, EXISTENTIAL_DEPOSIT};
+use sp_keyring::Sr25519Keyring::{Alice, Bob, Ferdie};
+use tempfile::tempdir;
+
+pub mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn account_summaries_count_pending_transactions() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let alice = rpc.summary(Alice.to_account_id(), None).await.unwrap();
+	assert!(alice.free > 0);
+	assert_eq!(alice.reserved, 0);
+	assert_eq!(alice.nonce, 0);
+	assert_eq!(alice.existential_deposit, EXISTENTIAL_DEPOSIT);
+	assert_eq!(alice.warning, None);
+
+	let ferdie = rpc.summary(Ferdie.to_account_id(), None).await.unwrap();
+	assert_eq!(ferdie.free, 0);
+	assert_eq!(ferdie.warning.as_deref(), Some("notExisting"));
+
+	// Transactions waiting in the pool count towards the nonce.
+	for nonce in 0..2 {
+		let call = RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+			dest: Bob.to_account_id().into(),
+			value: 1_000_000,
+		});
+		common::submit(&rpc, &common::signed_extrinsic(&rpc, Alice, call, nonce).await).await;
+	}
+	let summaries = rpc
+		.summaries(vec![Alice.to_account_id(), Ferdie.to_account_id()], None)
+		.await
+		.unwrap();
+	assert_eq!(summaries.len(), 2);
+	assert_eq!(summaries[0].nonce, 2);
+	assert_eq!(summaries[1].warning.as_deref(), Some("notExisting"));
+
+	let too_many = vec![Alice.to_account_id(); 1001];
+	assert!(rpc.summaries(too_many, None).await.is_err());
+}
@@ -0,0 +1,92 @@
+use node_template::decode::Decoder;
+use node_template_runtime::{pallet_template, BalancesCall, RuntimeCall};
+use serde_json::{json, Value};
+use sp_core::crypto::Ss58Codec;
+use sp_keyring::Sr25519Keyring;
+use std::{ffi::OsStr, process::Output};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn benchmark_extrinsic(pallet: &str, extrinsic: &str, call: Option<&str>) -> Output {
+	benchmark_extrinsic_on("dev", pallet, extrinsic, call)
+}
+
+fn benchmark_extrinsic_on(
+	chain: impl AsRef<OsStr>,
+	pallet: &str,
+	extrinsic: &str,
+	call: Option<&str>,
+) -> Output {
+	let base_path = tempdir().unwrap();
+	let mut command = common::node_command();
+	command
+		.args(["benchmark", "extrinsic", "--chain"])
+		.arg(chain)
+		.args(["--pallet", pallet, "--extrinsic", extrinsic])
+		.args(["--warmup", "1", "--repeat", "1", "--base-path"])
+		.arg(base_path.path());
+	if let Some(call) = call {
+		command.env("BENCHMARK_CALL", call);
+	}
+	command.output().unwrap()
+}
+
+#[test]
+fn calls_are_encoded_from_their_description() {
+	let decoder = Decoder::default();
+
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 42 });
+	let described =
+		json!({ "pallet": "TemplateModule", "name": "do_something", "args": { "something": 42 } });
+	assert_eq!(decoder.encode_call(&described), Ok(call));
+
+	// Whatever `inspect` shows can be encoded again.
+	let call = RuntimeCall::Balances(BalancesCall::transfer {
+		dest: Sr25519Keyring::Bob.to_account_id().into(),
+		value: u128::MAX,
+	});
+	let described = serde_json::to_value(decoder.call(&call)).unwrap();
+	assert_eq!(decoder.encode_call(&described), Ok(call));
+
+	let unknown = json!({ "pallet": "TemplateModule", "name": "do_nothing", "args": null });
+	assert!(decoder.encode_call(&unknown).is_err());
+	let missing = json!({ "pallet": "TemplateModule", "name": "do_something", "args": {} });
+	assert!(decoder.encode_call(&missing).unwrap_err().contains("something"));
+}
+
+#[test]
+fn benchmark_extrinsic_measures_template_calls() {
+	for extrinsic in ["do_something", "cause_error"] {
+		let output = benchmark_extrinsic("template", extrinsic, None);
+		assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+	}
+}
+
+#[test]
+fn benchmark_extrinsic_measures_configured_calls() {
+	let call = r#"{"pallet": "System", "name": "remark", "args": {"remark": "0x0102"}}"#;
+	let output = benchmark_extrinsic("custom", "remark", Some(call));
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+	let output = benchmark_extrinsic("custom", "remark", Some("0xzz"));
+	assert!(!output.status.success());
+}
+
+#[test]
+fn benchmark_extrinsic_measures_feeless_calls_where_allowlisted() {
+	// The dev chain spec allowlists nobody.
+	let output = benchmark_extrinsic("feeless", "do_something", None);
+	assert!(!output.status.success());
+
+	let output = common::node_command().args(["build-spec", "--dev"]).output().unwrap();
+	let mut spec: Value = serde_json::from_slice(&output.stdout).unwrap();
+	spec["genesis"]["runtime"]["feeless"]["accounts"] =
+		json!([Sr25519Keyring::Bob.to_account_id().to_ss58check()]);
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("feeless.json");
+	std::fs::write(&path, spec.to_string()).unwrap();
+
+	let output = benchmark_extrinsic_on(&path, "feeless", "do_something", None);
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
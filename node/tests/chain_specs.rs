@@ -0,0 +1,82 @@
+use serde_json::Value;
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Runs `build-spec` for the given chain and returns the parsed spec.
+fn build_spec(chain: &str, envs: &[(&str, &str)]) -> Option<Value> {
+	let output = common::node_command()
+		.args(["build-spec", "--chain", chain, "--disable-default-bootnode"])
+		.env_remove("STAGING_AUTHORITIES")
+		.env_remove("STAGING_AUTHORITIES_FILE")
+		.env_remove("STAGING_SUDO")
+		.env_remove("STAGING_SUDO_FILE")
+		.envs(envs.iter().copied())
+		.output()
+		.unwrap();
+
+	output.status.success().then(|| serde_json::from_slice(&output.stdout).unwrap())
+}
+
+#[test]
+fn built_in_specs_declare_token_properties() {
+	for chain in ["dev", "local", "pow"] {
+		let spec = build_spec(chain, &[]).unwrap();
+		assert_eq!(spec["properties"]["tokenSymbol"], "UNIT");
+		assert_eq!(spec["properties"]["tokenDecimals"], 12);
+		assert_eq!(spec["properties"]["ss58Format"], 42);
+	}
+}
+
+#[test]
+fn built_in_specs_declare_their_consensus() {
+	assert_eq!(build_spec("dev", &[]).unwrap()["consensus"], "authorities");
+	assert_eq!(build_spec("pow", &[]).unwrap()["consensus"], "proofOfWork");
+}
+
+#[test]
+fn staging_spec_reads_keys_from_the_environment() {
+	let authorities = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY:\
+		5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu";
+	let sudo = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+	let spec =
+		build_spec("staging", &[("STAGING_AUTHORITIES", authorities), ("STAGING_SUDO", sudo)])
+			.unwrap();
+	assert_eq!(spec["id"], "staging");
+	assert_eq!(spec["genesis"]["runtime"]["sudo"]["key"], sudo);
+
+	// Without keys there is no staging spec.
+	assert!(build_spec("staging", &[("STAGING_SUDO", sudo)]).is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn build_spec_embeds_a_finalized_checkpoint() {
+	let base_path = tempdir().unwrap();
+	let node = common::DevNode::start(base_path.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 2).await;
+	let genesis_hash = common::block_hash(&rpc, 0).await.unwrap();
+	assert!(node.stop().success());
+
+	let build_spec = |checkpoint: &str| {
+		common::node_command()
+			.args(["build-spec", "--dev", "--disable-default-bootnode", "--checkpoint", checkpoint])
+			.arg("--base-path")
+			.arg(base_path.path())
+			.output()
+			.unwrap()
+	};
+
+	let output = build_spec("1");
+	assert!(output.status.success());
+	let spec: Value = serde_json::from_slice(&output.stdout).unwrap();
+	let checkpoint = &spec["checkpoint"];
+	assert_eq!(checkpoint["header"]["number"], "0x1");
+	assert_eq!(checkpoint["header"]["parentHash"], format!("{:?}", genesis_hash));
+	assert_eq!(checkpoint["setId"], 0);
+	assert_eq!(checkpoint["authorities"].as_array().unwrap().len(), 1);
+
+	// Blocks that aren't finalized can't be checkpoints.
+	assert!(!build_spec("1000").status.success());
+}
@@ -0,0 +1,226 @@
+//! Helpers shared by the tests that run the `node-template` binary.
+
+#![allow(dead_code)]
+
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::client::ClientT,
+	rpc_params,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use nix::{
+	sys::signal::{kill, Signal::SIGINT},
+	unistd::Pid,
+};
+use node_template::{benchmarking, tx};
+use node_template_runtime::{
+	BlockNumber, Hash, Header, Index, RuntimeCall, RuntimeEvent, UncheckedExtrinsic,
+};
+use sp_core::{blake2_128, twox_128, Bytes};
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::traits::Header as _;
+use std::{
+	io::{BufRead, BufReader, Read},
+	ops::{Deref, DerefMut},
+	path::Path,
+	process::{Child, Command, ExitStatus, Stdio},
+	time::Duration,
+};
+
+/// How long a test may wait for the node to make progress.
+pub const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Returns a command running the node binary built for these tests.
+pub fn node_command() -> Command {
+	Command::new(env!("CARGO_BIN_EXE_node-template"))
+}
+
+/// Kills the child process when dropped, so that failing tests don't leave nodes behind.
+pub struct KillChildOnDrop(pub Child);
+
+impl Drop for KillChildOnDrop {
+	fn drop(&mut self) {
+		let _ = self.0.kill();
+		let _ = self.0.wait();
+	}
+}
+
+impl Deref for KillChildOnDrop {
+	type Target = Child;
+
+	fn deref(&self) -> &Child {
+		&self.0
+	}
+}
+
+impl DerefMut for KillChildOnDrop {
+	fn deref_mut(&mut self) -> &mut Child {
+		&mut self.0
+	}
+}
+
+/// A `--dev` node running in a child process.
+pub struct DevNode {
+	child: KillChildOnDrop,
+	/// The address of the node's WebSocket RPC server.
+	pub ws_url: String,
+}
+
+impl DevNode {
+	/// Starts `node-template --dev` on random ports, storing its data in `base_path`.
+	pub fn start(base_path: &Path) -> Self {
+		Self::start_with_args(base_path, &[])
+	}
+
+	/// Like [`Self::start`], passing additional arguments to the node.
+	pub fn start_with_args(base_path: &Path, args: &[&str]) -> Self {
+		let mut child = KillChildOnDrop(
+			node_command()
+				.stdout(Stdio::null())
+				.stderr(Stdio::piped())
+				.args(["--dev", "--port", "0", "--rpc-port", "0", "--ws-port", "0"])
+				.args(["--no-prometheus", "--no-telemetry", "--base-path"])
+				.arg(base_path)
+				.args(args)
+				.spawn()
+				.expect("failed to start the node"),
+		);
+
+		let ws_url = find_ws_url(child.stderr.take().expect("stderr is piped"));
+
+		Self { child, ws_url }
+	}
+
+	/// Connects an RPC client to the node.
+	pub async fn rpc(&self) -> WsClient {
+		WsClientBuilder::default()
+			.build(&self.ws_url)
+			.await
+			.expect("failed to connect to the node")
+	}
+
+	/// Interrupts the node and waits for it to shut down.
+	pub fn stop(mut self) -> ExitStatus {
+		kill(Pid::from_raw(self.child.id() as i32), SIGINT).expect("failed to interrupt the node");
+		self.child.wait().expect("failed to wait for the node")
+	}
+}
+
+/// Reads the node's log until the WebSocket RPC server reports its address.
+fn find_ws_url(stderr: impl Read + Send + 'static) -> String {
+	let mut lines = BufReader::new(stderr).lines();
+
+	let ws_url = lines
+		.by_ref()
+		.find_map(|line| {
+			let line = line.expect("failed to read the node's log");
+			let (_, addr) = line.split_once("Running JSON-RPC WS server: addr=")?;
+			Some(format!("ws://{}", addr.split(',').next()?))
+		})
+		.expect("the node exited before starting its RPC server");
+
+	// Keep draining the log, the node blocks once the pipe is full.
+	std::thread::spawn(move || lines.for_each(drop));
+
+	ws_url
+}
+
+/// Runs the node binary with the given arguments and waits for it to exit.
+pub fn run_node_command(args: &[&str], base_path: &Path) -> ExitStatus {
+	node_command()
+		.args(args)
+		.arg("--base-path")
+		.arg(base_path)
+		.status()
+		.expect("failed to run the node")
+}
+
+/// Returns the header of the best block, or of the block with the given hash.
+pub async fn header(rpc: &WsClient, hash: Option<Hash>) -> Header {
+	rpc.request::<Option<Header>>("chain_getHeader", rpc_params![hash])
+		.await
+		.unwrap()
+		.expect("the block exists")
+}
+
+/// Returns the hash of the block with the given number.
+pub async fn block_hash(rpc: &WsClient, number: BlockNumber) -> Option<Hash> {
+	rpc.request("chain_getBlockHash", rpc_params![number]).await.unwrap()
+}
+
+/// Returns the number of the last finalized block.
+pub async fn finalized_number(rpc: &WsClient) -> BlockNumber {
+	let hash: Hash = rpc.request("chain_getFinalizedHead", rpc_params![]).await.unwrap();
+	*header(rpc, Some(hash)).await.number()
+}
+
+/// Waits until the block with the given number has been finalized.
+pub async fn wait_for_finalized(rpc: &WsClient, number: BlockNumber) {
+	tokio::time::timeout(TIMEOUT, async {
+		while finalized_number(rpc).await < number {
+			tokio::time::sleep(Duration::from_secs(1)).await;
+		}
+	})
+	.await
+	.expect("the node did not finalize blocks in time")
+}
+
+/// Returns the nonce of `who` in the state of the best block, not counting the transactions in
+/// the pool.
+pub async fn account_nonce(rpc: &WsClient, who: Sr25519Keyring) -> Index {
+	let account = who.to_account_id();
+	let account: &[u8] = account.as_ref();
+	let key = [&twox_128(b"System")[..], &twox_128(b"Account"), &blake2_128(account), account];
+	let storage: Option<Bytes> =
+		rpc.request("state_getStorage", rpc_params![Bytes(key.concat())]).await.unwrap();
+
+	// The nonce leads the account info.
+	storage.map_or(0, |info| Index::decode(&mut &info[..]).expect("the account info is valid"))
+}
+
+/// Waits until the chain state has `who`'s nonce at `nonce`.
+pub async fn wait_for_nonce(rpc: &WsClient, who: Sr25519Keyring, nonce: Index) {
+	tokio::time::timeout(TIMEOUT, async {
+		while account_nonce(rpc, who).await < nonce {
+			tokio::time::sleep(Duration::from_secs(1)).await;
+		}
+	})
+	.await
+	.expect("the transactions were not included in time");
+}
+
+/// Returns the events deposited in the block with the given hash.
+pub async fn events(rpc: &WsClient, hash: Hash) -> Vec<RuntimeEvent> {
+	let key = Bytes([twox_128(b"System"), twox_128(b"Events")].concat());
+	let storage: Option<Bytes> =
+		rpc.request("state_getStorage", rpc_params![key, hash]).await.unwrap();
+
+	let records = Vec::<frame_system::EventRecord<RuntimeEvent, Hash>>::decode(
+		&mut &storage.unwrap_or_default()[..],
+	)
+	.expect("events are valid");
+
+	records.into_iter().map(|record| record.event).collect()
+}
+
+/// Signs `call` as `signer`, mortal from the node's current best block.
+pub async fn signed_extrinsic(
+	rpc: &WsClient,
+	signer: Sr25519Keyring,
+	call: RuntimeCall,
+	nonce: Index,
+) -> UncheckedExtrinsic {
+	let genesis_hash = block_hash(rpc, 0).await.expect("genesis exists");
+	let best = header(rpc, None).await;
+
+	let params = benchmarking::signing_params(genesis_hash, nonce, best.number, best.hash());
+
+	tx::sign(call, &signer.pair(), &params)
+}
+
+/// Submits the extrinsic to the node's transaction pool.
+pub async fn submit(rpc: &WsClient, extrinsic: &UncheckedExtrinsic) -> Hash {
+	rpc.request("author_submitExtrinsic", rpc_params![Bytes(extrinsic.encode())])
+		.await
+		.expect("the extrinsic is accepted by the pool")
+}
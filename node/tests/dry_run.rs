@@ -0,0 +1,87 @@
+use codec::Encode;
+use jsonrpsee::{core::client::ClientT, rpc_params};
+use node_template::rpc::dry_run::DryRunApiClient;
+use node_template_runtime::{pallet_template, BalancesCall, Index, RuntimeCall};
+use sp_core::{crypto::Ss58Codec, twox_128, Bytes};
+use sp_keyring::Sr25519Keyring::{Alice, Bob};
+use sp_runtime::{
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	DispatchError,
+};
+use tempfile::tempdir;
+
+pub mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dry_run_reports_the_effects_of_calls_without_keeping_them() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	let outcome = rpc.call(call.encode().into(), Some(Alice.to_account_id()), None).await.unwrap();
+	assert_eq!(outcome.result, Ok(Ok(())));
+	assert_eq!(outcome.events.len(), 1);
+	assert_eq!(outcome.events[0].name, "SomethingStored");
+	assert!(outcome.weight > 0);
+
+	let key = Bytes([twox_128(b"TemplateModule"), twox_128(b"Something")].concat());
+	assert!(outcome.touched_keys.contains(&key));
+	let stored: Option<Bytes> = rpc.request("state_getStorage", rpc_params![key]).await.unwrap();
+	assert_eq!(stored, None);
+
+	// Failed calls are reported as such, and Root is the default origin.
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::cause_error {});
+	let outcome = rpc.call(call.encode().into(), None, None).await.unwrap();
+	assert!(matches!(outcome.result, Ok(Err(DispatchError::BadOrigin))));
+	let outcome = rpc.call(call.encode().into(), Some(Alice.to_account_id()), None).await.unwrap();
+	assert!(matches!(outcome.result, Ok(Err(DispatchError::Module(_)))));
+	assert!(outcome.events.is_empty());
+
+	assert!(rpc.call(vec![0xff].into(), None, None).await.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dry_run_applies_extrinsics_with_their_checks() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let transfer = RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+		dest: Bob.to_account_id().into(),
+		value: 1_000_000,
+	});
+	let extrinsic = common::signed_extrinsic(&rpc, Alice, transfer.clone(), 0).await;
+	let outcome = rpc.extrinsic(extrinsic.encode().into(), None).await.unwrap();
+	assert_eq!(outcome.result, Ok(Ok(())));
+	let names: Vec<_> = outcome.events.iter().map(|event| event.name.as_str()).collect();
+	assert!(names.contains(&"Transfer"), "{:?}", names);
+	assert_eq!(names.last(), Some(&"ExtrinsicSuccess"));
+	assert!(outcome.weight > 0);
+
+	// The nonce was only bumped in the dry run.
+	let nonce: Index = rpc
+		.request("system_accountNextIndex", rpc_params![Alice.to_account_id().to_ss58check()])
+		.await
+		.unwrap();
+	assert_eq!(nonce, 0);
+
+	let stale = common::signed_extrinsic(&rpc, Alice, transfer, 5).await;
+	let outcome = rpc.extrinsic(stale.encode().into(), None).await.unwrap();
+	assert_eq!(outcome.result, Err(TransactionValidityError::Invalid(InvalidTransaction::Future)));
+	assert!(outcome.events.is_empty());
+	assert_eq!(outcome.weight, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dry_runs_are_unsafe() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start_with_args(dir.path(), &["--rpc-methods", "safe"]);
+	let rpc = node.rpc().await;
+
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	assert!(rpc.call(call.encode().into(), Some(Alice.to_account_id()), None).await.is_err());
+	assert!(rpc.extrinsic(call.encode().into(), None).await.is_err());
+}
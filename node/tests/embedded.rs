@@ -0,0 +1,32 @@
+use futures::channel::oneshot;
+use jsonrpsee::RpcModule;
+use node_template::{service::FullNodeBuilder, test_utils};
+use serde_json::json;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn embedded_dev_node_runs_extensions() {
+	let config = test_utils::dev_config(tokio::runtime::Handle::current()).unwrap();
+	let (imported_tx, imported_rx) = oneshot::channel();
+
+	let node = FullNodeBuilder::new(config)
+		.with_rpc(|_| {
+			let mut module = RpcModule::new(());
+			module.register_method("test_ping", |_, _| Ok("pong"))?;
+			Ok(module)
+		})
+		.with_task("test-block-watcher", |node| {
+			let client = node.client.clone();
+			async move {
+				let best = test_utils::wait_for_blocks(&client, 2).await;
+				let _ = imported_tx.send(best);
+			}
+		})
+		.build()
+		.unwrap();
+
+	assert!(imported_rx.await.unwrap() >= 2);
+	assert_eq!(
+		test_utils::rpc_call(&node.rpc_handlers, "test_ping", json!([])).await,
+		Ok(json!("pong"))
+	);
+}
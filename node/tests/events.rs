@@ -0,0 +1,74 @@
+use futures::{FutureExt, StreamExt};
+use jsonrpsee::core::client::Subscription;
+use node_template::rpc::events::{EventFilter, EventNotification, EventsApiClient};
+use node_template_runtime::{pallet_template, BalancesCall, RuntimeCall};
+use serde_json::json;
+use sp_core::crypto::Ss58Codec;
+use sp_keyring::Sr25519Keyring::{Alice, Bob, Charlie};
+use tempfile::tempdir;
+
+pub mod common;
+
+async fn next(subscription: &mut Subscription<EventNotification>) -> EventNotification {
+	tokio::time::timeout(common::TIMEOUT, subscription.next())
+		.await
+		.expect("no event in time")
+		.expect("the subscription is open")
+		.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn events_subscription_filters_and_reports_finality() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let filter = |pallet: &str, name: &str, account| EventFilter {
+		pallet: Some(pallet.into()),
+		name: Some(name.into()),
+		account: Some(account),
+	};
+	let mut stored = rpc
+		.subscribe(Some(filter("TemplateModule", "SomethingStored", Alice.to_account_id())))
+		.await
+		.unwrap();
+	let mut transfers = rpc
+		.subscribe(Some(filter("Balances", "Transfer", Bob.to_account_id())))
+		.await
+		.unwrap();
+	let mut unrelated = rpc
+		.subscribe(Some(filter("Balances", "Transfer", Charlie.to_account_id())))
+		.await
+		.unwrap();
+
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	common::submit(&rpc, &common::signed_extrinsic(&rpc, Alice, call, 0).await).await;
+	let transfer = RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+		dest: Bob.to_account_id().into(),
+		value: 1_000_000,
+	});
+	common::submit(&rpc, &common::signed_extrinsic(&rpc, Alice, transfer, 1).await).await;
+
+	let best = next(&mut stored).await;
+	assert!(!best.finalized);
+	assert!(!best.retracted);
+	assert_eq!(best.event.name, "SomethingStored");
+	assert_eq!(
+		best.event.args,
+		json!({ "something": 7, "who": Alice.to_account_id().to_ss58check() })
+	);
+	assert!(best.extrinsic_index.is_some());
+
+	let finalized = next(&mut stored).await;
+	assert!(finalized.finalized);
+	assert_eq!((finalized.block_hash, finalized.event), (best.block_hash, best.event));
+
+	let transfer = next(&mut transfers).await;
+	assert_eq!(transfer.event.pallet, "Balances");
+	assert_eq!(transfer.event.args["to"], json!(Bob.to_account_id().to_ss58check()));
+
+	// Charlie's subscription stays silent.
+	assert!(next(&mut transfers).await.finalized);
+	assert!(unrelated.next().now_or_never().is_none());
+}
@@ -0,0 +1,53 @@
+use serde_json::Value;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn export(to: u32, dir: &Path) -> Vec<Value> {
+	let status = common::node_command()
+		.args(["export-events", "--dev", "--to", &to.to_string(), "--base-path"])
+		.arg(dir.join("node"))
+		.arg("--output")
+		.arg(dir.join("events.jsonl"))
+		.arg("--cursor")
+		.arg(dir.join("cursor"))
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	fs::read_to_string(dir.join("events.jsonl"))
+		.unwrap()
+		.lines()
+		.map(|line| serde_json::from_str(line).unwrap())
+		.collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn export_events_resumes_from_the_cursor() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(&dir.path().join("node"));
+	common::wait_for_finalized(&node.rpc().await, 3).await;
+	assert!(node.stop().success());
+
+	let lines = export(2, dir.path());
+	assert!(lines.iter().all(|line| line["blockNumber"].as_u64().unwrap() <= 2));
+	assert_eq!(fs::read_to_string(dir.path().join("cursor")).unwrap(), "2");
+
+	let timestamp_set = lines
+		.iter()
+		.find(|line| line["kind"] == "extrinsic" && line["blockNumber"] == 1)
+		.expect("block #1 sets the timestamp");
+	assert_eq!(timestamp_set["pallet"], "Timestamp");
+	assert_eq!(timestamp_set["name"], "set");
+	assert_eq!(timestamp_set["args"]["now"], timestamp_set["timestamp"]);
+	assert!(lines.iter().any(|line| line["kind"] == "event" &&
+		line["pallet"] == "System" &&
+		line["name"] == "ExtrinsicSuccess"));
+
+	// The second run only appends the blocks after the cursor.
+	let all = export(3, dir.path());
+	assert_eq!(all[..lines.len()], lines[..]);
+	assert!(all[lines.len()..].iter().all(|line| line["blockNumber"] == 3));
+	assert!(all.len() > lines.len());
+}
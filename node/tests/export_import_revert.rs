@@ -0,0 +1,44 @@
+use tempfile::tempdir;
+
+pub mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn export_import_revert() {
+	let base_path = tempdir().unwrap();
+	let exported = base_path.path().join("exported_blocks");
+	let reexported = base_path.path().join("reexported_blocks");
+	let reverted = base_path.path().join("reverted_blocks");
+	let expected = base_path.path().join("expected_blocks");
+	let source = base_path.path().join("source");
+	let target = base_path.path().join("target");
+
+	let node = common::DevNode::start(&source);
+	common::wait_for_finalized(&node.rpc().await, 3).await;
+	assert!(node.stop().success());
+
+	// Exports up to block `to`, or up to the best block if `None`.
+	let export = |base_path: &std::path::Path, file: &std::path::Path, to: Option<&str>| {
+		let mut args = vec!["export-blocks", "--dev", file.to_str().unwrap()];
+		if let Some(to) = to {
+			args.extend(["--to", to]);
+		}
+		common::run_node_command(&args, base_path)
+	};
+
+	assert!(export(&source, &exported, Some("3")).success());
+	assert!(common::run_node_command(
+		&["import-blocks", "--dev", exported.to_str().unwrap()],
+		&target
+	)
+	.success());
+
+	// The imported chain exports to exactly the same blocks.
+	assert!(export(&target, &reexported, None).success());
+	assert_eq!(std::fs::read(&exported).unwrap(), std::fs::read(&reexported).unwrap());
+
+	// After reverting a block, the best block is #2.
+	assert!(common::run_node_command(&["revert", "--dev", "1"], &target).success());
+	assert!(export(&target, &reverted, None).success());
+	assert!(export(&source, &expected, Some("2")).success());
+	assert_eq!(std::fs::read(&reverted).unwrap(), std::fs::read(&expected).unwrap());
+}
@@ -0,0 +1,129 @@
+use codec::Encode;
+use jsonrpsee::{
+	core::{client::ClientT, Error},
+	rpc_params,
+	types::error::CallError,
+	ws_client::WsClient,
+};
+use node_template::rpc::{account::AccountApiClient, txpool::TxPoolApiClient};
+use node_template_runtime::{
+	pallet_feeless, pallet_template, BalancesCall, Index, RuntimeCall, SudoCall, SystemCall,
+	TemplateModulePriorityBoost, EXISTENTIAL_DEPOSIT,
+};
+use sp_core::{Bytes, H256};
+use sp_keyring::Sr25519Keyring::{self, Alice, Charlie, Dave, Eve};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// The error code of transactions the pool rejects as invalid.
+const POOL_INVALID_TX: i32 = 1010;
+
+/// The free transactions per period the runtime allows.
+const FREE_TRANSACTIONS: Index = 10;
+
+fn sudo(call: pallet_feeless::Call<node_template_runtime::Runtime>) -> RuntimeCall {
+	RuntimeCall::Sudo(SudoCall::sudo { call: Box::new(RuntimeCall::Feeless(call)) })
+}
+
+fn allow_do_something() -> RuntimeCall {
+	sudo(pallet_feeless::Call::allow_call {
+		pallet: b"TemplateModule".to_vec().try_into().unwrap(),
+		call: b"do_something".to_vec().try_into().unwrap(),
+	})
+}
+
+/// Creates the account of `who` with the existential deposit, which can't pay any fee.
+fn create_account(who: Sr25519Keyring) -> RuntimeCall {
+	RuntimeCall::Balances(BalancesCall::transfer {
+		dest: who.to_account_id().into(),
+		value: EXISTENTIAL_DEPOSIT,
+	})
+}
+
+/// Submits the extrinsic, returning the code it is rejected with.
+async fn rejection(rpc: &WsClient, extrinsic: Vec<u8>) -> i32 {
+	let rejected = rpc
+		.request::<H256>("author_submitExtrinsic", rpc_params![Bytes(extrinsic)])
+		.await
+		.unwrap_err();
+	match rejected {
+		Error::Call(CallError::Custom(error)) => error.code(),
+		error => panic!("unexpected error: {:?}", error),
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn allowlisted_transactions_are_feeless_within_the_quota() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	// Neither Charlie nor Dave has funds to pay fees with.
+	let allow_charlie = sudo(pallet_feeless::Call::allow_account { who: Charlie.to_account_id() });
+	let calls =
+		[allow_charlie, allow_do_something(), create_account(Charlie), create_account(Dave)];
+	for (nonce, call) in (0..).zip(calls) {
+		common::submit(&rpc, &common::signed_extrinsic(&rpc, Alice, call, nonce).await).await;
+	}
+	common::wait_for_nonce(&rpc, Alice, 4).await;
+
+	// Allowlisted calls are feeless for everyone, other calls are not.
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	common::submit(&rpc, &common::signed_extrinsic(&rpc, Dave, call, 0).await).await;
+	let remark = || RuntimeCall::System(SystemCall::remark { remark: b"free".to_vec() });
+	let paid = common::signed_extrinsic(&rpc, Dave, remark(), 1).await;
+	assert_eq!(rejection(&rpc, paid.encode()).await, POOL_INVALID_TX);
+
+	// Allowlisted accounts are feeless whatever they call, until the quota is spent, pending
+	// transactions included.
+	for nonce in 0..FREE_TRANSACTIONS {
+		common::submit(&rpc, &common::signed_extrinsic(&rpc, Charlie, remark(), nonce).await).await;
+	}
+	let pending = common::signed_extrinsic(&rpc, Charlie, remark(), FREE_TRANSACTIONS).await;
+	assert_eq!(rejection(&rpc, pending.encode()).await, POOL_INVALID_TX);
+	common::wait_for_nonce(&rpc, Charlie, FREE_TRANSACTIONS).await;
+	common::wait_for_nonce(&rpc, Dave, 1).await;
+
+	let beyond = common::signed_extrinsic(&rpc, Charlie, remark(), FREE_TRANSACTIONS).await;
+	assert_eq!(rejection(&rpc, beyond.encode()).await, POOL_INVALID_TX);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn feeless_transactions_provide_their_sender_and_get_no_boost() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	common::submit(&rpc, &common::signed_extrinsic(&rpc, Alice, allow_do_something(), 0).await)
+		.await;
+	common::wait_for_nonce(&rpc, Alice, 1).await;
+
+	// Eve has no account, yet its allowlisted call is feeless and provides the account, which
+	// keeps its nonce.
+	let summary = rpc.summary(Eve.to_account_id(), None).await.unwrap();
+	assert_eq!(summary.warning.as_deref(), Some("notExisting"));
+	let call = || RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	common::submit(&rpc, &common::signed_extrinsic(&rpc, Eve, call(), 0).await).await;
+	common::wait_for_nonce(&rpc, Eve, 1).await;
+	let summary = rpc.summary(Eve.to_account_id(), None).await.unwrap();
+	assert_eq!(summary.warning.as_deref(), Some("freeBelowExistentialDeposit"));
+
+	// Other calls still need funds, which Eve doesn't have.
+	let remark = RuntimeCall::System(SystemCall::remark { remark: b"paid".to_vec() });
+	let paid = common::signed_extrinsic(&rpc, Eve, remark, 1).await;
+	assert_eq!(rejection(&rpc, paid.encode()).await, POOL_INVALID_TX);
+
+	// Nonce 2 keeps Alice's feeless call in the future queue, out of any block.
+	let feeless =
+		common::submit(&rpc, &common::signed_extrinsic(&rpc, Alice, call(), 2).await).await;
+	let inspection = rpc.inspect().await.unwrap();
+	let feeless = inspection
+		.future
+		.iter()
+		.find(|tx| tx.hash == feeless)
+		.expect("the transaction is pooled");
+	assert!(feeless.priority < TemplateModulePriorityBoost::get());
+}
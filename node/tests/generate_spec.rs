@@ -0,0 +1,76 @@
+use serde_json::Value;
+use std::fs;
+use tempfile::tempdir;
+
+pub mod common;
+
+const DESCRIPTION: &str = r#"
+name = "Generated"
+id = "generated"
+chain_type = "Local"
+
+sudo = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+
+[properties]
+tokenSymbol = "GEN"
+tokenDecimals = 12
+
+[[authorities]]
+aura = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+grandpa = "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu"
+
+[[endowed]]
+account = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+balance = "1_000_000_000_000_000_000_000"
+"#;
+
+#[test]
+fn generate_spec_writes_plain_and_raw_specs() {
+	let dir = tempdir().unwrap();
+	let description = dir.path().join("network.toml");
+	fs::write(&description, DESCRIPTION).unwrap();
+
+	let status = common::node_command()
+		.arg("generate-spec")
+		.arg(&description)
+		.arg("--output-dir")
+		.arg(dir.path())
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	let plain: Value =
+		serde_json::from_slice(&fs::read(dir.path().join("generated.json")).unwrap()).unwrap();
+	assert_eq!(plain["id"], "generated");
+	assert_eq!(plain["chainType"], "Local");
+	assert_eq!(plain["properties"]["tokenSymbol"], "GEN");
+	assert_eq!(plain["consensus"], "authorities");
+	assert!(plain["genesis"]["runtime"].is_object());
+
+	let raw: Value =
+		serde_json::from_slice(&fs::read(dir.path().join("generated-raw.json")).unwrap()).unwrap();
+	assert!(raw["genesis"]["raw"]["top"].is_object());
+}
+
+#[test]
+fn generate_spec_rejects_invalid_descriptions() {
+	let dir = tempdir().unwrap();
+	let description = dir.path().join("network.toml");
+	// Endowing the same account twice is a mistake serde doesn't catch.
+	let duplicate = r#"
+[[endowed]]
+account = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+balance = 1000
+"#;
+	fs::write(&description, format!("{}{}", DESCRIPTION, duplicate)).unwrap();
+
+	let status = common::node_command()
+		.arg("generate-spec")
+		.arg(&description)
+		.arg("--output-dir")
+		.arg(dir.path())
+		.status()
+		.unwrap();
+	assert!(!status.success());
+	assert!(!dir.path().join("generated.json").exists());
+}
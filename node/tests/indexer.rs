@@ -0,0 +1,90 @@
+use node_template::rpc::indexer::IndexerApiClient;
+use node_template_runtime::{pallet_template, BalancesCall, RuntimeCall};
+use serde_json::json;
+use sp_core::crypto::Ss58Codec;
+use sp_keyring::Sr25519Keyring::{Alice, Bob};
+use std::time::Duration;
+use tempfile::tempdir;
+
+pub mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn indexer_serves_finalized_transfers_and_template_events() {
+	let dir = tempdir().unwrap();
+	let index = dir.path().join("index.sqlite");
+	let node = common::DevNode::start_with_args(
+		&dir.path().join("node"),
+		&["--indexer-db", index.to_str().unwrap()],
+	);
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let transfer = RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+		dest: Bob.to_account_id().into(),
+		value: 1_000_000,
+	});
+	common::submit(&rpc, &common::signed_extrinsic(&rpc, Alice, transfer, 0).await).await;
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	common::submit(&rpc, &common::signed_extrinsic(&rpc, Alice, call, 1).await).await;
+
+	let transfers = tokio::time::timeout(common::TIMEOUT, async {
+		loop {
+			let transfers = rpc.transfers(Bob.to_account_id(), None).await.unwrap();
+			if transfers.iter().any(|transfer| transfer.finalized) {
+				break transfers
+			}
+			tokio::time::sleep(Duration::from_secs(1)).await;
+		}
+	})
+	.await
+	.expect("the transfer was not indexed in time");
+
+	let alice = Alice.to_account_id().to_ss58check();
+	assert_eq!(transfers.len(), 1);
+	assert_eq!(transfers[0].from, alice);
+	assert_eq!(transfers[0].to, Bob.to_account_id().to_ss58check());
+	assert_eq!(transfers[0].amount, 1_000_000);
+
+	let status = rpc.status().await.unwrap();
+	assert!(status.last_finalized >= Some(transfers[0].block_number));
+
+	let extrinsics = rpc.extrinsics(Some(Alice.to_account_id()), None).await.unwrap();
+	let indexed = extrinsics
+		.iter()
+		.find(|extrinsic| {
+			extrinsic.block_number == transfers[0].block_number &&
+				extrinsic.name == "transfer_keep_alive"
+		})
+		.expect("the transfer extrinsic is indexed");
+	assert_eq!(indexed.pallet, "Balances");
+	assert!(indexed.success);
+	assert!(indexed.fee.unwrap() > 0);
+
+	let stored = tokio::time::timeout(common::TIMEOUT, async {
+		loop {
+			match rpc.template_events(Some(Alice.to_account_id()), None).await.unwrap().pop() {
+				Some(event) => break event,
+				None => tokio::time::sleep(Duration::from_secs(1)).await,
+			}
+		}
+	})
+	.await
+	.expect("the template event was not indexed in time");
+	assert_eq!(stored.name, "SomethingStored");
+	assert_eq!(stored.args, json!({ "something": 7, "who": alice }));
+
+	let events = rpc.events("TemplateModule".into(), None, Some(10)).await.unwrap();
+	assert!(events.iter().any(|event| event.args == stored.args));
+
+	assert!(rpc.transfers(Bob.to_account_id(), Some(100_000)).await.is_err());
+}
+
+#[test]
+fn indexer_backfill_requires_an_archive_node() {
+	let dir = tempdir().unwrap();
+	let index = dir.path().join("index.sqlite");
+	let args = ["--dev", "--indexer-db", index.to_str().unwrap(), "--indexer-backfill"];
+
+	assert!(!common::run_node_command(&args, &dir.path().join("node")).success());
+	assert!(!index.exists());
+}
@@ -0,0 +1,84 @@
+use node_template_runtime::{pallet_template, RuntimeCall, RuntimeEvent};
+use serde_json::{json, Value};
+use sp_core::crypto::Ss58Codec;
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::traits::Header as _;
+use std::path::Path;
+use tempfile::tempdir;
+
+pub mod common;
+
+fn inspect(args: &[&str], base_path: &Path) -> String {
+	let output = common::node_command()
+		.arg("inspect")
+		.args(args)
+		.args(["--dev", "--base-path"])
+		.arg(base_path)
+		.output()
+		.unwrap();
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+	String::from_utf8(output.stdout).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn inspect_decodes_blocks_and_extrinsics() {
+	let base_path = tempdir().unwrap();
+	let node = common::DevNode::start(base_path.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let from = *common::header(&rpc, None).await.number();
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 42 });
+	let extrinsic = common::signed_extrinsic(&rpc, Sr25519Keyring::Alice, call, 0).await;
+	common::submit(&rpc, &extrinsic).await;
+
+	let expected = RuntimeEvent::TemplateModule(pallet_template::Event::SomethingStored {
+		something: 42,
+		who: Sr25519Keyring::Alice.to_account_id(),
+	});
+	let included = tokio::time::timeout(common::TIMEOUT, async {
+		let mut number = from;
+		loop {
+			match common::block_hash(&rpc, number).await {
+				Some(hash) if common::events(&rpc, hash).await.contains(&expected) => break number,
+				Some(_) => number += 1,
+				None => tokio::time::sleep(std::time::Duration::from_secs(1)).await,
+			}
+		}
+	})
+	.await
+	.expect("the extrinsic was not included in time");
+
+	drop(rpc);
+	assert!(node.stop().success());
+
+	let block: Value = serde_json::from_str(&inspect(
+		&["--json", "block", &included.to_string()],
+		base_path.path(),
+	))
+	.unwrap();
+	assert_eq!(block["number"], included);
+
+	let alice = Sr25519Keyring::Alice.to_account_id().to_ss58check();
+	let extrinsic = block["extrinsics"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.find(|extrinsic| extrinsic["signer"] == alice.as_str())
+		.expect("the block contains Alice's extrinsic");
+	assert_eq!(
+		extrinsic["call"],
+		json!({ "pallet": "TemplateModule", "name": "do_something", "args": { "something": 42 } })
+	);
+	assert_eq!(extrinsic["signedExtra"]["CheckNonce"], 0);
+	assert!(extrinsic["events"].as_array().unwrap().contains(&json!({
+		"pallet": "TemplateModule",
+		"name": "SomethingStored",
+		"args": { "something": 42, "who": alice },
+	})));
+
+	let index = extrinsic["index"].as_u64().unwrap();
+	let text = inspect(&["extrinsic", &format!("{}:{}", included, index)], base_path.path());
+	assert!(text.contains(&format!("signer: {}", alice)));
+	assert!(text.contains("call: TemplateModule::do_something {\"something\":42}"));
+}
@@ -0,0 +1,36 @@
+use tempfile::tempdir;
+
+pub mod common;
+
+#[test]
+fn load_reports_included_and_finalized_transactions() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(&dir.path().join("node"));
+
+	for workload in ["transfer", "do-something"] {
+		let output = common::node_command()
+			.args(["load", "--rpc-url", &node.ws_url, "--workload", workload])
+			.args(["--accounts", "4", "--rate", "5", "--duration", "10", "--wait", "60"])
+			.output()
+			.unwrap();
+		assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+		let stdout = String::from_utf8(output.stdout).unwrap();
+		assert!(stdout.contains("Funding 4 senders"), "{}", stdout);
+		assert!(stdout.contains("0 rejected by the pool"), "{}", stdout);
+		assert!(stdout.contains("still pending 0"), "{}", stdout);
+		assert!(stdout.contains("Inclusion latency: min"), "{}", stdout);
+		assert!(stdout.contains("Finality latency: min"), "{}", stdout);
+		assert!(stdout.contains("Achieved TPS: "), "{}", stdout);
+	}
+}
+
+#[test]
+fn load_fails_without_a_node() {
+	let output = common::node_command()
+		.args(["load", "--rpc-url", "ws://127.0.0.1:1", "--duration", "1"])
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+	assert!(String::from_utf8_lossy(&output.stderr).contains("Error connecting"));
+}
@@ -0,0 +1,101 @@
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::{client::ClientT, Error},
+	rpc_params,
+	types::error::CallError,
+	ws_client::WsClient,
+};
+use node_template::{benchmarking, tx};
+use node_template_runtime::{
+	extensions::METADATA_HASH, pallet_template, RuntimeCall, UncheckedExtrinsic,
+};
+use sp_core::{Bytes, H256};
+use sp_keyring::Sr25519Keyring::{Alice, Bob};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// The error code of transactions the pool rejects as invalid.
+const POOL_INVALID_TX: i32 = 1010;
+
+/// Calls the runtime API `method` at the best block and decodes its result.
+async fn state_call<T: Decode>(rpc: &WsClient, method: &str, args: Vec<u8>) -> T {
+	let result: Bytes = rpc.request("state_call", rpc_params![method, Bytes(args)]).await.unwrap();
+	T::decode(&mut &result[..]).unwrap()
+}
+
+#[cfg(feature = "metadata-hash")]
+#[tokio::test(flavor = "multi_thread")]
+async fn proofs_verify_against_the_metadata_hash() {
+	use frame_metadata::RuntimeMetadataPrefixed;
+	use metadata_hash::MetadataProof;
+	use node_template_runtime::VERSION;
+
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let hash: Option<[u8; 32]> = state_call(&rpc, "MetadataHashApi_metadata_hash", vec![]).await;
+	assert!(hash.is_some());
+	assert_eq!(hash, METADATA_HASH);
+
+	// The hash the build computed is the one of the metadata the node serves.
+	let metadata: Bytes = rpc.request("state_getMetadata", rpc_params![]).await.unwrap();
+	let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..]).unwrap();
+	let metadata = metadata_hash::v14(&metadata).unwrap();
+	assert_eq!(
+		Some(metadata_hash::metadata_hash(metadata, &VERSION.spec_name, VERSION.spec_version)),
+		hash
+	);
+
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	let extrinsic = UncheckedExtrinsic::new_unsigned(call);
+	let proof: Option<MetadataProof> =
+		state_call(&rpc, "MetadataHashApi_metadata_proof", extrinsic.encode()).await;
+	let proof = proof.expect("the call decodes");
+	assert_eq!(proof.verify(), hash);
+	assert!(proof.leaves.len() < proof.leaf_count as usize);
+}
+
+#[cfg(not(feature = "metadata-hash"))]
+#[tokio::test(flavor = "multi_thread")]
+async fn runtimes_built_without_the_feature_have_no_hash() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let hash: Option<[u8; 32]> = state_call(&rpc, "MetadataHashApi_metadata_hash", vec![]).await;
+	assert_eq!(hash, None);
+	assert_eq!(METADATA_HASH, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn transactions_must_commit_to_the_runtime_metadata() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let genesis_hash = common::block_hash(&rpc, 0).await.expect("genesis exists");
+	let best = common::header(&rpc, None).await;
+	let params = benchmarking::signing_params(genesis_hash, 0, best.number, best.hash());
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+
+	// A signer decoding with other metadata signs over another hash.
+	let forged =
+		tx::sign(call.clone(), &Alice.pair(), &params.clone().metadata_hash(Some([0; 32])));
+	let rejected = rpc
+		.request::<H256>("author_submitExtrinsic", rpc_params![Bytes(forged.encode())])
+		.await
+		.unwrap_err();
+	match rejected {
+		Error::Call(CallError::Custom(error)) => assert_eq!(error.code(), POOL_INVALID_TX),
+		error => panic!("unexpected error: {:?}", error),
+	}
+
+	common::submit(&rpc, &tx::sign(call.clone(), &Alice.pair(), &params)).await;
+	// Committing to no hash stays possible, for signers that don't check it.
+	common::submit(&rpc, &tx::sign(call, &Bob.pair(), &params.metadata_hash(None))).await;
+}
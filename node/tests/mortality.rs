@@ -0,0 +1,174 @@
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::{client::ClientT, Error},
+	rpc_params,
+	types::error::CallError,
+	ws_client::WsClient,
+};
+use node_template::{rpc::account::AccountApiClient, tx};
+use node_template_runtime::{
+	extensions::{IMMORTAL, MORTALITY_TOO_LONG},
+	BalancesCall, BlockNumber, Hash, MaxMortality, RuntimeCall, SystemCall, UncheckedExtrinsic,
+};
+use sp_core::{Bytes, H256};
+use sp_keyring::Sr25519Keyring::{self, Alice, Bob, Charlie};
+use sp_runtime::{
+	generic::Era,
+	traits::Header as _,
+	transaction_validity::{
+		InvalidTransaction, TransactionSource, TransactionValidity, TransactionValidityError,
+	},
+};
+use std::time::Duration;
+use tempfile::tempdir;
+
+pub mod common;
+
+/// The error code of transactions the pool rejects as invalid.
+const POOL_INVALID_TX: i32 = 1010;
+
+/// The shortest era.
+const PERIOD: u64 = 4;
+
+const ENDOWMENT: u128 = 1_000_000_000_000;
+
+/// Asserts the pool rejects `extrinsic` as invalid.
+async fn assert_rejected(rpc: &WsClient, extrinsic: &UncheckedExtrinsic) {
+	let rejected = rpc
+		.request::<H256>("author_submitExtrinsic", rpc_params![Bytes(extrinsic.encode())])
+		.await
+		.unwrap_err();
+	match rejected {
+		Error::Call(CallError::Custom(error)) => assert_eq!(error.code(), POOL_INVALID_TX),
+		error => panic!("unexpected error: {:?}", error),
+	}
+}
+
+/// Validates `extrinsic` on top of the best block, bypassing the pool, which bans included
+/// transactions for a while.
+async fn validate(rpc: &WsClient, extrinsic: &UncheckedExtrinsic) -> TransactionValidity {
+	let best: Hash = common::header(rpc, None).await.hash();
+	let args = (TransactionSource::External, extrinsic, best).encode();
+	let result: Bytes = rpc
+		.request(
+			"state_call",
+			rpc_params!["TaggedTransactionQueue_validate_transaction", Bytes(args)],
+		)
+		.await
+		.unwrap();
+	TransactionValidity::decode(&mut &result[..]).unwrap()
+}
+
+/// The result of validating transactions rejected with `error`.
+fn invalid(error: InvalidTransaction) -> TransactionValidity {
+	Err(TransactionValidityError::Invalid(error))
+}
+
+/// Signs `call` as `signer`, valid for `period` blocks from the best block, or immortal if
+/// `None`.
+async fn extrinsic(
+	rpc: &WsClient,
+	signer: Sr25519Keyring,
+	call: RuntimeCall,
+	nonce: u32,
+	period: Option<u64>,
+) -> UncheckedExtrinsic {
+	let genesis_hash = common::block_hash(rpc, 0).await.expect("genesis exists");
+	let best = common::header(rpc, None).await;
+	let params = match period {
+		Some(period) => {
+			let era = Era::mortal(period, best.number.into());
+			tx::SigningParams::new(genesis_hash, nonce, era, best.hash())
+		},
+		None => tx::SigningParams::new(genesis_hash, nonce, Era::Immortal, genesis_hash),
+	};
+
+	tx::sign(call, &signer.pair(), &params)
+}
+
+/// Has Alice send Charlie [`ENDOWMENT`], creating their account, and waits until Charlie has it.
+async fn fund_charlie(rpc: &WsClient, nonce: u32) {
+	let call = RuntimeCall::Balances(BalancesCall::transfer {
+		dest: Charlie.to_account_id().into(),
+		value: ENDOWMENT,
+	});
+	common::submit(rpc, &common::signed_extrinsic(rpc, Alice, call, nonce).await).await;
+	wait_for_free(rpc, Charlie, |free| free > 0).await;
+}
+
+/// Waits until the free balance of `who` satisfies `done`.
+async fn wait_for_free(rpc: &WsClient, who: Sr25519Keyring, done: impl Fn(u128) -> bool) {
+	tokio::time::timeout(common::TIMEOUT, async {
+		while !done(rpc.summary(who.to_account_id(), None).await.unwrap().free) {
+			tokio::time::sleep(Duration::from_secs(1)).await;
+		}
+	})
+	.await
+	.expect("the free balance did not change in time");
+}
+
+/// Waits until the best block is at least `number`.
+async fn wait_for_best(rpc: &WsClient, number: BlockNumber) {
+	tokio::time::timeout(common::TIMEOUT, async {
+		while common::header(rpc, None).await.number < number {
+			tokio::time::sleep(Duration::from_secs(1)).await;
+		}
+	})
+	.await
+	.expect("the chain did not advance in time");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn immortal_and_overlong_transactions_are_rejected() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let remark = RuntimeCall::System(SystemCall::remark { remark: b"era".to_vec() });
+	let immortal = extrinsic(&rpc, Alice, remark.clone(), 0, None).await;
+	assert_eq!(validate(&rpc, &immortal).await, invalid(InvalidTransaction::Custom(IMMORTAL)));
+	assert_rejected(&rpc, &immortal).await;
+	let overlong = extrinsic(&rpc, Alice, remark.clone(), 0, Some(MaxMortality::get() * 2)).await;
+	assert_eq!(
+		validate(&rpc, &overlong).await,
+		invalid(InvalidTransaction::Custom(MORTALITY_TOO_LONG))
+	);
+	assert_rejected(&rpc, &overlong).await;
+
+	let longest = extrinsic(&rpc, Alice, remark, 0, Some(MaxMortality::get())).await;
+	common::submit(&rpc, &longest).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn transactions_cant_be_replayed_after_reaping() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	fund_charlie(&rpc, 0).await;
+
+	// Charlie empties and so reaps their account.
+	let empty = RuntimeCall::Balances(BalancesCall::transfer_all {
+		dest: Bob.to_account_id().into(),
+		keep_alive: false,
+	});
+	let emptying = extrinsic(&rpc, Charlie, empty.clone(), 0, Some(PERIOD)).await;
+	// Had Charlie signed it immortal, it would stay valid forever; it's rejected instead.
+	let immortal = extrinsic(&rpc, Charlie, empty, 0, None).await;
+	assert_eq!(validate(&rpc, &immortal).await, invalid(InvalidTransaction::Custom(IMMORTAL)));
+
+	let birth = common::header(&rpc, None).await.number;
+	common::submit(&rpc, &emptying).await;
+	wait_for_free(&rpc, Charlie, |free| free == 0).await;
+	assert_eq!(rpc.summary(Charlie.to_account_id(), None).await.unwrap().nonce, 0);
+
+	// Recreated, the account starts over at nonce zero, but the era of the old transaction is
+	// over.
+	wait_for_best(&rpc, birth + PERIOD as BlockNumber).await;
+	fund_charlie(&rpc, 1).await;
+	assert_eq!(rpc.summary(Charlie.to_account_id(), None).await.unwrap().nonce, 0);
+	// The era's first block is another one now, so the signature doesn't match.
+	assert_eq!(validate(&rpc, &emptying).await, invalid(InvalidTransaction::BadProof));
+}
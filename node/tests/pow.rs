@@ -0,0 +1,28 @@
+use tempfile::tempdir;
+
+pub mod common;
+
+#[test]
+fn pow_mine_needs_a_thread() {
+	let status = common::node_command()
+		.args(["--chain", "pow", "--tmp", "--pow-mine", "0"])
+		.status()
+		.unwrap();
+	assert!(!status.success());
+}
+
+#[test]
+fn pow_chains_export_and_revert() {
+	let base_path = tempdir().unwrap();
+	let exported = base_path.path().join("exported_blocks");
+
+	// Both open the chain with the proof-of-work import components, without GRANDPA.
+	assert!(common::run_node_command(
+		&["export-blocks", "--chain", "pow", exported.to_str().unwrap()],
+		base_path.path()
+	)
+	.success());
+	assert!(
+		common::run_node_command(&["revert", "--chain", "pow", "1"], base_path.path()).success()
+	);
+}
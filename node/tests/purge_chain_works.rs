@@ -0,0 +1,24 @@
+use tempfile::tempdir;
+
+pub mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn purge_chain_works() {
+	let base_path = tempdir().unwrap();
+
+	let node = common::DevNode::start_with_args(base_path.path(), &["--tx-status"]);
+	common::wait_for_finalized(&node.rpc().await, 1).await;
+	assert!(node.stop().success());
+
+	let db_path = base_path.path().join("chains/dev/db/full");
+	assert!(db_path.exists());
+	let tx_status = base_path.path().join("chains/dev/tx-status.sqlite");
+	assert!(tx_status.exists());
+
+	assert!(common::run_node_command(&["purge-chain", "--dev", "-y"], base_path.path()).success());
+
+	// Only the database is removed, the rest of the node's data stays.
+	assert!(base_path.path().join("chains/dev").exists());
+	assert!(!db_path.exists());
+	assert!(!tx_status.exists());
+}
@@ -0,0 +1,284 @@
+use futures::future::BoxFuture;
+use jsonrpsee::{
+	core::{
+		client::{ClientT, Subscription, SubscriptionClientT, SubscriptionKind},
+		Error,
+	},
+	rpc_params,
+	types::error::CallError,
+	ws_client::{WsClient, WsClientBuilder},
+	RpcModule,
+};
+use node_template::rpc::middleware::{
+	self, InterfaceConfig, MiddlewareConfig, RateLimit, METHOD_NOT_ALLOWED, RATE_LIMITED,
+	TOO_MANY_SUBSCRIPTIONS,
+};
+use prometheus_endpoint::Registry;
+use sc_service::config::RpcMethods;
+use sp_core::traits::SpawnNamed;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tempfile::tempdir;
+
+/// Spawns the middleware's tasks on the test's runtime.
+#[derive(Clone)]
+struct TokioSpawner;
+
+impl SpawnNamed for TokioSpawner {
+	fn spawn_blocking(
+		&self,
+		_name: &'static str,
+		_group: Option<&'static str>,
+		future: BoxFuture<'static, ()>,
+	) {
+		tokio::spawn(future);
+	}
+
+	fn spawn(
+		&self,
+		_name: &'static str,
+		_group: Option<&'static str>,
+		future: BoxFuture<'static, ()>,
+	) {
+		tokio::spawn(future);
+	}
+}
+
+fn module() -> RpcModule<()> {
+	let mut module = RpcModule::new(());
+	module.register_method("test_hello", |_, _| Ok("hello")).unwrap();
+	module.register_method("admin_secret", |_, _| Ok("secret")).unwrap();
+	module.register_method("author_rotateKeys", |_, _| Ok("keys")).unwrap();
+	module
+		.register_subscription(
+			"test_subscribe",
+			"test_tick",
+			"test_unsubscribe",
+			|_, mut sink, _| {
+				tokio::spawn(async move {
+					sink.pipe_from_stream(futures::stream::pending::<u32>()).await;
+				});
+				Ok(())
+			},
+		)
+		.unwrap();
+	module
+}
+
+fn interface(allow: Option<&[&str]>, deny: &[&str]) -> InterfaceConfig {
+	InterfaceConfig {
+		listen: "127.0.0.1:0".parse().unwrap(),
+		allow: allow.map(|allow| allow.iter().map(|method| method.to_string()).collect()),
+		deny: deny.iter().map(|method| method.to_string()).collect(),
+	}
+}
+
+async fn connect(address: std::net::SocketAddr) -> WsClient {
+	WsClientBuilder::default().build(format!("ws://{}", address)).await.unwrap()
+}
+
+fn error_code(error: Error) -> i32 {
+	match error {
+		Error::Call(CallError::Custom(error)) => error.code(),
+		error => panic!("unexpected error: {:?}", error),
+	}
+}
+
+fn rejected(registry: &Registry, method: &str, reason: &str) -> u64 {
+	registry
+		.gather()
+		.iter()
+		.filter(|family| family.get_name() == "rpc_middleware_rejected_total")
+		.flat_map(|family| family.get_metric())
+		.filter(|metric| {
+			let label = |name| {
+				metric
+					.get_label()
+					.iter()
+					.find(|label| label.get_name() == name)
+					.unwrap()
+					.get_value()
+			};
+			label("method") == method && label("reason") == reason
+		})
+		.map(|metric| metric.get_counter().get_value() as u64)
+		.sum()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn interfaces_serve_only_their_methods() {
+	let config = MiddlewareConfig {
+		max_subscriptions: 10,
+		per_ip: None,
+		methods: HashMap::new(),
+		interfaces: vec![interface(Some(&["test_*"]), &["test_subscribe"]), interface(None, &[])],
+	};
+	let registry = Registry::new();
+	let addresses = middleware::start(
+		config,
+		Arc::new(module()),
+		RpcMethods::Unsafe,
+		Some(&registry),
+		TokioSpawner,
+	)
+	.unwrap();
+
+	let public = connect(addresses[0]).await;
+	let hello: String = public.request("test_hello", rpc_params![]).await.unwrap();
+	assert_eq!(hello, "hello");
+	let denied = public.request::<String>("admin_secret", rpc_params![]).await.unwrap_err();
+	assert_eq!(error_code(denied), METHOD_NOT_ALLOWED);
+	let denied = public
+		.subscribe::<u32>("test_subscribe", rpc_params![], "test_unsubscribe")
+		.await
+		.unwrap_err();
+	assert_eq!(error_code(denied), METHOD_NOT_ALLOWED);
+
+	let private = connect(addresses[1]).await;
+	let secret: String = private.request("admin_secret", rpc_params![]).await.unwrap();
+	assert_eq!(secret, "secret");
+
+	assert_eq!(rejected(&registry, "admin_secret", "not_allowed"), 1);
+	assert_eq!(rejected(&registry, "test_subscribe", "not_allowed"), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn calls_over_the_rate_limits_are_rejected() {
+	let config = MiddlewareConfig {
+		max_subscriptions: 10,
+		per_ip: Some(RateLimit { rate: 0.001, burst: 5 }),
+		methods: [("admin_secret".to_string(), RateLimit { rate: 0.001, burst: 2 })].into(),
+		interfaces: vec![interface(None, &[])],
+	};
+	let registry = Registry::new();
+	let addresses = middleware::start(
+		config,
+		Arc::new(module()),
+		RpcMethods::Unsafe,
+		Some(&registry),
+		TokioSpawner,
+	)
+	.unwrap();
+	let client = connect(addresses[0]).await;
+
+	for _ in 0..2 {
+		client.request::<String>("admin_secret", rpc_params![]).await.unwrap();
+	}
+	let limited = client.request::<String>("admin_secret", rpc_params![]).await.unwrap_err();
+	assert_eq!(error_code(limited), RATE_LIMITED);
+
+	// Every admitted or method-limited call took from the IP's bucket, which has two calls left.
+	for _ in 0..2 {
+		client.request::<String>("test_hello", rpc_params![]).await.unwrap();
+	}
+	let limited = client.request::<String>("test_hello", rpc_params![]).await.unwrap_err();
+	assert_eq!(error_code(limited), RATE_LIMITED);
+
+	assert_eq!(rejected(&registry, "admin_secret", "method_rate_limit"), 1);
+	assert_eq!(rejected(&registry, "test_hello", "ip_rate_limit"), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn subscriptions_are_capped_over_all_connections() {
+	let config = MiddlewareConfig {
+		max_subscriptions: 1,
+		per_ip: None,
+		methods: HashMap::new(),
+		interfaces: vec![interface(None, &[])],
+	};
+	let registry = Registry::new();
+	let addresses = middleware::start(
+		config,
+		Arc::new(module()),
+		RpcMethods::Unsafe,
+		Some(&registry),
+		TokioSpawner,
+	)
+	.unwrap();
+	let (first, second) = (connect(addresses[0]).await, connect(addresses[0]).await);
+
+	let subscribe = |client: &WsClient| {
+		client.subscribe::<u32>("test_subscribe", rpc_params![], "test_unsubscribe")
+	};
+	let subscription: Subscription<u32> = subscribe(&first).await.unwrap();
+	let capped = subscribe(&second).await.unwrap_err();
+	assert_eq!(error_code(capped), TOO_MANY_SUBSCRIPTIONS);
+	assert_eq!(rejected(&registry, "test_subscribe", "subscription_limit"), 1);
+
+	// Only the connection that opened the subscription can end it.
+	let id = match subscription.kind() {
+		SubscriptionKind::Subscription(id) => id.clone(),
+		kind => panic!("unexpected subscription kind: {:?}", kind),
+	};
+	let ended: bool = second.request("test_unsubscribe", rpc_params![id]).await.unwrap();
+	assert!(!ended);
+	assert!(subscribe(&second).await.is_err());
+
+	// The slot is released once the subscription has ended.
+	subscription.unsubscribe().await.unwrap();
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while subscribe(&second).await.is_err() {
+			tokio::time::sleep(Duration::from_millis(100)).await;
+		}
+	})
+	.await
+	.expect("the subscription slot was never released");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unsafe_methods_are_served_as_rpc_methods_allows() {
+	for (rpc_methods, allowed) in
+		[(RpcMethods::Safe, false), (RpcMethods::Auto, true), (RpcMethods::Unsafe, true)]
+	{
+		let config = MiddlewareConfig {
+			max_subscriptions: 10,
+			per_ip: None,
+			methods: HashMap::new(),
+			interfaces: vec![interface(None, &[])],
+		};
+		let addresses =
+			middleware::start(config, Arc::new(module()), rpc_methods, None, TokioSpawner).unwrap();
+		let client = connect(addresses[0]).await;
+
+		let result = client.request::<String>("author_rotateKeys", rpc_params![]).await;
+		if allowed {
+			assert_eq!(result.unwrap(), "keys");
+		} else {
+			assert_eq!(error_code(result.unwrap_err()), METHOD_NOT_ALLOWED);
+		}
+		// Safe methods are always served.
+		client.request::<String>("admin_secret", rpc_params![]).await.unwrap();
+	}
+}
+
+#[test]
+fn configurations_are_validated() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("middleware.toml");
+	let load = |content: &str| {
+		std::fs::write(&path, content).unwrap();
+		MiddlewareConfig::load(&path)
+	};
+
+	let config = load(
+		r#"
+		max_subscriptions = 16
+		per_ip = { rate = 10.0, burst = 20 }
+
+		[methods]
+		author_submitExtrinsic = { rate = 1.0, burst = 5 }
+
+		[[interfaces]]
+		listen = "0.0.0.0:9955"
+		allow = ["chain_*", "state_getStorage", "author_submitExtrinsic"]
+		deny = ["chain_subscribeAllHeads"]
+		"#,
+	)
+	.unwrap();
+	assert_eq!(config.max_subscriptions, 16);
+	assert_eq!(config.interfaces[0].deny, ["chain_subscribeAllHeads"]);
+
+	assert!(load("").is_err());
+	assert!(load("[[interfaces]]\nlisten = \"127.0.0.1:0\"\nallowed = []").is_err());
+	assert!(load("per_ip = { rate = 0.0, burst = 1 }\n[[interfaces]]\nlisten = \"127.0.0.1:0\"")
+		.is_err());
+}
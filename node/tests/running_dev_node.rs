@@ -0,0 +1,52 @@
+use node_template_runtime::{pallet_template, RuntimeCall, RuntimeEvent};
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::traits::Header as _;
+use tempfile::tempdir;
+
+pub mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn blocks_are_produced_and_finalized() {
+	let base_path = tempdir().unwrap();
+	let node = common::DevNode::start(base_path.path());
+	let rpc = node.rpc().await;
+
+	common::wait_for_finalized(&rpc, 3).await;
+	assert!(*common::header(&rpc, None).await.number() >= 3);
+
+	drop(rpc);
+	assert!(node.stop().success());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn template_extrinsic_emits_event() {
+	let base_path = tempdir().unwrap();
+	let node = common::DevNode::start(base_path.path());
+	let rpc = node.rpc().await;
+
+	// Wait for the node to be up and authoring.
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let from = *common::header(&rpc, None).await.number();
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 42 });
+	let extrinsic = common::signed_extrinsic(&rpc, Sr25519Keyring::Alice, call, 0).await;
+	common::submit(&rpc, &extrinsic).await;
+
+	let expected = RuntimeEvent::TemplateModule(pallet_template::Event::SomethingStored {
+		something: 42,
+		who: Sr25519Keyring::Alice.to_account_id(),
+	});
+
+	tokio::time::timeout(common::TIMEOUT, async {
+		let mut number = from;
+		loop {
+			match common::block_hash(&rpc, number).await {
+				Some(hash) if common::events(&rpc, hash).await.contains(&expected) => break,
+				Some(_) => number += 1,
+				None => tokio::time::sleep(std::time::Duration::from_secs(1)).await,
+			}
+		}
+	})
+	.await
+	.expect("the extrinsic was not included in time");
+}
@@ -0,0 +1,65 @@
+use std::{fs, path::Path, process::Output};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn snapshot(args: &[&str], base_path: &Path, file: &Path) -> Output {
+	common::node_command()
+		.arg("snapshot")
+		.args(args)
+		.arg(file)
+		.args(["--dev", "--base-path"])
+		.arg(base_path)
+		.output()
+		.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn snapshot_restores_into_a_fresh_database() {
+	let dir = tempdir().unwrap();
+	let (source, restored) = (dir.path().join("source"), dir.path().join("restored"));
+	let file = dir.path().join("state.snapshot");
+
+	let node = common::DevNode::start(&source);
+	common::wait_for_finalized(&node.rpc().await, 3).await;
+	assert!(node.stop().success());
+
+	let create = snapshot(&["create", "--chunk-size", "4096"], &source, &file);
+	assert!(create.status.success(), "{}", String::from_utf8_lossy(&create.stderr));
+
+	let restore = snapshot(&["restore"], &restored, &file);
+	assert!(restore.status.success(), "{}", String::from_utf8_lossy(&restore.stderr));
+	let stdout = String::from_utf8(restore.stdout).unwrap();
+	let number: u32 = stdout
+		.strip_prefix("Restored block #")
+		.and_then(|rest| rest.split_whitespace().next())
+		.and_then(|number| number.parse().ok())
+		.unwrap_or_else(|| panic!("unexpected output: {}", stdout));
+	assert!(number >= 3);
+
+	// Only fresh databases can be restored into.
+	assert!(!snapshot(&["restore"], &restored, &file).status.success());
+
+	// The restored node carries on from the snapshot, without the blocks before it.
+	let node = common::DevNode::start(&restored);
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, number + 2).await;
+	assert_eq!(common::block_hash(&rpc, number - 1).await, None);
+}
+
+#[test]
+fn snapshot_restore_rejects_corrupted_files() {
+	let dir = tempdir().unwrap();
+	let source = dir.path().join("source");
+	let file = dir.path().join("state.snapshot");
+
+	assert!(snapshot(&["create"], &source, &file).status.success());
+	let mut content = fs::read(&file).unwrap();
+	let middle = content.len() / 2;
+	content[middle] ^= 0xff;
+	fs::write(&file, &content).unwrap();
+
+	let restore = snapshot(&["restore"], &dir.path().join("restored"), &file);
+	assert!(!restore.status.success());
+	assert!(String::from_utf8_lossy(&restore.stderr).contains("corrupted"));
+}
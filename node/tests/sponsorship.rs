@@ -0,0 +1,173 @@
+use codec::Encode;
+use jsonrpsee::{
+	core::{client::ClientT, Error},
+	rpc_params,
+	types::error::CallError,
+	ws_client::WsClient,
+};
+use node_template::{
+	benchmarking,
+	rpc::{
+		account::AccountApiClient,
+		payment::{PaidBy, PaymentApiClient},
+	},
+	tx,
+};
+use node_template_runtime::{
+	pallet_sponsorship::{self, CallFilter},
+	pallet_template, Balance, Index, RuntimeCall, SystemCall, UncheckedExtrinsic,
+};
+use pallet_transaction_payment::RuntimeDispatchInfo;
+use serde_json::Value;
+use sp_core::{crypto::Ss58Codec, Bytes, H256};
+use sp_keyring::Sr25519Keyring::{self, Alice, Bob, Charlie};
+use std::time::Duration;
+use tempfile::tempdir;
+
+pub mod common;
+
+/// The error code of transactions the pool rejects as invalid.
+const POOL_INVALID_TX: i32 = 1010;
+
+const BUDGET: u128 = 1_000_000_000_000;
+
+/// Signs `call` as `signer`, with the fee paid by `sponsor`.
+async fn sponsored_extrinsic(
+	rpc: &WsClient,
+	signer: Sr25519Keyring,
+	sponsor: Sr25519Keyring,
+	call: RuntimeCall,
+	nonce: Index,
+) -> UncheckedExtrinsic {
+	tipped_extrinsic(rpc, signer, sponsor, call, nonce, 0).await
+}
+
+/// Signs `call` as `signer`, with the fee paid by `sponsor` and the given tip.
+async fn tipped_extrinsic(
+	rpc: &WsClient,
+	signer: Sr25519Keyring,
+	sponsor: Sr25519Keyring,
+	call: RuntimeCall,
+	nonce: Index,
+	tip: Balance,
+) -> UncheckedExtrinsic {
+	let genesis_hash = common::block_hash(rpc, 0).await.expect("genesis exists");
+	let best = common::header(rpc, None).await;
+	let params = benchmarking::signing_params(genesis_hash, nonce, best.number, best.hash())
+		.tip(tip)
+		.sponsor(Some(sponsor.to_account_id()));
+
+	tx::sign(call, &signer.pair(), &params)
+}
+
+/// Registers Alice as a sponsor of `TemplateModule` calls, paying for Charlie.
+async fn register_alice(rpc: &WsClient) {
+	let calls =
+		vec![CallFilter { pallet: b"TemplateModule".to_vec().try_into().unwrap(), call: None }];
+	let register = RuntimeCall::Sponsorship(pallet_sponsorship::Call::register {
+		budget: BUDGET,
+		calls: calls.try_into().unwrap(),
+	});
+	let allow = RuntimeCall::Sponsorship(pallet_sponsorship::Call::allow_account {
+		who: Charlie.to_account_id(),
+	});
+	common::submit(rpc, &common::signed_extrinsic(rpc, Alice, register, 0).await).await;
+	common::submit(rpc, &common::signed_extrinsic(rpc, Alice, allow, 1).await).await;
+	common::wait_for_nonce(rpc, Alice, 2).await;
+}
+
+/// Submits the extrinsic, returning the code it is rejected with.
+async fn rejection(rpc: &WsClient, extrinsic: &UncheckedExtrinsic) -> i32 {
+	let rejected = rpc
+		.request::<H256>("author_submitExtrinsic", rpc_params![Bytes(extrinsic.encode())])
+		.await
+		.unwrap_err();
+	match rejected {
+		Error::Call(CallError::Custom(error)) => error.code(),
+		error => panic!("unexpected error: {:?}", error),
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sponsors_pay_for_the_calls_they_sponsor() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	register_alice(&rpc).await;
+
+	// Charlie has no funds, but Alice pays for Charlie's `TemplateModule` calls.
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	let sponsored = sponsored_extrinsic(&rpc, Charlie, Alice, call, 0).await;
+	let payer = rpc.query_payer(Bytes(sponsored.encode()), None).await.unwrap();
+	assert_eq!(payer.paid_by, PaidBy::Sponsor);
+	assert_eq!(payer.payer, Some(Alice.to_account_id().to_ss58check()));
+	assert!(partial_fee(&rpc, &sponsored).await > 0);
+
+	// The standard payment methods answer as usual.
+	let info: Value = rpc
+		.request("payment_queryInfo", rpc_params![Bytes(sponsored.encode())])
+		.await
+		.unwrap();
+	assert!(info.get("partialFee").is_some());
+	assert!(info.get("paidBy").is_none());
+
+	let remark = RuntimeCall::System(SystemCall::remark { remark: b"paid".to_vec() });
+	let paid = common::signed_extrinsic(&rpc, Bob, remark.clone(), 0).await;
+	let payer = rpc.query_payer(Bytes(paid.encode()), None).await.unwrap();
+	assert_eq!(payer.paid_by, PaidBy::Sender);
+	assert_eq!(payer.payer, Some(Bob.to_account_id().to_ss58check()));
+
+	common::submit(&rpc, &sponsored).await;
+	wait_for_reserved(&rpc, Alice, |reserved| reserved < BUDGET).await;
+	assert_eq!(rpc.summary(Charlie.to_account_id(), None).await.unwrap().free, 0);
+
+	// Alice doesn't pay for other calls, nor for other senders.
+	let unsponsored = sponsored_extrinsic(&rpc, Charlie, Alice, remark, 1).await;
+	assert_eq!(rejection(&rpc, &unsponsored).await, POOL_INVALID_TX);
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 8 });
+	let unsponsored = sponsored_extrinsic(&rpc, Bob, Alice, call, 0).await;
+	assert_eq!(rejection(&rpc, &unsponsored).await, POOL_INVALID_TX);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sponsors_pay_no_more_than_the_base_fee() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+	register_alice(&rpc).await;
+
+	// A tip would come out of Alice's budget.
+	let call = || RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	let tipped = tipped_extrinsic(&rpc, Charlie, Alice, call(), 0, BUDGET / 2).await;
+	assert_eq!(rejection(&rpc, &tipped).await, POOL_INVALID_TX);
+
+	let sponsored = sponsored_extrinsic(&rpc, Charlie, Alice, call(), 0).await;
+	let fee = partial_fee(&rpc, &sponsored).await;
+	common::submit(&rpc, &sponsored).await;
+	wait_for_reserved(&rpc, Alice, |reserved| reserved < BUDGET).await;
+	let reserved = rpc.summary(Alice.to_account_id(), None).await.unwrap().reserved;
+	assert!(BUDGET - reserved <= fee);
+}
+
+/// The partial fee `payment_queryInfo` reports for `extrinsic`.
+async fn partial_fee(rpc: &WsClient, extrinsic: &UncheckedExtrinsic) -> Balance {
+	let info: RuntimeDispatchInfo<Balance> = rpc
+		.request("payment_queryInfo", rpc_params![Bytes(extrinsic.encode())])
+		.await
+		.unwrap();
+	info.partial_fee
+}
+
+/// Waits until the reserved balance of `who` satisfies `done`.
+async fn wait_for_reserved(rpc: &WsClient, who: Sr25519Keyring, done: impl Fn(u128) -> bool) {
+	tokio::time::timeout(common::TIMEOUT, async {
+		while !done(rpc.summary(who.to_account_id(), None).await.unwrap().reserved) {
+			tokio::time::sleep(Duration::from_secs(1)).await;
+		}
+	})
+	.await
+	.expect("the reserved balance did not change in time");
+}
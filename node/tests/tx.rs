@@ -0,0 +1,135 @@
+use codec::Decode;
+use jsonrpsee::{core::client::ClientT, rpc_params};
+use node_template_runtime::{
+	extensions::{
+		ChargeSponsoredTransactionPayment, CheckEra, CheckMetadataHash, CheckNonce, METADATA_HASH,
+	},
+	pallet_template, payment_extension, RuntimeCall, RuntimeEvent, UncheckedExtrinsic,
+};
+use sp_core::{crypto::Ss58Codec, Bytes};
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::{generic::Era, traits::Header as _, MultiAddress};
+use tempfile::tempdir;
+
+pub mod common;
+
+const GENESIS_HASH: &str = "0x0101010101010101010101010101010101010101010101010101010101010101";
+
+#[test]
+fn tx_signs_offline() {
+	let sponsor = Sr25519Keyring::Bob.to_account_id();
+	let output = common::node_command()
+		.args(["tx", "--suri", "//Alice", "--nonce", "5", "--mortality", "128"])
+		.args(["--era-block-number", "1000", "--era-block-hash", GENESIS_HASH])
+		.args(["--sponsor", &sponsor.to_ss58check()])
+		.args(["--genesis-hash", GENESIS_HASH, "do-something", "--value", "7"])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let hex = String::from_utf8(output.stdout).unwrap();
+	let encoded = sp_core::bytes::from_hex(hex.trim()).unwrap();
+	let extrinsic = UncheckedExtrinsic::decode(&mut &encoded[..]).unwrap();
+
+	assert_eq!(
+		extrinsic.function,
+		RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 })
+	);
+	let (signer, _, extra) = extrinsic.signature.expect("the extrinsic is signed");
+	assert_eq!(signer, MultiAddress::Id(Sr25519Keyring::Alice.to_account_id()));
+	assert_eq!(extra.4, CheckEra::from(Era::mortal(128, 1000)));
+	assert_eq!(extra.5, CheckNonce::from(5));
+	assert_eq!(payment_extension(&extra), &ChargeSponsoredTransactionPayment::sponsored(sponsor));
+}
+
+#[test]
+fn tx_commits_to_the_metadata_hash() {
+	let sign = |args: &[&str]| {
+		let output = common::node_command()
+			.args(["tx", "--suri", "//Alice", "--nonce", "0"])
+			.args(["--era-block-number", "0", "--era-block-hash", GENESIS_HASH])
+			.args(["--genesis-hash", GENESIS_HASH])
+			.args(args)
+			.arg("cause-error")
+			.output()
+			.unwrap();
+		assert!(output.status.success());
+
+		let hex = String::from_utf8(output.stdout).unwrap();
+		let encoded = sp_core::bytes::from_hex(hex.trim()).unwrap();
+		let extrinsic = UncheckedExtrinsic::decode(&mut &encoded[..]).unwrap();
+		let (_, _, extra) = extrinsic.signature.expect("the extrinsic is signed");
+		extra.9
+	};
+
+	assert_eq!(sign(&[]), CheckMetadataHash::new(METADATA_HASH.is_some()));
+	assert_eq!(sign(&["--metadata-hash", GENESIS_HASH]), CheckMetadataHash::new(true));
+	assert_eq!(sign(&["--no-metadata-hash"]), CheckMetadataHash::new(false));
+}
+
+#[test]
+fn tx_rejects_mortality_the_runtime_does_not_allow() {
+	for mortality in ["0", "4096"] {
+		let status = common::node_command()
+			.args(["tx", "--suri", "//Alice", "--nonce", "0", "--mortality", mortality])
+			.args(["--era-block-number", "0", "--era-block-hash", GENESIS_HASH])
+			.args(["--genesis-hash", GENESIS_HASH, "cause-error"])
+			.status()
+			.unwrap();
+		assert!(!status.success());
+	}
+}
+
+#[test]
+fn tx_requires_a_node_for_missing_params() {
+	let status = common::node_command()
+		.args(["tx", "--suri", "//Alice", "--genesis-hash", GENESIS_HASH, "cause-error"])
+		.status()
+		.unwrap();
+	assert!(!status.success());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tx_submits_to_a_node() {
+	let base_path = tempdir().unwrap();
+	let node = common::DevNode::start(base_path.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+	let from = *common::header(&rpc, None).await.number();
+
+	let output = common::node_command()
+		.args(["tx", "--suri", "//Alice", "--rpc-url", &node.ws_url, "--submit"])
+		.args(["do-something", "--value", "7"])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	// The transaction commits to the metadata hash of the node's runtime.
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	let encoded = sp_core::bytes::from_hex(stdout.lines().next().unwrap()).unwrap();
+	let extrinsic = UncheckedExtrinsic::decode(&mut &encoded[..]).unwrap();
+	let (_, _, extra) = extrinsic.signature.expect("the extrinsic is signed");
+	let hash: Bytes = rpc
+		.request("state_call", rpc_params!["MetadataHashApi_metadata_hash", Bytes(Vec::new())])
+		.await
+		.unwrap();
+	let hash = Option::<[u8; 32]>::decode(&mut &hash[..]).unwrap();
+	assert_eq!(extra.9, CheckMetadataHash::new(hash.is_some()));
+
+	let expected = RuntimeEvent::TemplateModule(pallet_template::Event::SomethingStored {
+		something: 7,
+		who: Sr25519Keyring::Alice.to_account_id(),
+	});
+	tokio::time::timeout(common::TIMEOUT, async {
+		let mut number = from;
+		loop {
+			match common::block_hash(&rpc, number).await {
+				Some(hash) if common::events(&rpc, hash).await.contains(&expected) => break,
+				Some(_) => number += 1,
+				None => tokio::time::sleep(std::time::Duration::from_secs(1)).await,
+			}
+		}
+	})
+	.await
+	.expect("the transaction was not included in time");
+}
@@ -0,0 +1,104 @@
+use futures::StreamExt;
+use jsonrpsee::core::client::Subscription;
+use node_template::{
+	rpc::tx_status::TxStatusApiClient,
+	tx_status::{TxState, TxStatus},
+};
+use node_template_runtime::{pallet_template, BalancesCall, RuntimeCall, SystemCall};
+use sp_keyring::Sr25519Keyring::{Alice, Bob, Charlie};
+use sp_runtime::traits::{BlakeTwo256, Hash as _};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Collects the statuses of a subscription until it ends.
+async fn statuses(subscription: Subscription<TxStatus>) -> Vec<TxStatus> {
+	tokio::time::timeout(common::TIMEOUT, subscription.map(Result::unwrap).collect())
+		.await
+		.expect("the transaction did not reach a final state in time")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn statuses_follow_transactions_and_survive_restarts() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start_with_args(dir.path(), &["--tx-status"]);
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	let stored = common::signed_extrinsic(&rpc, Alice, call, 0).await;
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::cause_error {});
+	let failing = common::signed_extrinsic(&rpc, Alice, call, 1).await;
+	let (stored_hash, failing_hash) =
+		(BlakeTwo256::hash_of(&stored), BlakeTwo256::hash_of(&failing));
+	assert_eq!(rpc.status(stored_hash).await.unwrap(), None);
+
+	let stored_updates = rpc.subscribe_status(stored_hash).await.unwrap();
+	let failing_updates = rpc.subscribe_status(failing_hash).await.unwrap();
+	common::submit(&rpc, &stored).await;
+	common::submit(&rpc, &failing).await;
+
+	let stored_statuses = statuses(stored_updates).await;
+	let states = stored_statuses.iter().map(|status| status.state).collect::<Vec<_>>();
+	assert_eq!(states, [TxState::Pooled, TxState::Included, TxState::Finalized]);
+	let finalized = stored_statuses.last().unwrap().clone();
+	assert!(finalized.pooled_at.is_some());
+	assert!(finalized.block_hash.is_some() && finalized.extrinsic_index.is_some());
+	assert_eq!(finalized.success, Some(true));
+	assert!(finalized.fee.unwrap() > 0);
+
+	let failed = statuses(failing_updates).await.pop().unwrap();
+	assert_eq!(failed.state, TxState::Finalized);
+	assert_eq!(failed.success, Some(false));
+	assert!(failed.error.is_some());
+	assert!(failed.fee.unwrap() > 0);
+
+	// The record outlives the connection and the node.
+	drop(rpc);
+	assert!(node.stop().success());
+	let node = common::DevNode::start_with_args(dir.path(), &["--tx-status"]);
+	let rpc = node.rpc().await;
+	assert_eq!(rpc.status(stored_hash).await.unwrap(), Some(finalized.clone()));
+
+	// Subscribing to a finalized transaction sends its status and ends.
+	let replay = statuses(rpc.subscribe_status(stored_hash).await.unwrap()).await;
+	assert_eq!(replay, [finalized]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn transactions_that_cannot_pay_anymore_turn_invalid() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start_with_args(dir.path(), &["--tx-status"]);
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	// Both are valid on their own, but once Bob has given away everything, the remark can't pay.
+	let transfer_all = RuntimeCall::Balances(BalancesCall::transfer_all {
+		dest: Charlie.to_account_id().into(),
+		keep_alive: false,
+	});
+	let transfer_all = common::signed_extrinsic(&rpc, Bob, transfer_all, 0).await;
+	let remark = RuntimeCall::System(SystemCall::remark { remark: b"too late".to_vec() });
+	let remark = common::signed_extrinsic(&rpc, Bob, remark, 1).await;
+	let remark_hash = BlakeTwo256::hash_of(&remark);
+
+	let updates = rpc.subscribe_status(remark_hash).await.unwrap();
+	common::submit(&rpc, &transfer_all).await;
+	common::submit(&rpc, &remark).await;
+
+	let invalid = statuses(updates).await.pop().unwrap();
+	assert_eq!(invalid.state, TxState::Invalid);
+	assert!(invalid.block_hash.is_none());
+	assert!(invalid.reason.unwrap().contains("Payment"));
+	assert_eq!(rpc.status(remark_hash).await.unwrap().unwrap().state, TxState::Invalid);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn statuses_are_only_recorded_on_request() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+
+	assert!(rpc.status(Default::default()).await.is_err());
+	assert!(!dir.path().join("chains/dev/tx-status.sqlite").exists());
+}
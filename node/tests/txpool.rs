@@ -0,0 +1,73 @@
+use codec::Encode;
+use jsonrpsee::{
+	core::{client::ClientT, Error},
+	rpc_params,
+	types::error::CallError,
+};
+use node_template::rpc::txpool::TxPoolApiClient;
+use node_template_runtime::{
+	pallet_template, MaxPendingPerSender, RuntimeCall, SystemCall, TemplateModulePriorityBoost,
+};
+use sp_core::{crypto::Ss58Codec, Bytes, H256};
+use sp_keyring::Sr25519Keyring::{Alice, Bob};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// The error code of transactions the pool rejects as invalid.
+const POOL_INVALID_TX: i32 = 1010;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn template_calls_are_prioritized() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	// Nonce 1 keeps both in the future queue, out of any block.
+	let call = RuntimeCall::TemplateModule(pallet_template::Call::do_something { something: 7 });
+	let template =
+		common::submit(&rpc, &common::signed_extrinsic(&rpc, Alice, call, 1).await).await;
+	let call = RuntimeCall::System(SystemCall::remark { remark: b"plain".to_vec() });
+	let remark = common::submit(&rpc, &common::signed_extrinsic(&rpc, Bob, call, 1).await).await;
+
+	let inspection = rpc.inspect().await.unwrap();
+	assert!(inspection.ready.is_empty());
+	let find = |hash: H256| {
+		inspection
+			.future
+			.iter()
+			.find(|tx| tx.hash == hash)
+			.expect("the transaction is pooled")
+	};
+	let (template, remark) = (find(template), find(remark));
+
+	assert!(template.priority >= TemplateModulePriorityBoost::get());
+	assert!(remark.priority < TemplateModulePriorityBoost::get());
+	assert_eq!(template.sender, Some(Alice.to_account_id().to_ss58check()));
+	assert_eq!(template.requires, [Bytes((Alice.to_account_id(), 0u32).encode())]);
+	assert_eq!(template.provides, [Bytes((Alice.to_account_id(), 1u32).encode())]);
+	assert!(template.longevity > 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn senders_are_limited_to_their_pending_window() {
+	let dir = tempdir().unwrap();
+	let node = common::DevNode::start(dir.path());
+	let rpc = node.rpc().await;
+	common::wait_for_finalized(&rpc, 1).await;
+
+	let remark = || RuntimeCall::System(SystemCall::remark { remark: b"queued".to_vec() });
+	let last = MaxPendingPerSender::get() - 1;
+	common::submit(&rpc, &common::signed_extrinsic(&rpc, Alice, remark(), last).await).await;
+
+	let beyond = common::signed_extrinsic(&rpc, Alice, remark(), last + 1).await;
+	let rejected = rpc
+		.request::<H256>("author_submitExtrinsic", rpc_params![Bytes(beyond.encode())])
+		.await
+		.unwrap_err();
+	match rejected {
+		Error::Call(CallError::Custom(error)) => assert_eq!(error.code(), POOL_INVALID_TX),
+		error => panic!("unexpected error: {:?}", error),
+	}
+}
@@ -0,0 +1,39 @@
+use std::{path::Path, process::Output};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Alice's sr25519 and ed25519 public keys, which are also the dev chain's authority keys.
+const ALICE_SESSION_KEYS: &str =
+	"0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d\
+	88dc3417d5058ec4b4503e0c12ea1a0a89be200fe98922423d4334014fa6b0ee";
+
+fn validator(args: &[&str], base_path: &Path) -> Output {
+	common::node_command()
+		.arg("validator")
+		.args(args)
+		.args(["--dev", "--base-path"])
+		.arg(base_path)
+		.output()
+		.unwrap()
+}
+
+#[test]
+fn validator_init_inserts_keys_that_check_accepts() {
+	let base_path = tempdir().unwrap();
+
+	let init = validator(&["init", "--suri", "//Alice"], base_path.path());
+	assert!(init.status.success());
+	let stdout = String::from_utf8(init.stdout).unwrap();
+	assert!(stdout.contains(&format!("Session keys: {}", ALICE_SESSION_KEYS)), "{}", stdout);
+
+	assert!(validator(&["check"], base_path.path()).status.success());
+}
+
+#[test]
+fn validator_check_rejects_keys_of_non_authorities() {
+	let base_path = tempdir().unwrap();
+
+	assert!(validator(&["init", "--suri", "//Bob"], base_path.path()).status.success());
+	assert!(!validator(&["check"], base_path.path()).status.success());
+}
@@ -0,0 +1,94 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// Stores the proof-of-work difficulty and retargets it after every block so that the chain
+/// converges on `TargetBlockTime`.
+///
+/// The pallet must be hooked up as (one of) `pallet_timestamp`'s `OnTimestampSet` handlers.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// The difficulty type understood by the node's proof-of-work algorithm.
+pub type Difficulty = u128;
+
+/// How far a single block may move the difficulty, as a factor in either direction.
+pub const MAX_ADJUSTMENT_FACTOR: u64 = 2;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::{Difficulty, MAX_ADJUSTMENT_FACTOR};
+	use frame_support::{pallet_prelude::*, traits::OnTimestampSet};
+	use sp_runtime::SaturatedConversion;
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_timestamp::Config {
+		/// The block time, in milliseconds, that the difficulty is adjusted towards.
+		#[pallet::constant]
+		type TargetBlockTime: Get<u64>;
+
+		/// The difficulty never drops below this value.
+		#[pallet::constant]
+		type MinDifficulty: Get<Difficulty>;
+	}
+
+	/// The difficulty the next block has to be sealed with.
+	#[pallet::storage]
+	#[pallet::getter(fn difficulty)]
+	pub type CurrentDifficulty<T> = StorageValue<_, Difficulty, ValueQuery>;
+
+	/// The timestamp of the previous block, used to measure the block time.
+	#[pallet::storage]
+	pub type LastTimestamp<T: Config> = StorageValue<_, T::Moment>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig {
+		pub initial_difficulty: Difficulty,
+	}
+
+	#[cfg(feature = "std")]
+	impl Default for GenesisConfig {
+		fn default() -> Self {
+			Self { initial_difficulty: 1_000_000 }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		fn build(&self) {
+			CurrentDifficulty::<T>::put(self.initial_difficulty.max(T::MinDifficulty::get()));
+		}
+	}
+
+	impl<T: Config> OnTimestampSet<T::Moment> for Pallet<T> {
+		fn on_timestamp_set(now: T::Moment) {
+			if let Some(last) = LastTimestamp::<T>::get() {
+				let target = T::TargetBlockTime::get().max(1);
+				// Clamp the measured block time so that a single outlier can only move the
+				// difficulty by `MAX_ADJUSTMENT_FACTOR`.
+				let block_time = now
+					.saturating_sub(last)
+					.saturated_into::<u64>()
+					.clamp(target / MAX_ADJUSTMENT_FACTOR, target * MAX_ADJUSTMENT_FACTOR)
+					.max(1);
+
+				let difficulty = Self::difficulty()
+					.saturating_mul(target.into())
+					.checked_div(block_time.into())
+					.unwrap_or_default()
+					.max(T::MinDifficulty::get());
+
+				CurrentDifficulty::<T>::put(difficulty);
+			}
+
+			LastTimestamp::<T>::put(now);
+		}
+	}
+}
@@ -0,0 +1,59 @@
+use crate::{mock::*, LastTimestamp};
+use frame_support::traits::OnTimestampSet;
+
+#[test]
+fn first_block_only_records_timestamp() {
+	new_test_ext().execute_with(|| {
+		Difficulty::on_timestamp_set(1_000);
+
+		assert_eq!(Difficulty::difficulty(), INITIAL_DIFFICULTY);
+		assert_eq!(LastTimestamp::<Test>::get(), Some(1_000));
+	});
+}
+
+#[test]
+fn difficulty_follows_block_time() {
+	new_test_ext().execute_with(|| {
+		Difficulty::on_timestamp_set(0);
+
+		// Blocks on target keep the difficulty.
+		Difficulty::on_timestamp_set(TARGET_BLOCK_TIME);
+		assert_eq!(Difficulty::difficulty(), INITIAL_DIFFICULTY);
+
+		// Blocks that come too fast make mining harder.
+		Difficulty::on_timestamp_set(TARGET_BLOCK_TIME + 4_000);
+		assert_eq!(Difficulty::difficulty(), INITIAL_DIFFICULTY * 3 / 2);
+
+		// Blocks that come too slow make mining easier.
+		Difficulty::on_timestamp_set(TARGET_BLOCK_TIME + 4_000 + 9_000);
+		assert_eq!(Difficulty::difficulty(), INITIAL_DIFFICULTY);
+	});
+}
+
+#[test]
+fn adjustment_is_clamped() {
+	new_test_ext().execute_with(|| {
+		Difficulty::on_timestamp_set(0);
+
+		Difficulty::on_timestamp_set(1);
+		assert_eq!(Difficulty::difficulty(), INITIAL_DIFFICULTY * 2);
+
+		Difficulty::on_timestamp_set(1 + 100 * TARGET_BLOCK_TIME);
+		assert_eq!(Difficulty::difficulty(), INITIAL_DIFFICULTY);
+	});
+}
+
+#[test]
+fn difficulty_never_drops_below_minimum() {
+	new_test_ext().execute_with(|| {
+		let mut now = 0;
+		Difficulty::on_timestamp_set(now);
+
+		for _ in 0..64 {
+			now += 2 * TARGET_BLOCK_TIME;
+			Difficulty::on_timestamp_set(now);
+		}
+
+		assert_eq!(Difficulty::difficulty(), MIN_DIFFICULTY);
+	});
+}
@@ -0,0 +1,230 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// Keeps the allowlist of accounts and calls whose transactions don't pay fees, and the quota
+/// that bounds how many such transactions an account sends per period.
+///
+/// The pallet only does the bookkeeping: a signed extension of the runtime asks
+/// [`Pallet::is_allowed`] and [`Pallet::remaining`] when validating a transaction, and spends
+/// the quota with [`Pallet::use_free_transaction`] when dispatching it. Senders without an
+/// account get one then, so that new users can transact before they hold any tokens.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{pallet_prelude::*, traits::GetCallMetadata};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::One;
+	use sp_std::vec::Vec;
+
+	/// The name of a pallet or of a call, as in the runtime metadata.
+	pub type NameOf<T> = BoundedVec<u8, <T as Config>::MaxNameLength>;
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The origin allowed to change the allowlist.
+		type AllowlistOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// How many feeless transactions an account may send per period.
+		#[pallet::constant]
+		type FreeTransactionsPerPeriod: Get<u32>;
+
+		/// The length of a quota period, in blocks.
+		#[pallet::constant]
+		type QuotaPeriod: Get<Self::BlockNumber>;
+
+		/// The longest pallet or call name the allowlist holds.
+		#[pallet::constant]
+		type MaxNameLength: Get<u32>;
+	}
+
+	/// The accounts whose transactions are feeless, whatever they call.
+	#[pallet::storage]
+	pub type AllowedAccounts<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, ()>;
+
+	/// The calls, by pallet and call name, that are feeless for every account.
+	#[pallet::storage]
+	pub type AllowedCalls<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, NameOf<T>, Blake2_128Concat, NameOf<T>, ()>;
+
+	/// The quota period an account last sent a feeless transaction in, and how many it sent then.
+	#[pallet::storage]
+	pub type Usage<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (T::BlockNumber, u32)>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The transactions of an account became feeless.
+		AccountAllowed { who: T::AccountId },
+		/// The transactions of an account pay fees again.
+		AccountRemoved { who: T::AccountId },
+		/// A call became feeless.
+		CallAllowed { pallet: NameOf<T>, call: NameOf<T> },
+		/// A call pays fees again.
+		CallRemoved { pallet: NameOf<T>, call: NameOf<T> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The account or call is already on the allowlist.
+		AlreadyAllowed,
+		/// The account or call is not on the allowlist.
+		NotAllowed,
+	}
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// The accounts whose transactions are feeless from genesis.
+		pub accounts: Vec<T::AccountId>,
+		/// The calls, by pallet and call name, that are feeless from genesis.
+		pub calls: Vec<(Vec<u8>, Vec<u8>)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { accounts: Vec::new(), calls: Vec::new() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for who in &self.accounts {
+				AllowedAccounts::<T>::insert(who, ());
+			}
+			for (pallet, call) in &self.calls {
+				let name = |name: &Vec<u8>| {
+					NameOf::<T>::try_from(name.clone()).expect("names fit `MaxNameLength`")
+				};
+				AllowedCalls::<T>::insert(name(pallet), name(call), ());
+			}
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Makes the transactions of `who` feeless, within the quota.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1).ref_time())]
+		pub fn allow_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::AllowlistOrigin::ensure_origin(origin)?;
+			ensure!(!AllowedAccounts::<T>::contains_key(&who), Error::<T>::AlreadyAllowed);
+
+			AllowedAccounts::<T>::insert(&who, ());
+			Self::deposit_event(Event::AccountAllowed { who });
+			Ok(())
+		}
+
+		/// Takes `who` off the allowlist.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1).ref_time())]
+		pub fn remove_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::AllowlistOrigin::ensure_origin(origin)?;
+			ensure!(AllowedAccounts::<T>::contains_key(&who), Error::<T>::NotAllowed);
+
+			AllowedAccounts::<T>::remove(&who);
+			Self::deposit_event(Event::AccountRemoved { who });
+			Ok(())
+		}
+
+		/// Makes `call` of `pallet` feeless for every account, within the quota.
+		///
+		/// Only the outermost call of a transaction counts, so allowing e.g. `Sudo.sudo` makes
+		/// whatever it wraps feeless.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1).ref_time())]
+		pub fn allow_call(
+			origin: OriginFor<T>,
+			pallet: NameOf<T>,
+			call: NameOf<T>,
+		) -> DispatchResult {
+			T::AllowlistOrigin::ensure_origin(origin)?;
+			ensure!(!AllowedCalls::<T>::contains_key(&pallet, &call), Error::<T>::AlreadyAllowed);
+
+			AllowedCalls::<T>::insert(&pallet, &call, ());
+			Self::deposit_event(Event::CallAllowed { pallet, call });
+			Ok(())
+		}
+
+		/// Takes `call` of `pallet` off the allowlist.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1).ref_time())]
+		pub fn remove_call(
+			origin: OriginFor<T>,
+			pallet: NameOf<T>,
+			call: NameOf<T>,
+		) -> DispatchResult {
+			T::AllowlistOrigin::ensure_origin(origin)?;
+			ensure!(AllowedCalls::<T>::contains_key(&pallet, &call), Error::<T>::NotAllowed);
+
+			AllowedCalls::<T>::remove(&pallet, &call);
+			Self::deposit_event(Event::CallRemoved { pallet, call });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether `who` is on the allowlist, or `call` is.
+		pub fn is_allowed(
+			who: &T::AccountId,
+			call: &<T as frame_system::Config>::RuntimeCall,
+		) -> bool
+		where
+			<T as frame_system::Config>::RuntimeCall: GetCallMetadata,
+		{
+			if AllowedAccounts::<T>::contains_key(who) {
+				return true
+			}
+
+			let metadata = call.get_call_metadata();
+			let name = |name: &str| NameOf::<T>::try_from(name.as_bytes().to_vec()).ok();
+			match (name(metadata.pallet_name), name(metadata.function_name)) {
+				(Some(pallet), Some(call)) => AllowedCalls::<T>::contains_key(pallet, call),
+				_ => false,
+			}
+		}
+
+		/// How many feeless transactions `who` has left in the current period.
+		pub fn remaining(who: &T::AccountId) -> u32 {
+			let used = match Usage::<T>::get(who) {
+				Some((period, used)) if period == Self::current_period() => used,
+				_ => 0,
+			};
+
+			T::FreeTransactionsPerPeriod::get().saturating_sub(used)
+		}
+
+		/// Spends one of the feeless transactions `who` has left, returning whether there was one.
+		///
+		/// If `who` has no account, the pallet provides one, so that the nonce the transaction
+		/// bumps is kept. Like the transaction, the account is bounded by the quota.
+		pub fn use_free_transaction(who: &T::AccountId) -> bool {
+			let remaining = Self::remaining(who);
+			if remaining == 0 {
+				return false
+			}
+
+			let used = T::FreeTransactionsPerPeriod::get() - remaining + 1;
+			Usage::<T>::insert(who, (Self::current_period(), used));
+			if !frame_system::Pallet::<T>::account_exists(who) {
+				frame_system::Pallet::<T>::inc_providers(who);
+			}
+			true
+		}
+
+		/// The index of the quota period the current block is in.
+		fn current_period() -> T::BlockNumber {
+			frame_system::Pallet::<T>::block_number() / T::QuotaPeriod::get().max(One::one())
+		}
+	}
+}
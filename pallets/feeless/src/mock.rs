@@ -0,0 +1,78 @@
+use crate as pallet_feeless;
+use frame_support::traits::{ConstU16, ConstU32, ConstU64};
+use frame_system as system;
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	BuildStorage,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub const FREE_TRANSACTIONS: u32 = 3;
+pub const QUOTA_PERIOD: u64 = 10;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Feeless: pallet_feeless,
+	}
+);
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+impl pallet_feeless::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type AllowlistOrigin = EnsureRoot<u64>;
+	type FreeTransactionsPerPeriod = ConstU32<FREE_TRANSACTIONS>;
+	type QuotaPeriod = ConstU64<QUOTA_PERIOD>;
+	type MaxNameLength = ConstU32<32>;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	new_test_ext_with(Default::default())
+}
+
+// Build genesis storage with the given allowlist.
+pub fn new_test_ext_with(feeless: pallet_feeless::GenesisConfig<Test>) -> sp_io::TestExternalities {
+	let mut ext: sp_io::TestExternalities = GenesisConfig { system: Default::default(), feeless }
+		.build_storage()
+		.unwrap()
+		.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,102 @@
+use crate::{mock::*, AllowedAccounts, Error, Event, NameOf};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::DispatchError;
+
+fn name(name: &str) -> NameOf<Test> {
+	name.as_bytes().to_vec().try_into().unwrap()
+}
+
+fn remark() -> RuntimeCall {
+	RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+}
+
+#[test]
+fn only_the_allowlist_origin_changes_the_allowlist() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Feeless::allow_account(RuntimeOrigin::signed(1), 1), DispatchError::BadOrigin);
+		assert_noop!(
+			Feeless::allow_call(RuntimeOrigin::signed(1), name("System"), name("remark")),
+			DispatchError::BadOrigin
+		);
+
+		assert_ok!(Feeless::allow_account(RuntimeOrigin::root(), 1));
+		assert!(AllowedAccounts::<Test>::contains_key(1));
+		System::assert_last_event(Event::AccountAllowed { who: 1 }.into());
+		assert_noop!(
+			Feeless::allow_account(RuntimeOrigin::root(), 1),
+			Error::<Test>::AlreadyAllowed
+		);
+
+		assert_ok!(Feeless::remove_account(RuntimeOrigin::root(), 1));
+		System::assert_last_event(Event::AccountRemoved { who: 1 }.into());
+		assert_noop!(Feeless::remove_account(RuntimeOrigin::root(), 1), Error::<Test>::NotAllowed);
+	});
+}
+
+#[test]
+fn allowed_accounts_and_calls_are_feeless() {
+	new_test_ext().execute_with(|| {
+		assert!(!Feeless::is_allowed(&1, &remark()));
+
+		assert_ok!(Feeless::allow_account(RuntimeOrigin::root(), 1));
+		assert!(Feeless::is_allowed(&1, &remark()));
+		assert!(!Feeless::is_allowed(&2, &remark()));
+
+		assert_ok!(Feeless::allow_call(RuntimeOrigin::root(), name("System"), name("remark")));
+		assert!(Feeless::is_allowed(&2, &remark()));
+		let other = RuntimeCall::System(frame_system::Call::set_heap_pages { pages: 1 });
+		assert!(!Feeless::is_allowed(&2, &other));
+
+		assert_ok!(Feeless::remove_call(RuntimeOrigin::root(), name("System"), name("remark")));
+		assert!(!Feeless::is_allowed(&2, &remark()));
+	});
+}
+
+#[test]
+fn the_allowlist_is_set_at_genesis() {
+	let feeless = crate::GenesisConfig {
+		accounts: vec![1],
+		calls: vec![(b"System".to_vec(), b"remark".to_vec())],
+	};
+	new_test_ext_with(feeless).execute_with(|| {
+		assert!(AllowedAccounts::<Test>::contains_key(1));
+		assert!(Feeless::is_allowed(&2, &remark()));
+		let other = RuntimeCall::System(frame_system::Call::set_heap_pages { pages: 1 });
+		assert!(!Feeless::is_allowed(&2, &other));
+	});
+}
+
+#[test]
+fn quota_is_spent_and_renewed_every_period() {
+	new_test_ext().execute_with(|| {
+		for left in (0..FREE_TRANSACTIONS).rev() {
+			assert!(Feeless::use_free_transaction(&1));
+			assert_eq!(Feeless::remaining(&1), left);
+		}
+		assert!(!Feeless::use_free_transaction(&1));
+
+		// Other accounts have their own quota.
+		assert_eq!(Feeless::remaining(&2), FREE_TRANSACTIONS);
+
+		System::set_block_number(QUOTA_PERIOD - 1);
+		assert_eq!(Feeless::remaining(&1), 0);
+		System::set_block_number(QUOTA_PERIOD);
+		assert_eq!(Feeless::remaining(&1), FREE_TRANSACTIONS);
+		assert!(Feeless::use_free_transaction(&1));
+		assert_eq!(Feeless::remaining(&1), FREE_TRANSACTIONS - 1);
+	});
+}
+
+#[test]
+fn free_transactions_provide_the_sender_account() {
+	new_test_ext().execute_with(|| {
+		assert!(!System::account_exists(&1));
+		assert!(Feeless::use_free_transaction(&1));
+		assert!(System::account_exists(&1));
+		assert_eq!(System::providers(&1), 1);
+
+		// Once is enough.
+		assert!(Feeless::use_free_transaction(&1));
+		assert_eq!(System::providers(&1), 1);
+	});
+}
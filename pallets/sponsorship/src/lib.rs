@@ -0,0 +1,315 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// Lets sponsors pay the transaction fees of others. A sponsor reserves a budget, names the
+/// calls it pays for, e.g. all `TemplateModule` calls, and allows the accounts it pays for;
+/// transactions of those accounts naming the sponsor then have their fees taken from that
+/// budget instead of from their sender.
+///
+/// The pallet only does the bookkeeping: a signed extension of the runtime asks
+/// [`Pallet::covers`] when validating a transaction, earmarks its fee with
+/// [`Pallet::withdraw_fee`] when dispatching it and charges the actual fee with
+/// [`Pallet::settle_fee`] afterwards.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{Currency, GetCallMetadata, Imbalance, OnUnbalanced, ReservableCurrency},
+		CloneNoBound, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound,
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::{Saturating, Zero};
+
+	/// The balance type of the sponsors' currency.
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	/// The imbalance of the fees taken from sponsors.
+	pub type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+		<T as frame_system::Config>::AccountId,
+	>>::NegativeImbalance;
+
+	/// The name of a pallet or of a call, as in the runtime metadata.
+	pub type NameOf<T> = BoundedVec<u8, <T as Config>::MaxNameLength>;
+
+	/// The calls a sponsor pays for.
+	pub type CallsOf<T> = BoundedVec<CallFilter<T>, <T as Config>::MaxCallFilters>;
+
+	/// Calls a sponsor pays for: `call` of `pallet`, or all calls of `pallet` if `call` is `None`.
+	#[derive(
+		CloneNoBound,
+		PartialEqNoBound,
+		EqNoBound,
+		RuntimeDebugNoBound,
+		Encode,
+		Decode,
+		MaxEncodedLen,
+		TypeInfo,
+	)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct CallFilter<T: Config> {
+		pub pallet: NameOf<T>,
+		pub call: Option<NameOf<T>>,
+	}
+
+	/// What a sponsor pays for, and how much is left to pay with.
+	#[derive(
+		CloneNoBound,
+		PartialEqNoBound,
+		EqNoBound,
+		RuntimeDebugNoBound,
+		Encode,
+		Decode,
+		MaxEncodedLen,
+		TypeInfo,
+	)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct Sponsorship<T: Config> {
+		/// The reserved balance left for fees.
+		pub budget: BalanceOf<T>,
+		/// The calls the sponsor pays for.
+		pub calls: CallsOf<T>,
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The currency budgets are reserved in.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Where the fees taken from sponsors go.
+		type OnFee: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+		/// The longest pallet or call name a call filter holds.
+		#[pallet::constant]
+		type MaxNameLength: Get<u32>;
+
+		/// The most call filters a sponsor may have.
+		#[pallet::constant]
+		type MaxCallFilters: Get<u32>;
+	}
+
+	/// The sponsorships, by sponsor.
+	#[pallet::storage]
+	pub type Sponsors<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Sponsorship<T>>;
+
+	/// The accounts a sponsor pays for, by sponsor.
+	#[pallet::storage]
+	pub type AllowedAccounts<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, T::AccountId, ()>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// An account started sponsoring calls.
+		Registered { sponsor: T::AccountId, budget: BalanceOf<T> },
+		/// A sponsor reserved more for fees.
+		ToppedUp { sponsor: T::AccountId, budget: BalanceOf<T> },
+		/// A sponsor changed the calls it pays for.
+		CallsSet { sponsor: T::AccountId },
+		/// A sponsor started paying for the transactions of `who`.
+		AccountAllowed { sponsor: T::AccountId, who: T::AccountId },
+		/// A sponsor stopped paying for the transactions of `who`.
+		AccountRemoved { sponsor: T::AccountId, who: T::AccountId },
+		/// A sponsor stopped and got the rest of its budget back.
+		Unregistered { sponsor: T::AccountId, refunded: BalanceOf<T> },
+		/// A sponsor paid the fee of a transaction of `who`.
+		FeePaid { sponsor: T::AccountId, who: T::AccountId, fee: BalanceOf<T> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The account already sponsors calls.
+		AlreadySponsor,
+		/// The account doesn't sponsor calls.
+		NotSponsor,
+		/// The sponsor already pays for the account.
+		AlreadyAllowed,
+		/// The sponsor doesn't pay for the account.
+		NotAllowed,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Reserves `budget` to pay the fees of `calls` with.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2).ref_time())]
+		pub fn register(
+			origin: OriginFor<T>,
+			budget: BalanceOf<T>,
+			calls: CallsOf<T>,
+		) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			ensure!(!Sponsors::<T>::contains_key(&sponsor), Error::<T>::AlreadySponsor);
+
+			T::Currency::reserve(&sponsor, budget)?;
+			Sponsors::<T>::insert(&sponsor, Sponsorship { budget, calls });
+			Self::deposit_event(Event::Registered { sponsor, budget });
+			Ok(())
+		}
+
+		/// Reserves `amount` more to pay fees with.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2).ref_time())]
+		pub fn top_up(origin: OriginFor<T>, amount: BalanceOf<T>) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			let mut sponsorship = Sponsors::<T>::get(&sponsor).ok_or(Error::<T>::NotSponsor)?;
+
+			T::Currency::reserve(&sponsor, amount)?;
+			sponsorship.budget = sponsorship.budget.saturating_add(amount);
+			Sponsors::<T>::insert(&sponsor, &sponsorship);
+			Self::deposit_event(Event::ToppedUp { sponsor, budget: sponsorship.budget });
+			Ok(())
+		}
+
+		/// Replaces the calls the sender pays for.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1).ref_time())]
+		pub fn set_calls(origin: OriginFor<T>, calls: CallsOf<T>) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			Sponsors::<T>::try_mutate(&sponsor, |sponsorship| {
+				let sponsorship = sponsorship.as_mut().ok_or(Error::<T>::NotSponsor)?;
+				sponsorship.calls = calls;
+				Ok::<_, Error<T>>(())
+			})?;
+
+			Self::deposit_event(Event::CallsSet { sponsor });
+			Ok(())
+		}
+
+		/// Pays for the transactions of `who` from now on.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 1).ref_time())]
+		pub fn allow_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			ensure!(Sponsors::<T>::contains_key(&sponsor), Error::<T>::NotSponsor);
+			ensure!(
+				!AllowedAccounts::<T>::contains_key(&sponsor, &who),
+				Error::<T>::AlreadyAllowed
+			);
+
+			AllowedAccounts::<T>::insert(&sponsor, &who, ());
+			Self::deposit_event(Event::AccountAllowed { sponsor, who });
+			Ok(())
+		}
+
+		/// Stops paying for the transactions of `who`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1).ref_time())]
+		pub fn remove_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			ensure!(AllowedAccounts::<T>::contains_key(&sponsor, &who), Error::<T>::NotAllowed);
+
+			AllowedAccounts::<T>::remove(&sponsor, &who);
+			Self::deposit_event(Event::AccountRemoved { sponsor, who });
+			Ok(())
+		}
+
+		/// Stops sponsoring and unreserves the rest of the budget.
+		///
+		/// The allowed accounts are kept, and paid for again if the sponsor registers again.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2).ref_time())]
+		pub fn unregister(origin: OriginFor<T>) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			let sponsorship = Sponsors::<T>::take(&sponsor).ok_or(Error::<T>::NotSponsor)?;
+
+			let missing = T::Currency::unreserve(&sponsor, sponsorship.budget);
+			let refunded = sponsorship.budget.saturating_sub(missing);
+			Self::deposit_event(Event::Unregistered { sponsor, refunded });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether `sponsor` pays for `call`, sent by `who`, and has `fee` left in its budget.
+		pub fn covers(
+			sponsor: &T::AccountId,
+			who: &T::AccountId,
+			call: &<T as frame_system::Config>::RuntimeCall,
+			fee: BalanceOf<T>,
+		) -> bool
+		where
+			<T as frame_system::Config>::RuntimeCall: GetCallMetadata,
+		{
+			let sponsorship = match Sponsors::<T>::get(sponsor) {
+				Some(sponsorship) => sponsorship,
+				None => return false,
+			};
+			let metadata = call.get_call_metadata();
+			let matches = |filter: &CallFilter<T>| {
+				filter.pallet[..] == *metadata.pallet_name.as_bytes() &&
+					filter
+						.call
+						.as_ref()
+						.map_or(true, |call| call[..] == *metadata.function_name.as_bytes())
+			};
+
+			AllowedAccounts::<T>::contains_key(sponsor, who) &&
+				sponsorship.calls.iter().any(matches) &&
+				fee <= sponsorship.budget &&
+				fee <= T::Currency::reserved_balance(sponsor)
+		}
+
+		/// Earmarks `fee` of the budget of `sponsor` for a transaction of `who` dispatching
+		/// `call`, returning whether the sponsor covers it.
+		///
+		/// The fee stays reserved until [`Self::settle_fee`] charges it.
+		pub fn withdraw_fee(
+			sponsor: &T::AccountId,
+			who: &T::AccountId,
+			call: &<T as frame_system::Config>::RuntimeCall,
+			fee: BalanceOf<T>,
+		) -> bool
+		where
+			<T as frame_system::Config>::RuntimeCall: GetCallMetadata,
+		{
+			if !Self::covers(sponsor, who, call, fee) {
+				return false
+			}
+
+			Sponsors::<T>::mutate(sponsor, |sponsorship| {
+				if let Some(sponsorship) = sponsorship {
+					sponsorship.budget = sponsorship.budget.saturating_sub(fee);
+				}
+			});
+			true
+		}
+
+		/// Charges `sponsor` the `actual_fee` of the transaction of `who` that `withdrawn` was
+		/// earmarked for, and returns the rest to the budget. Returns the fee charged.
+		pub fn settle_fee(
+			sponsor: &T::AccountId,
+			who: &T::AccountId,
+			withdrawn: BalanceOf<T>,
+			actual_fee: BalanceOf<T>,
+		) -> BalanceOf<T> {
+			let actual_fee = actual_fee.min(withdrawn);
+			let (imbalance, _) = T::Currency::slash_reserved(sponsor, actual_fee);
+			let fee = imbalance.peek();
+			T::OnFee::on_unbalanced(imbalance);
+
+			// The sponsor may have unregistered in the meantime, without the earmarked fee.
+			let refund = withdrawn.saturating_sub(actual_fee);
+			Sponsors::<T>::mutate(sponsor, |sponsorship| match sponsorship {
+				Some(sponsorship) => sponsorship.budget = sponsorship.budget.saturating_add(refund),
+				None if !refund.is_zero() => {
+					T::Currency::unreserve(sponsor, refund);
+				},
+				None => (),
+			});
+
+			Self::deposit_event(Event::FeePaid { sponsor: sponsor.clone(), who: who.clone(), fee });
+			fee
+		}
+	}
+}
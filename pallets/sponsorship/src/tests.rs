@@ -0,0 +1,140 @@
+use crate::{mock::*, AllowedAccounts, CallFilter, CallsOf, Error, Event, Sponsors};
+use frame_support::{assert_noop, assert_ok, traits::ReservableCurrency};
+
+fn calls(filters: &[(&str, Option<&str>)]) -> CallsOf<Test> {
+	let name = |name: &str| name.as_bytes().to_vec().try_into().unwrap();
+	filters
+		.iter()
+		.map(|(pallet, call)| CallFilter { pallet: name(pallet), call: call.map(name) })
+		.collect::<Vec<_>>()
+		.try_into()
+		.unwrap()
+}
+
+fn remark() -> RuntimeCall {
+	RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+}
+
+fn transfer() -> RuntimeCall {
+	RuntimeCall::Balances(pallet_balances::Call::transfer { dest: USER, value: 1 })
+}
+
+#[test]
+fn registering_reserves_the_budget() {
+	new_test_ext().execute_with(|| {
+		let filters = calls(&[("System", None)]);
+		assert_ok!(Sponsorship::register(RuntimeOrigin::signed(SPONSOR), 300, filters.clone()));
+		assert_eq!(Balances::reserved_balance(SPONSOR), 300);
+		System::assert_last_event(Event::Registered { sponsor: SPONSOR, budget: 300 }.into());
+		assert_noop!(
+			Sponsorship::register(RuntimeOrigin::signed(SPONSOR), 1, filters),
+			Error::<Test>::AlreadySponsor
+		);
+
+		assert_ok!(Sponsorship::top_up(RuntimeOrigin::signed(SPONSOR), 200));
+		assert_eq!(Sponsors::<Test>::get(SPONSOR).unwrap().budget, 500);
+		assert_eq!(Balances::reserved_balance(SPONSOR), 500);
+
+		assert_ok!(Sponsorship::unregister(RuntimeOrigin::signed(SPONSOR)));
+		assert_eq!(Balances::reserved_balance(SPONSOR), 0);
+		System::assert_last_event(Event::Unregistered { sponsor: SPONSOR, refunded: 500 }.into());
+		assert_noop!(
+			Sponsorship::top_up(RuntimeOrigin::signed(SPONSOR), 1),
+			Error::<Test>::NotSponsor
+		);
+
+		// The budget can't exceed the free balance.
+		assert!(Sponsorship::register(RuntimeOrigin::signed(USER), 1, calls(&[])).is_err());
+	});
+}
+
+#[test]
+fn sponsors_allow_the_accounts_they_pay_for() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Sponsorship::allow_account(RuntimeOrigin::signed(SPONSOR), USER),
+			Error::<Test>::NotSponsor
+		);
+
+		let filters = calls(&[("System", None)]);
+		assert_ok!(Sponsorship::register(RuntimeOrigin::signed(SPONSOR), 100, filters));
+		assert!(!Sponsorship::covers(&SPONSOR, &USER, &remark(), 1));
+
+		assert_ok!(Sponsorship::allow_account(RuntimeOrigin::signed(SPONSOR), USER));
+		assert!(AllowedAccounts::<Test>::contains_key(SPONSOR, USER));
+		System::assert_last_event(Event::AccountAllowed { sponsor: SPONSOR, who: USER }.into());
+		assert_noop!(
+			Sponsorship::allow_account(RuntimeOrigin::signed(SPONSOR), USER),
+			Error::<Test>::AlreadyAllowed
+		);
+		assert!(Sponsorship::covers(&SPONSOR, &USER, &remark(), 1));
+		assert!(!Sponsorship::covers(&SPONSOR, &SPONSOR, &remark(), 1));
+		assert!(!Sponsorship::withdraw_fee(&SPONSOR, &SPONSOR, &remark(), 1));
+
+		assert_ok!(Sponsorship::remove_account(RuntimeOrigin::signed(SPONSOR), USER));
+		System::assert_last_event(Event::AccountRemoved { sponsor: SPONSOR, who: USER }.into());
+		assert!(!Sponsorship::covers(&SPONSOR, &USER, &remark(), 1));
+		assert_noop!(
+			Sponsorship::remove_account(RuntimeOrigin::signed(SPONSOR), USER),
+			Error::<Test>::NotAllowed
+		);
+	});
+}
+
+#[test]
+fn sponsors_cover_matching_calls_within_their_budget() {
+	new_test_ext().execute_with(|| {
+		assert!(!Sponsorship::covers(&SPONSOR, &USER, &remark(), 0));
+
+		let filters = calls(&[("System", Some("remark"))]);
+		assert_ok!(Sponsorship::register(RuntimeOrigin::signed(SPONSOR), 100, filters));
+		assert_ok!(Sponsorship::allow_account(RuntimeOrigin::signed(SPONSOR), USER));
+		assert!(Sponsorship::covers(&SPONSOR, &USER, &remark(), 100));
+		assert!(!Sponsorship::covers(&SPONSOR, &USER, &remark(), 101));
+		assert!(!Sponsorship::covers(&SPONSOR, &USER, &transfer(), 1));
+
+		assert_ok!(Sponsorship::set_calls(
+			RuntimeOrigin::signed(SPONSOR),
+			calls(&[("Balances", None)])
+		));
+		assert!(!Sponsorship::covers(&SPONSOR, &USER, &remark(), 1));
+		assert!(Sponsorship::covers(&SPONSOR, &USER, &transfer(), 1));
+	});
+}
+
+#[test]
+fn fees_are_charged_from_the_budget() {
+	new_test_ext().execute_with(|| {
+		let filters = calls(&[("System", None)]);
+		assert_ok!(Sponsorship::register(RuntimeOrigin::signed(SPONSOR), 100, filters));
+		assert_ok!(Sponsorship::allow_account(RuntimeOrigin::signed(SPONSOR), USER));
+
+		assert!(Sponsorship::withdraw_fee(&SPONSOR, &USER, &remark(), 60));
+		assert_eq!(Sponsors::<Test>::get(SPONSOR).unwrap().budget, 40);
+		assert!(!Sponsorship::withdraw_fee(&SPONSOR, &USER, &remark(), 60));
+
+		// Only the actual fee is charged, the rest goes back to the budget.
+		assert_eq!(Sponsorship::settle_fee(&SPONSOR, &USER, 60, 25), 25);
+		System::assert_last_event(Event::FeePaid { sponsor: SPONSOR, who: USER, fee: 25 }.into());
+		assert_eq!(Sponsors::<Test>::get(SPONSOR).unwrap().budget, 75);
+		assert_eq!(Balances::reserved_balance(SPONSOR), 75);
+		assert_eq!(Balances::free_balance(SPONSOR), ENDOWMENT - 100);
+	});
+}
+
+#[test]
+fn earmarked_fees_are_refunded_after_unregistering() {
+	new_test_ext().execute_with(|| {
+		let filters = calls(&[("System", None)]);
+		assert_ok!(Sponsorship::register(RuntimeOrigin::signed(SPONSOR), 100, filters));
+		assert_ok!(Sponsorship::allow_account(RuntimeOrigin::signed(SPONSOR), USER));
+		assert!(Sponsorship::withdraw_fee(&SPONSOR, &USER, &remark(), 60));
+
+		assert_ok!(Sponsorship::unregister(RuntimeOrigin::signed(SPONSOR)));
+		assert_eq!(Balances::reserved_balance(SPONSOR), 60);
+
+		assert_eq!(Sponsorship::settle_fee(&SPONSOR, &USER, 60, 10), 10);
+		assert_eq!(Balances::reserved_balance(SPONSOR), 0);
+		assert_eq!(Balances::free_balance(SPONSOR), ENDOWMENT - 10);
+	});
+}
@@ -0,0 +1,398 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Merkleized hashes of runtime metadata.
+//!
+//! The types of the metadata registry are the leaves of a binary merkle tree, in the order of
+//! their ids. The metadata hash commits to the root of that tree, to the extrinsic format and to
+//! the chain's name, spec version and SS58 prefix.
+//!
+//! A [`MetadataProof`] carries the types needed to decode one call and the signed extensions,
+//! along with the sibling hashes that tie them to the metadata hash. An offline signer checks a
+//! proof with [`MetadataProof::verify`] against the hash it signs with, and can then decode the
+//! call without trusting whoever built the proof: if the types were forged, the hash differs
+//! and the runtime rejects the signature.
+
+use codec::{Decode, Encode};
+use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed, RuntimeMetadataV14};
+use scale_info::{PortableRegistry, TypeDef, TypeDefPrimitive};
+use sp_core_hashing::blake2_256;
+use sp_std::{
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+	vec::Vec,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The SS58 prefix of chains that don't set one.
+const DEFAULT_SS58_PREFIX: u16 = 42;
+
+/// How deeply nested the types of a call may be.
+const MAX_DEPTH: u32 = 256;
+
+/// What the metadata hash commits to besides the types and the extrinsic format.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct ExtraInfo {
+	/// The name of the runtime, as in its version.
+	pub spec_name: Vec<u8>,
+	/// The spec version of the runtime.
+	pub spec_version: u32,
+	/// The SS58 prefix of addresses on the chain.
+	pub ss58_prefix: u16,
+}
+
+/// The parts of the metadata needed to decode a call, and the hashes that prove them.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, scale_info::TypeInfo)]
+pub struct MetadataProof {
+	/// How many types the registry has.
+	pub leaf_count: u32,
+	/// The ids of the types in the proof, in ascending order, and their encoding as
+	/// `PortableType`s.
+	pub leaves: Vec<(u32, Vec<u8>)>,
+	/// The hashes of the subtrees without a type in the proof, bottom up and left to right.
+	pub nodes: Vec<[u8; 32]>,
+	/// The encoded `ExtrinsicMetadata`.
+	pub extrinsic: Vec<u8>,
+	/// The encoded [`ExtraInfo`].
+	pub extra: Vec<u8>,
+}
+
+impl MetadataProof {
+	/// Returns the metadata hash the proof is for, or `None` if the proof is malformed.
+	pub fn verify(&self) -> Option<[u8; 32]> {
+		let mut width = (self.leaf_count as usize).max(1).next_power_of_two();
+		let mut known = BTreeMap::new();
+		for (id, leaf) in &self.leaves {
+			if *id >= self.leaf_count || known.insert(*id as usize, blake2_256(leaf)).is_some() {
+				return None
+			}
+		}
+		if known.is_empty() {
+			return None
+		}
+
+		let mut nodes = self.nodes.iter();
+		while width > 1 {
+			let mut parents = BTreeMap::new();
+			for (&index, hash) in &known {
+				if parents.contains_key(&(index / 2)) {
+					continue
+				}
+				let sibling = match known.get(&(index ^ 1)) {
+					Some(sibling) => *sibling,
+					None => *nodes.next()?,
+				};
+				let parent =
+					if index % 2 == 0 { merge(hash, &sibling) } else { merge(&sibling, hash) };
+				parents.insert(index / 2, parent);
+			}
+			known = parents;
+			width /= 2;
+		}
+
+		if nodes.next().is_some() {
+			return None
+		}
+		let types_root = known.get(&0)?;
+		Some(root(types_root, &self.extrinsic, &self.extra))
+	}
+}
+
+/// Returns the V14 metadata in `metadata`, if that is its version.
+pub fn v14(metadata: &RuntimeMetadataPrefixed) -> Option<&RuntimeMetadataV14> {
+	match &metadata.1 {
+		RuntimeMetadata::V14(metadata) => Some(metadata),
+		_ => None,
+	}
+}
+
+/// Returns the hash of `metadata` for the runtime `spec_name` at `spec_version`.
+pub fn metadata_hash(
+	metadata: &RuntimeMetadataV14,
+	spec_name: &str,
+	spec_version: u32,
+) -> [u8; 32] {
+	let levels = levels(&metadata.types);
+	let types_root = levels.last().and_then(|level| level.first()).copied().unwrap_or_default();
+	root(&types_root, &metadata.extrinsic.encode(), &extra_info(metadata, spec_name, spec_version))
+}
+
+/// Returns a proof of the types needed to decode `call` and to sign a transaction dispatching
+/// it, or `None` if `call` doesn't decode with `metadata`.
+pub fn metadata_proof(
+	metadata: &RuntimeMetadataV14,
+	spec_name: &str,
+	spec_version: u32,
+	call: &[u8],
+) -> Option<MetadataProof> {
+	let types = &metadata.types;
+	let extrinsic = types.resolve(metadata.extrinsic.ty.id())?;
+	let param = |name: &str| {
+		extrinsic
+			.type_params()
+			.iter()
+			.find(|param| param.name() == name)?
+			.ty()
+			.map(|ty| ty.id())
+	};
+
+	let mut used = BTreeSet::new();
+	used.insert(metadata.extrinsic.ty.id());
+	let mut input = call;
+	walk(types, param("Call")?, &mut input, &mut used, 0)?;
+	if !input.is_empty() {
+		return None
+	}
+	for name in ["Address", "Signature", "Extra"] {
+		if let Some(id) = param(name) {
+			close(types, id, &mut used);
+		}
+	}
+	for extension in &metadata.extrinsic.signed_extensions {
+		close(types, extension.ty.id(), &mut used);
+		close(types, extension.additional_signed.id(), &mut used);
+	}
+
+	let levels = levels(types);
+	let mut nodes = Vec::new();
+	let mut known: BTreeSet<usize> = used.iter().map(|id| *id as usize).collect();
+	for level in &levels[..levels.len() - 1] {
+		for index in &known {
+			let sibling = index ^ 1;
+			if !known.contains(&sibling) {
+				nodes.push(level[sibling]);
+			}
+		}
+		known = known.iter().map(|index| index / 2).collect();
+	}
+
+	let leaves = types
+		.types()
+		.iter()
+		.filter(|ty| used.contains(&ty.id()))
+		.map(|ty| (ty.id(), ty.encode()))
+		.collect();
+	Some(MetadataProof {
+		leaf_count: types.types().len() as u32,
+		leaves,
+		nodes,
+		extrinsic: metadata.extrinsic.encode(),
+		extra: extra_info(metadata, spec_name, spec_version),
+	})
+}
+
+/// Decodes a metadata hash from the 64 hex digits of `hex`, with or without a `0x` prefix.
+pub const fn decode_hex(hex: &str) -> Option<[u8; 32]> {
+	let mut digits = hex.as_bytes();
+	if let [b'0', b'x', rest @ ..] = digits {
+		digits = rest;
+	}
+	if digits.len() != 64 {
+		return None
+	}
+
+	const fn digit(c: u8) -> Option<u8> {
+		match c {
+			b'0'..=b'9' => Some(c - b'0'),
+			b'a'..=b'f' => Some(c - b'a' + 10),
+			b'A'..=b'F' => Some(c - b'A' + 10),
+			_ => None,
+		}
+	}
+
+	let mut hash = [0; 32];
+	let mut i = 0;
+	while i < 32 {
+		match (digit(digits[2 * i]), digit(digits[2 * i + 1])) {
+			(Some(high), Some(low)) => hash[i] = high << 4 | low,
+			_ => return None,
+		}
+		i += 1;
+	}
+	Some(hash)
+}
+
+/// Hashes two sibling nodes into their parent.
+fn merge(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+	let mut both = [0; 64];
+	both[..32].copy_from_slice(left);
+	both[32..].copy_from_slice(right);
+	blake2_256(&both)
+}
+
+/// Combines the root of the types with the extrinsic format and the extra info.
+fn root(types_root: &[u8; 32], extrinsic: &[u8], extra: &[u8]) -> [u8; 32] {
+	blake2_256(&(types_root, blake2_256(extrinsic), blake2_256(extra)).encode())
+}
+
+/// Returns the levels of the merkle tree of `types`, from the leaves, padded to a power of two
+/// with zeroes, up to the root.
+fn levels(types: &PortableRegistry) -> Vec<Vec<[u8; 32]>> {
+	let mut level: Vec<[u8; 32]> =
+		types.types().iter().map(|ty| blake2_256(&ty.encode())).collect();
+	level.resize(level.len().max(1).next_power_of_two(), [0; 32]);
+
+	let mut levels = Vec::new();
+	while level.len() > 1 {
+		let parents = level.chunks(2).map(|pair| merge(&pair[0], &pair[1])).collect();
+		levels.push(level);
+		level = parents;
+	}
+	levels.push(level);
+	levels
+}
+
+/// Returns the encoded [`ExtraInfo`] of `metadata`.
+fn extra_info(metadata: &RuntimeMetadataV14, spec_name: &str, spec_version: u32) -> Vec<u8> {
+	let ss58_prefix = metadata
+		.pallets
+		.iter()
+		.filter(|pallet| pallet.name == "System")
+		.flat_map(|pallet| &pallet.constants)
+		.find(|constant| constant.name == "SS58Prefix")
+		.and_then(|constant| u16::decode(&mut &constant.value[..]).ok())
+		.unwrap_or(DEFAULT_SS58_PREFIX);
+
+	ExtraInfo { spec_name: spec_name.as_bytes().to_vec(), spec_version, ss58_prefix }.encode()
+}
+
+/// Adds `id` and every type it refers to to `used`.
+fn close(types: &PortableRegistry, id: u32, used: &mut BTreeSet<u32>) {
+	// `used` may hold enums of which only one variant was walked, so track what is closed over
+	// separately.
+	let mut closed = BTreeSet::new();
+	let mut pending = Vec::from([id]);
+	while let Some(id) = pending.pop() {
+		if !closed.insert(id) {
+			continue
+		}
+		used.insert(id);
+		let ty = match types.resolve(id) {
+			Some(ty) => ty,
+			None => continue,
+		};
+		let mut refer = |id: u32| {
+			if !closed.contains(&id) {
+				pending.push(id);
+			}
+		};
+		ty.type_params()
+			.iter()
+			.filter_map(|param| param.ty())
+			.for_each(|ty| refer(ty.id()));
+		match ty.type_def() {
+			TypeDef::Composite(composite) =>
+				composite.fields().iter().for_each(|field| refer(field.ty().id())),
+			TypeDef::Variant(variant) => variant
+				.variants()
+				.iter()
+				.flat_map(|variant| variant.fields())
+				.for_each(|field| refer(field.ty().id())),
+			TypeDef::Sequence(sequence) => refer(sequence.type_param().id()),
+			TypeDef::Array(array) => refer(array.type_param().id()),
+			TypeDef::Tuple(tuple) => tuple.fields().iter().for_each(|ty| refer(ty.id())),
+			TypeDef::Compact(compact) => refer(compact.type_param().id()),
+			TypeDef::BitSequence(bits) => {
+				refer(bits.bit_store_type().id());
+				refer(bits.bit_order_type().id());
+			},
+			TypeDef::Primitive(_) => (),
+		}
+	}
+}
+
+/// Decodes a value of type `id` from `input`, adding the types it takes to decode it to `used`.
+/// Of an enum, only the fields of the variant in `input` are decoded.
+fn walk(
+	types: &PortableRegistry,
+	id: u32,
+	input: &mut &[u8],
+	used: &mut BTreeSet<u32>,
+	depth: u32,
+) -> Option<()> {
+	if depth > MAX_DEPTH {
+		return None
+	}
+	used.insert(id);
+	let walk = |id: u32, input: &mut &[u8], used: &mut BTreeSet<u32>| {
+		walk(types, id, input, used, depth + 1)
+	};
+
+	match types.resolve(id)?.type_def() {
+		TypeDef::Composite(composite) =>
+			for field in composite.fields() {
+				walk(field.ty().id(), input, used)?;
+			},
+		TypeDef::Variant(variant) => {
+			let index = u8::decode(input).ok()?;
+			let variant = variant.variants().iter().find(|variant| variant.index() == index)?;
+			for field in variant.fields() {
+				walk(field.ty().id(), input, used)?;
+			}
+		},
+		TypeDef::Sequence(sequence) => {
+			// Every element takes at least a byte, except of types no call should have.
+			let len = compact(input)?;
+			if len > input.len() {
+				return None
+			}
+			used.insert(sequence.type_param().id());
+			for _ in 0..len {
+				walk(sequence.type_param().id(), input, used)?;
+			}
+		},
+		TypeDef::Array(array) => {
+			used.insert(array.type_param().id());
+			for _ in 0..array.len() {
+				walk(array.type_param().id(), input, used)?;
+			}
+		},
+		TypeDef::Tuple(tuple) =>
+			for ty in tuple.fields() {
+				walk(ty.id(), input, used)?;
+			},
+		TypeDef::Primitive(primitive) => {
+			let len = match primitive {
+				TypeDefPrimitive::Bool | TypeDefPrimitive::U8 | TypeDefPrimitive::I8 => 1,
+				TypeDefPrimitive::U16 | TypeDefPrimitive::I16 => 2,
+				TypeDefPrimitive::Char | TypeDefPrimitive::U32 | TypeDefPrimitive::I32 => 4,
+				TypeDefPrimitive::U64 | TypeDefPrimitive::I64 => 8,
+				TypeDefPrimitive::U128 | TypeDefPrimitive::I128 => 16,
+				TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => 32,
+				TypeDefPrimitive::Str => compact(input)?,
+			};
+			skip(input, len)?;
+		},
+		TypeDef::Compact(inner) => {
+			compact(input)?;
+			close(types, inner.type_param().id(), used);
+		},
+		TypeDef::BitSequence(bits) => {
+			let store = match types.resolve(bits.bit_store_type().id())?.type_def() {
+				TypeDef::Primitive(TypeDefPrimitive::U8) => 1,
+				TypeDef::Primitive(TypeDefPrimitive::U16) => 2,
+				TypeDef::Primitive(TypeDefPrimitive::U32) => 4,
+				TypeDef::Primitive(TypeDefPrimitive::U64) => 8,
+				_ => return None,
+			};
+			let len = compact(input)?;
+			skip(input, (len + store * 8 - 1) / (store * 8) * store)?;
+			used.insert(bits.bit_store_type().id());
+			used.insert(bits.bit_order_type().id());
+		},
+	}
+	Some(())
+}
+
+/// Decodes a compact integer from `input`, as a length.
+fn compact(input: &mut &[u8]) -> Option<usize> {
+	codec::Compact::<u64>::decode(input)
+		.ok()
+		.and_then(|len| usize::try_from(len.0).ok())
+}
+
+/// Skips `len` bytes of `input`.
+fn skip(input: &mut &[u8], len: usize) -> Option<()> {
+	*input = input.get(len..)?;
+	Some(())
+}
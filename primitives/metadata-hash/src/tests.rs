@@ -0,0 +1,203 @@
+use crate::*;
+use frame_metadata::{
+	ExtrinsicMetadata, PalletCallMetadata, PalletConstantMetadata, PalletMetadata,
+	SignedExtensionMetadata,
+};
+use scale_info::{meta_type, TypeInfo};
+use sp_std::marker::PhantomData;
+
+#[derive(TypeInfo)]
+struct Extrinsic<Address, Call, Signature, Extra>(PhantomData<(Address, Call, Signature, Extra)>);
+
+#[derive(Encode, TypeInfo)]
+enum Call {
+	#[codec(index = 0)]
+	System(SystemCall),
+	#[codec(index = 8)]
+	Template(TemplateCall),
+}
+
+#[derive(Encode, TypeInfo)]
+enum SystemCall {
+	#[codec(index = 0)]
+	Remark { remark: Vec<u8> },
+	#[codec(index = 1)]
+	SetSecret { secret: Secret },
+}
+
+#[derive(Encode, TypeInfo)]
+struct Secret(u128);
+
+#[derive(Encode, TypeInfo)]
+enum TemplateCall {
+	#[codec(index = 0)]
+	DoSomething {
+		#[codec(compact)]
+		something: u32,
+	},
+}
+
+#[derive(Encode, TypeInfo)]
+struct CheckNonce(#[codec(compact)] u64);
+
+#[derive(Encode, TypeInfo)]
+struct CheckGenesis;
+
+fn metadata() -> RuntimeMetadataV14 {
+	let pallet = |name, index, calls| PalletMetadata {
+		name,
+		storage: None,
+		calls: Some(PalletCallMetadata { ty: calls }),
+		event: None,
+		constants: vec![],
+		error: None,
+		index,
+	};
+	let mut system = pallet("System", 0, meta_type::<SystemCall>());
+	system.constants.push(PalletConstantMetadata {
+		name: "SS58Prefix",
+		ty: meta_type::<u16>(),
+		value: 7u16.encode(),
+		docs: vec![],
+	});
+
+	let signed_extensions = vec![
+		SignedExtensionMetadata {
+			identifier: "CheckNonce",
+			ty: meta_type::<CheckNonce>(),
+			additional_signed: meta_type::<()>(),
+		},
+		SignedExtensionMetadata {
+			identifier: "CheckGenesis",
+			ty: meta_type::<CheckGenesis>(),
+			additional_signed: meta_type::<[u8; 32]>(),
+		},
+	];
+	let extrinsic = ExtrinsicMetadata {
+		ty: meta_type::<Extrinsic<[u8; 32], Call, [u8; 64], (CheckNonce, CheckGenesis)>>(),
+		version: 4,
+		signed_extensions,
+	};
+
+	RuntimeMetadataV14::new(
+		vec![system, pallet("TemplateModule", 8, meta_type::<TemplateCall>())],
+		extrinsic,
+		meta_type::<()>(),
+	)
+}
+
+fn type_id(metadata: &RuntimeMetadataV14, name: &str) -> u32 {
+	metadata
+		.types
+		.types()
+		.iter()
+		.find(|ty| ty.ty().path().segments().last().map_or(false, |segment| segment == name))
+		.map(|ty| ty.id())
+		.unwrap()
+}
+
+fn ids(proof: &MetadataProof) -> Vec<u32> {
+	proof.leaves.iter().map(|(id, _)| *id).collect()
+}
+
+#[test]
+fn proofs_verify_to_the_metadata_hash() {
+	let metadata = metadata();
+	let hash = metadata_hash(&metadata, "node-template", 1);
+
+	let calls = [
+		Call::Template(TemplateCall::DoSomething { something: 42 }),
+		Call::System(SystemCall::Remark { remark: vec![1, 2, 3] }),
+		Call::System(SystemCall::SetSecret { secret: Secret(7) }),
+	];
+	for call in calls {
+		let proof = metadata_proof(&metadata, "node-template", 1, &call.encode()).unwrap();
+		assert_eq!(proof.verify(), Some(hash));
+		assert!(proof.leaves.len() < proof.leaf_count as usize);
+	}
+}
+
+#[test]
+fn proofs_hold_the_types_of_the_call() {
+	let metadata = metadata();
+	let secret = type_id(&metadata, "Secret");
+	let template = type_id(&metadata, "TemplateCall");
+
+	let call = Call::System(SystemCall::SetSecret { secret: Secret(7) }).encode();
+	let proof = metadata_proof(&metadata, "node-template", 1, &call).unwrap();
+	assert!(ids(&proof).contains(&secret));
+	assert!(!ids(&proof).contains(&template));
+
+	// Other variants of the same call enum don't need their fields.
+	let call = Call::System(SystemCall::Remark { remark: vec![] }).encode();
+	let proof = metadata_proof(&metadata, "node-template", 1, &call).unwrap();
+	assert!(!ids(&proof).contains(&secret));
+
+	// The signed extensions are always in.
+	assert!(ids(&proof).contains(&type_id(&metadata, "CheckNonce")));
+	assert!(ids(&proof).contains(&type_id(&metadata, "CheckGenesis")));
+}
+
+#[test]
+fn tampered_proofs_verify_to_another_hash() {
+	let metadata = metadata();
+	let hash = metadata_hash(&metadata, "node-template", 1);
+	let call = Call::Template(TemplateCall::DoSomething { something: 42 }).encode();
+	let proof = metadata_proof(&metadata, "node-template", 1, &call).unwrap();
+
+	let mut forged = proof.clone();
+	forged.leaves[0].1.push(0);
+	assert_ne!(forged.verify(), Some(hash));
+
+	let mut forged = proof.clone();
+	forged.extra =
+		ExtraInfo { spec_name: b"other".to_vec(), spec_version: 1, ss58_prefix: 7 }.encode();
+	assert_ne!(forged.verify(), Some(hash));
+
+	let mut forged = proof.clone();
+	forged.nodes.pop();
+	assert_eq!(forged.verify(), None);
+
+	let mut forged = proof;
+	forged.leaves.clear();
+	assert_eq!(forged.verify(), None);
+}
+
+#[test]
+fn hash_commits_to_the_version() {
+	let metadata = metadata();
+	let hash = metadata_hash(&metadata, "node-template", 1);
+
+	assert_ne!(metadata_hash(&metadata, "node-template", 2), hash);
+	assert_ne!(metadata_hash(&metadata, "other", 1), hash);
+
+	let call = Call::Template(TemplateCall::DoSomething { something: 42 }).encode();
+	let proof = metadata_proof(&metadata, "node-template", 1, &call).unwrap();
+	let extra = ExtraInfo::decode(&mut &proof.extra[..]).unwrap();
+	assert_eq!(
+		extra,
+		ExtraInfo { spec_name: b"node-template".to_vec(), spec_version: 1, ss58_prefix: 7 }
+	);
+}
+
+#[test]
+fn calls_that_dont_decode_have_no_proof() {
+	let metadata = metadata();
+	let mut call = Call::Template(TemplateCall::DoSomething { something: 42 }).encode();
+
+	assert_eq!(metadata_proof(&metadata, "node-template", 1, &[1, 0]), None);
+	assert_eq!(metadata_proof(&metadata, "node-template", 1, &call[..1]), None);
+	call.push(0);
+	assert_eq!(metadata_proof(&metadata, "node-template", 1, &call), None);
+}
+
+#[test]
+fn decode_hex_works() {
+	let hash = [0xab; 32];
+	let hex = "ab".repeat(32);
+
+	assert_eq!(decode_hex(&hex), Some(hash));
+	assert_eq!(decode_hex(&format!("0x{}", hex.to_uppercase())), Some(hash));
+	assert_eq!(decode_hex(&hex[2..]), None);
+	assert_eq!(decode_hex(&format!("{}zz", &hex[2..])), None);
+}
@@ -1,9 +1,90 @@
 use substrate_wasm_builder::WasmBuilder;
 
+/// The variable the metadata hash is passed to the runtime in, as hex.
+#[cfg(feature = "metadata-hash")]
+const METADATA_HASH: &str = "RUNTIME_METADATA_HASH";
+
 fn main() {
+	build_wasm();
+
+	#[cfg(feature = "metadata-hash")]
+	metadata::enable();
+}
+
+fn build_wasm() {
 	WasmBuilder::new()
 		.with_current_project()
 		.export_heap_base()
 		.import_memory()
 		.build()
 }
+
+/// Computes the metadata hash `CheckMetadataHash` signs over.
+///
+/// The metadata is only known once the runtime is built, so this builds it once, reads the
+/// metadata out of its wasm binary and builds it again with the hash in [`METADATA_HASH`]. The
+/// hash doesn't change the metadata, so both builds have the same one.
+#[cfg(feature = "metadata-hash")]
+mod metadata {
+	use super::{build_wasm, METADATA_HASH};
+	use codec::Decode;
+	use frame_metadata::RuntimeMetadataPrefixed;
+	use sc_executor::{WasmExecutionMethod, WasmExecutor};
+	use sc_executor_common::runtime_blob::RuntimeBlob;
+	use std::{env, fs, path::PathBuf};
+
+	pub fn enable() {
+		println!("cargo:rerun-if-env-changed={}", METADATA_HASH);
+		// The runtime is built from within its own wasm build, where there is nothing to hash.
+		if env::var_os("SKIP_WASM_BUILD").is_some() {
+			return
+		}
+		let wasm = match wasm_binary() {
+			Some(wasm) => wasm,
+			None => return,
+		};
+
+		let hash = hash(&wasm).unwrap_or_else(|e| panic!("Failed to hash the metadata: {}", e));
+		let hex: String = hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+		env::set_var(METADATA_HASH, &hex);
+		build_wasm();
+		println!("cargo:rustc-env={}={}", METADATA_HASH, hex);
+	}
+
+	/// Reads the wasm binary the wasm builder included, if it built one.
+	fn wasm_binary() -> Option<Vec<u8>> {
+		let out_dir = PathBuf::from(env::var("OUT_DIR").expect("`OUT_DIR` is set by cargo"));
+		let include = fs::read_to_string(out_dir.join("wasm_binary.rs")).ok()?;
+		let start = include.find("include_bytes!(\"")? + "include_bytes!(\"".len();
+		let end = start + include[start..].find('"')?;
+		fs::read(&include[start..end]).ok()
+	}
+
+	fn hash(wasm: &[u8]) -> Result<[u8; 32], String> {
+		let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::new(
+			WasmExecutionMethod::Interpreted,
+			None,
+			1,
+			None,
+			1,
+		);
+		let call = |method: &str| {
+			let blob = RuntimeBlob::uncompress_if_needed(wasm).map_err(|e| e.to_string())?;
+			let mut ext = sp_io::TestExternalities::default();
+			executor
+				.uncached_call(blob, &mut ext.ext(), true, method, &[])
+				.map_err(|e| e.to_string())
+		};
+
+		let metadata = Vec::<u8>::decode(&mut &call("Metadata_metadata")?[..])
+			.and_then(|metadata| RuntimeMetadataPrefixed::decode(&mut &metadata[..]))
+			.map_err(|e| e.to_string())?;
+		let metadata = metadata_hash::v14(&metadata).ok_or("The metadata is not V14")?;
+		// `RuntimeVersion` starts with its spec name, impl name, authoring and spec versions.
+		let (spec_name, _, _, spec_version) =
+			<(String, String, u32, u32)>::decode(&mut &call("Core_version")?[..])
+				.map_err(|e| e.to_string())?;
+
+		Ok(metadata_hash::metadata_hash(metadata, &spec_name, spec_version))
+	}
+}
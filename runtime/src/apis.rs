@@ -0,0 +1,190 @@
+//! Runtime APIs declared by this runtime, and their implementations.
+
+use crate::{
+	extensions::Payer, AccountId, Balance, Balances, Executive, Index, Runtime, RuntimeCall,
+	RuntimeEvent, RuntimeOrigin, System, Weight, EXISTENTIAL_DEPOSIT, VERSION,
+};
+use codec::{Decode, Encode};
+use frame_support::dispatch::{extract_actual_weight, GetDispatchInfo};
+use metadata_hash::MetadataProof;
+use pallet_balances::{BalanceLock, ReserveData};
+use sp_runtime::{
+	traits::{Block as BlockT, Dispatchable, StaticLookup},
+	ApplyExtrinsicResult, RuntimeDebug,
+};
+use sp_std::prelude::*;
+
+/// The effects of a dry run.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct DryRunResult {
+	/// Whether the extrinsic was valid and, if so, the result of its dispatch. Calls are always
+	/// valid.
+	pub result: ApplyExtrinsicResult,
+	/// The events the call emitted.
+	pub events: Vec<RuntimeEvent>,
+	/// The weight the call actually consumed.
+	pub weight: Weight,
+}
+
+/// The balances and nonce of an account.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct AccountSummary {
+	pub free: Balance,
+	pub reserved: Balance,
+	/// The part of the free balance that can't be transferred, the larger of the two amounts
+	/// frozen for fees and for everything else.
+	pub frozen: Balance,
+	pub locks: Vec<BalanceLock<Balance>>,
+	pub reserves: Vec<ReserveData<[u8; 8], Balance>>,
+	/// The nonce of the next transaction, not counting those still in a transaction pool.
+	pub nonce: Index,
+	pub existential_deposit: Balance,
+	pub warning: Option<ExistentialDepositWarning>,
+}
+
+/// How the existential deposit restricts transfers from or to an account.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub enum ExistentialDepositWarning {
+	/// The account doesn't exist, so transfers to it must be at least the existential deposit.
+	NotExisting,
+	/// The free balance is below the existential deposit and only the reserved balance keeps the
+	/// account alive, so transfers that keep it alive are impossible.
+	FreeBelowExistentialDeposit,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Applies calls and extrinsics on top of a block.
+	///
+	/// The changes are left in the runtime API's overlay, where the caller can inspect them before
+	/// discarding them; nothing is ever written to the database.
+	pub trait DryRunApi {
+		/// Dispatches `call` from the signed `origin`, or from Root if `None`.
+		fn dry_run_call(origin: Option<AccountId>, call: RuntimeCall) -> DryRunResult;
+
+		/// Applies the signed `extrinsic`, including its checks and fees.
+		fn dry_run_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> DryRunResult;
+	}
+
+	/// Summarizes the balances and nonces of accounts.
+	pub trait AccountApi {
+		/// The summary of `account`.
+		fn account_summary(account: AccountId) -> AccountSummary;
+
+		/// The summaries of `accounts`, in the same order.
+		fn account_summaries(accounts: Vec<AccountId>) -> Vec<AccountSummary>;
+	}
+
+	/// Tells who pays the fees of transactions, complementing `TransactionPaymentApi`.
+	pub trait PaymentApi {
+		/// The party that pays the fee of `extrinsic`.
+		fn payer(extrinsic: <Block as BlockT>::Extrinsic) -> Payer;
+	}
+
+	/// Lets offline signers check the metadata they decode transactions with against the hash
+	/// `CheckMetadataHash` commits to.
+	pub trait MetadataHashApi {
+		/// The metadata hash, or `None` if the runtime was built without the `metadata-hash`
+		/// feature.
+		fn metadata_hash() -> Option<[u8; 32]>;
+
+		/// The types needed to decode the call of `extrinsic` and its signed extensions, proven
+		/// against the metadata hash, or `None` if the call is unknown. `extrinsic` may be
+		/// unsigned.
+		fn metadata_proof(extrinsic: <Block as BlockT>::Extrinsic) -> Option<MetadataProof>;
+	}
+}
+
+/// See [`DryRunApi::dry_run_call`].
+pub fn dry_run_call(origin: Option<AccountId>, call: RuntimeCall) -> DryRunResult {
+	let info = call.get_dispatch_info();
+	let origin = match origin {
+		Some(who) => RuntimeOrigin::signed(who),
+		None => RuntimeOrigin::root(),
+	};
+	let first_event = System::event_count();
+	let outcome = call.dispatch(origin);
+
+	DryRunResult {
+		weight: extract_actual_weight(&outcome, &info),
+		result: Ok(outcome.map(|_| ()).map_err(|e| e.error)),
+		events: events_since(first_event),
+	}
+}
+
+/// See [`DryRunApi::dry_run_extrinsic`].
+pub fn dry_run_extrinsic(extrinsic: <crate::Block as BlockT>::Extrinsic) -> DryRunResult {
+	let first_event = System::event_count();
+	let result = Executive::apply_extrinsic(extrinsic);
+	let events = events_since(first_event);
+
+	// The actual weight, including the base extrinsic weight, is only reported in the event
+	// concluding the extrinsic; invalid extrinsics have none.
+	let weight = events
+		.iter()
+		.find_map(|event| match event {
+			RuntimeEvent::System(frame_system::Event::ExtrinsicSuccess { dispatch_info }) |
+			RuntimeEvent::System(frame_system::Event::ExtrinsicFailed {
+				dispatch_info, ..
+			}) => Some(dispatch_info.weight),
+			_ => None,
+		})
+		.unwrap_or_default();
+
+	DryRunResult { result, events, weight }
+}
+
+/// The events deposited after the first `count` of the block.
+fn events_since(count: u32) -> Vec<RuntimeEvent> {
+	frame_system::Pallet::<Runtime>::events()
+		.into_iter()
+		.skip(count as usize)
+		.map(|record| record.event)
+		.collect()
+}
+
+/// See [`AccountApi::account_summary`].
+pub fn account_summary(account: AccountId) -> AccountSummary {
+	let data = System::account(&account).data;
+	let warning = if !System::account_exists(&account) {
+		Some(ExistentialDepositWarning::NotExisting)
+	} else if data.free < EXISTENTIAL_DEPOSIT {
+		Some(ExistentialDepositWarning::FreeBelowExistentialDeposit)
+	} else {
+		None
+	};
+
+	AccountSummary {
+		free: data.free,
+		reserved: data.reserved,
+		frozen: data.misc_frozen.max(data.fee_frozen),
+		locks: Balances::locks(&account).into_inner(),
+		reserves: Balances::reserves(&account).into_inner(),
+		nonce: System::account_nonce(&account),
+		existential_deposit: EXISTENTIAL_DEPOSIT,
+		warning,
+	}
+}
+
+/// See [`PaymentApi::payer`].
+pub fn payer(extrinsic: <crate::Block as BlockT>::Extrinsic) -> Payer {
+	let (address, extra) = match extrinsic.signature {
+		Some((address, _, extra)) => (address, extra),
+		None => return Payer::Nobody,
+	};
+
+	match <Runtime as frame_system::Config>::Lookup::lookup(address) {
+		Ok(who) => crate::payment_extension(&extra).payer(&who, &extrinsic.function),
+		Err(_) => Payer::Nobody,
+	}
+}
+
+/// See [`MetadataHashApi::metadata_proof`].
+pub fn metadata_proof(extrinsic: <crate::Block as BlockT>::Extrinsic) -> Option<MetadataProof> {
+	let metadata = Runtime::metadata();
+	metadata_hash::metadata_proof(
+		metadata_hash::v14(&metadata)?,
+		&VERSION.spec_name,
+		VERSION.spec_version,
+		&extrinsic.function.encode(),
+	)
+}
@@ -0,0 +1,489 @@
+//! Signed extensions specific to this runtime.
+
+use crate::{
+	AccountId, Balance, Feeless, Hash, Index, MaxPendingPerSender, OperationalPriorityBoost,
+	Runtime, RuntimeCall, Sponsorship, System, TemplateModulePriorityBoost, TransactionPayment,
+};
+use codec::{Decode, Encode};
+use frame_support::{
+	dispatch::DispatchClass, traits::Get, CloneNoBound, EqNoBound, PartialEqNoBound,
+	RuntimeDebugNoBound,
+};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	generic::Era,
+	traits::{DispatchInfoOf, PostDispatchInfoOf, SignedExtension},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+	DispatchResult, RuntimeDebug,
+};
+use sp_std::marker::PhantomData;
+
+/// The custom error of transactions whose sender already has [`MaxPendingPerSender`]
+/// transactions pending ahead of them.
+pub const TOO_MANY_PENDING: u8 = 1;
+
+/// Whether [`pallet_feeless`] waives the fee of `call`, sent by `who` without a tip, while `who`
+/// has quota left.
+///
+/// `who` needs no account, [`pallet_feeless`] provides one when the transaction spends quota.
+fn is_fee_waived(who: &AccountId, call: &RuntimeCall) -> bool {
+	Feeless::is_allowed(who, call)
+}
+
+/// Raises the pool priority of operational calls by [`OperationalPriorityBoost`] and of
+/// `TemplateModule` calls by [`TemplateModulePriorityBoost`], on top of the priority their fee and
+/// tip buy.
+///
+/// Calls whose fee their sender may have waived get no boost, so that free transactions don't
+/// crowd out paid ones. This extension doesn't see the tip, so a tipped transaction of such a
+/// sender gets no boost either, even though it pays its fee; its fee and tip still buy priority.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub struct PrioritizeCalls;
+
+impl PrioritizeCalls {
+	/// The boost `call`, sent by `who`, gets.
+	pub fn boost(who: &AccountId, call: &RuntimeCall, info: &DispatchInfoOf<RuntimeCall>) -> u64 {
+		if is_fee_waived(who, call) && Feeless::remaining(who) > 0 {
+			return 0
+		}
+
+		let class = match info.class {
+			DispatchClass::Operational => OperationalPriorityBoost::get(),
+			_ => 0,
+		};
+		let pallet = match call {
+			RuntimeCall::TemplateModule(_) => TemplateModulePriorityBoost::get(),
+			_ => 0,
+		};
+
+		class.saturating_add(pallet)
+	}
+}
+
+impl SignedExtension for PrioritizeCalls {
+	const IDENTIFIER: &'static str = "PrioritizeCalls";
+	type AccountId = AccountId;
+	type Call = RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &AccountId,
+		call: &RuntimeCall,
+		info: &DispatchInfoOf<RuntimeCall>,
+		_len: usize,
+	) -> TransactionValidity {
+		Ok(ValidTransaction { priority: Self::boost(who, call, info), ..Default::default() })
+	}
+
+	fn pre_dispatch(
+		self,
+		_who: &AccountId,
+		_call: &RuntimeCall,
+		_info: &DispatchInfoOf<RuntimeCall>,
+		_len: usize,
+	) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+}
+
+/// The custom error of feeless transactions whose sender's pending transactions spend the rest of
+/// its quota.
+pub const QUOTA_SPENT: u8 = 6;
+
+/// [`frame_system::CheckNonce`], which also rejects transactions more than
+/// [`MaxPendingPerSender`] nonces ahead of their sender's account, and feeless ones that more
+/// than the sender's remaining [`pallet_feeless`] quota are ahead of.
+///
+/// A sender's pending transactions have consecutive nonces starting at the account's, so this
+/// caps how many of them a transaction pool holds. It encodes like and has the identifier of the
+/// extension it wraps.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub struct CheckNonce(#[codec(compact)] pub Index);
+
+impl From<Index> for CheckNonce {
+	fn from(nonce: Index) -> Self {
+		Self(nonce)
+	}
+}
+
+impl CheckNonce {
+	fn inner(&self) -> frame_system::CheckNonce<Runtime> {
+		frame_system::CheckNonce::<Runtime>::from(self.0)
+	}
+}
+
+impl SignedExtension for CheckNonce {
+	const IDENTIFIER: &'static str = "CheckNonce";
+	type AccountId = AccountId;
+	type Call = RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &AccountId,
+		call: &RuntimeCall,
+		info: &DispatchInfoOf<RuntimeCall>,
+		len: usize,
+	) -> TransactionValidity {
+		let account_nonce = frame_system::Pallet::<Runtime>::account_nonce(who);
+		if self.0 >= account_nonce.saturating_add(MaxPendingPerSender::get()) {
+			return Err(InvalidTransaction::Custom(TOO_MANY_PENDING).into())
+		}
+
+		let remaining = Feeless::remaining(who);
+		if remaining > 0 &&
+			is_fee_waived(who, call) &&
+			self.0 >= account_nonce.saturating_add(remaining)
+		{
+			return Err(InvalidTransaction::Custom(QUOTA_SPENT).into())
+		}
+
+		self.inner().validate(who, call, info, len)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &AccountId,
+		call: &RuntimeCall,
+		info: &DispatchInfoOf<RuntimeCall>,
+		len: usize,
+	) -> Result<(), TransactionValidityError> {
+		self.inner().pre_dispatch(who, call, info, len)
+	}
+}
+
+/// The custom error of transactions whose sponsor doesn't pay for their sender or call, or can't
+/// afford their fee.
+pub const NOT_SPONSORED: u8 = 2;
+
+/// The custom error of sponsored transactions with a tip, which would be taken from the sponsor.
+pub const SPONSORED_TIP: u8 = 7;
+
+/// The party that pays the fee of a transaction.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub enum Payer {
+	/// The sender of the transaction.
+	Sender(AccountId),
+	/// A sponsor registered with [`pallet_sponsorship`].
+	Sponsor(AccountId),
+	/// Nobody, as the transaction is unsigned or feeless.
+	Nobody,
+}
+
+/// How [`ChargeSponsoredTransactionPayment`] charged a transaction.
+pub enum Payment {
+	/// The transaction is feeless.
+	Free,
+	/// The sender pays, like with [`ChargeTransactionPayment`].
+	Sender(<ChargeTransactionPayment<Runtime> as SignedExtension>::Pre),
+	/// The sponsor pays, `withdrawn` being the fee earmarked before dispatch.
+	Sponsor { sponsor: AccountId, who: AccountId, withdrawn: Balance },
+}
+
+/// [`ChargeTransactionPayment`], which lets a [`pallet_sponsorship`] sponsor pay the fee, and
+/// waives the fee of transactions [`pallet_feeless`] allows while their sender has quota left.
+///
+/// Feeless transactions need no tip, nor a sender account; tipped ones, and those of senders whose
+/// quota is spent, are charged as usual. Transactions naming a sponsor are invalid unless
+/// it pays for their sender and call and has their fee left in its budget, or if they have a
+/// tip. Feeless transactions get no priority from their fee.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub struct ChargeSponsoredTransactionPayment {
+	/// Tip for the block author.
+	#[codec(compact)]
+	pub tip: Balance,
+	/// The sponsor paying the fee instead of the sender.
+	pub sponsor: Option<AccountId>,
+}
+
+impl From<Balance> for ChargeSponsoredTransactionPayment {
+	fn from(tip: Balance) -> Self {
+		Self { tip, sponsor: None }
+	}
+}
+
+impl ChargeSponsoredTransactionPayment {
+	/// Has `sponsor` pay the fee, without a tip.
+	pub fn sponsored(sponsor: AccountId) -> Self {
+		Self { tip: 0, sponsor: Some(sponsor) }
+	}
+
+	/// The party that pays the fee of `call`, sent by `who`.
+	pub fn payer(&self, who: &AccountId, call: &RuntimeCall) -> Payer {
+		match &self.sponsor {
+			_ if self.is_feeless(who, call) && Feeless::remaining(who) > 0 => Payer::Nobody,
+			Some(sponsor) => Payer::Sponsor(sponsor.clone()),
+			None => Payer::Sender(who.clone()),
+		}
+	}
+
+	fn inner(&self) -> ChargeTransactionPayment<Runtime> {
+		ChargeTransactionPayment::<Runtime>::from(self.tip)
+	}
+
+	/// The fee `sponsor` pays, if it covers the transaction.
+	fn sponsored_fee(
+		&self,
+		sponsor: &AccountId,
+		who: &AccountId,
+		call: &RuntimeCall,
+		info: &DispatchInfoOf<RuntimeCall>,
+		len: usize,
+	) -> Result<Balance, TransactionValidityError> {
+		if self.tip > 0 {
+			return Err(InvalidTransaction::Custom(SPONSORED_TIP).into())
+		}
+
+		let fee = TransactionPayment::compute_fee(len as u32, info, 0);
+		if !Sponsorship::covers(sponsor, who, call, fee) {
+			return Err(InvalidTransaction::Custom(NOT_SPONSORED).into())
+		}
+		Ok(fee)
+	}
+
+	/// Whether the transaction qualifies for a waived fee, quota aside.
+	fn is_feeless(&self, who: &AccountId, call: &RuntimeCall) -> bool {
+		self.tip == 0 && is_fee_waived(who, call)
+	}
+}
+
+impl SignedExtension for ChargeSponsoredTransactionPayment {
+	const IDENTIFIER: &'static str = "ChargeSponsoredTransactionPayment";
+	type AccountId = AccountId;
+	type Call = RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = Payment;
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &AccountId,
+		call: &RuntimeCall,
+		info: &DispatchInfoOf<RuntimeCall>,
+		len: usize,
+	) -> TransactionValidity {
+		match self.payer(who, call) {
+			Payer::Nobody => Ok(ValidTransaction::default()),
+			Payer::Sponsor(sponsor) => {
+				let fee = self.sponsored_fee(&sponsor, who, call, info, len)?;
+				let priority = ChargeTransactionPayment::<Runtime>::get_priority(info, len, 0, fee);
+				Ok(ValidTransaction { priority, ..Default::default() })
+			},
+			Payer::Sender(_) => self.inner().validate(who, call, info, len),
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &AccountId,
+		call: &RuntimeCall,
+		info: &DispatchInfoOf<RuntimeCall>,
+		len: usize,
+	) -> Result<Payment, TransactionValidityError> {
+		if self.is_feeless(who, call) && Feeless::use_free_transaction(who) {
+			return Ok(Payment::Free)
+		}
+
+		match self.sponsor {
+			Some(sponsor) => {
+				let fee = self.sponsored_fee(&sponsor, who, call, info, len)?;
+				if !Sponsorship::withdraw_fee(&sponsor, who, call, fee) {
+					return Err(InvalidTransaction::Custom(NOT_SPONSORED).into())
+				}
+
+				Ok(Payment::Sponsor { sponsor, who: who.clone(), withdrawn: fee })
+			},
+			None => self.inner().pre_dispatch(who, call, info, len).map(Payment::Sender),
+		}
+	}
+
+	fn post_dispatch(
+		pre: Option<Payment>,
+		info: &DispatchInfoOf<RuntimeCall>,
+		post_info: &PostDispatchInfoOf<RuntimeCall>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		match pre {
+			Some(Payment::Sender(pre)) => ChargeTransactionPayment::<Runtime>::post_dispatch(
+				Some(pre),
+				info,
+				post_info,
+				len,
+				result,
+			),
+			Some(Payment::Sponsor { sponsor, who, withdrawn }) => {
+				let actual_fee =
+					TransactionPayment::compute_actual_fee(len as u32, info, post_info, 0);
+				let actual_fee = Sponsorship::settle_fee(&sponsor, &who, withdrawn, actual_fee);
+				// Like for fees paid by senders, so that fees show up in the same place.
+				System::deposit_event(
+					pallet_transaction_payment::Event::<Runtime>::TransactionFeePaid {
+						who: sponsor,
+						actual_fee,
+						tip: 0,
+					},
+				);
+				Ok(())
+			},
+			Some(Payment::Free) | None => Ok(()),
+		}
+	}
+}
+
+/// The custom error of transactions committing to the metadata hash when the runtime was built
+/// without one.
+pub const NO_METADATA_HASH: u8 = 3;
+
+/// The hash of the runtime's metadata, computed by the build script if the runtime is built with
+/// the `metadata-hash` feature.
+pub const METADATA_HASH: Option<[u8; 32]> = match option_env!("RUNTIME_METADATA_HASH") {
+	Some(hex) => match metadata_hash::decode_hex(hex) {
+		Some(hash) => Some(hash),
+		None => panic!("`RUNTIME_METADATA_HASH` is not a hex encoded hash"),
+	},
+	None => None,
+};
+
+/// Whether a transaction commits to the metadata hash.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub enum Mode {
+	Disabled,
+	Enabled,
+}
+
+/// Has transactions sign over [`METADATA_HASH`], if their [`Mode`] is enabled.
+///
+/// An offline signer decodes the call it signs with metadata it got from elsewhere. Committing
+/// to the metadata hash makes the signature invalid if that metadata was not the runtime's, so
+/// the signer can't be tricked into signing a call other than the one it shows.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub struct CheckMetadataHash {
+	pub mode: Mode,
+}
+
+impl CheckMetadataHash {
+	/// Commits to the metadata hash if `enabled`.
+	pub fn new(enabled: bool) -> Self {
+		Self { mode: if enabled { Mode::Enabled } else { Mode::Disabled } }
+	}
+}
+
+impl SignedExtension for CheckMetadataHash {
+	const IDENTIFIER: &'static str = "CheckMetadataHash";
+	type AccountId = AccountId;
+	type Call = RuntimeCall;
+	type AdditionalSigned = Option<[u8; 32]>;
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<Option<[u8; 32]>, TransactionValidityError> {
+		match self.mode {
+			Mode::Disabled => Ok(None),
+			Mode::Enabled => METADATA_HASH
+				.map(Some)
+				.ok_or_else(|| InvalidTransaction::Custom(NO_METADATA_HASH).into()),
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		_who: &AccountId,
+		_call: &RuntimeCall,
+		_info: &DispatchInfoOf<RuntimeCall>,
+		_len: usize,
+	) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+}
+
+/// The custom error of immortal transactions.
+pub const IMMORTAL: u8 = 4;
+
+/// The custom error of transactions valid for more blocks than their [`CheckEra`] allows, e.g.
+/// [`MaxMortality`](crate::MaxMortality).
+pub const MORTALITY_TOO_LONG: u8 = 5;
+
+/// [`frame_system::CheckEra`], which also rejects immortal transactions and those valid for more
+/// than `M` blocks.
+///
+/// An account that is reaped and recreated starts over at nonce zero, so its old transactions
+/// would be valid again if they never expired. Mortal ones can only be replayed until their era
+/// ends. It encodes like and has the identifier of the extension it wraps.
+#[derive(
+	CloneNoBound, PartialEqNoBound, EqNoBound, Encode, Decode, TypeInfo, RuntimeDebugNoBound,
+)]
+#[scale_info(skip_type_params(M))]
+pub struct CheckEra<M>(pub Era, PhantomData<M>);
+
+impl<M> From<Era> for CheckEra<M> {
+	fn from(era: Era) -> Self {
+		Self(era, PhantomData)
+	}
+}
+
+impl<M: Get<u64>> CheckEra<M> {
+	fn inner(&self) -> frame_system::CheckEra<Runtime> {
+		frame_system::CheckEra::<Runtime>::from(self.0)
+	}
+
+	/// Rejects eras the runtime doesn't allow.
+	fn check(&self) -> Result<(), TransactionValidityError> {
+		match self.0 {
+			Era::Immortal => Err(InvalidTransaction::Custom(IMMORTAL).into()),
+			Era::Mortal(period, _) if period > M::get() =>
+				Err(InvalidTransaction::Custom(MORTALITY_TOO_LONG).into()),
+			Era::Mortal(..) => Ok(()),
+		}
+	}
+}
+
+impl<M: Get<u64> + Send + Sync + 'static> SignedExtension for CheckEra<M> {
+	const IDENTIFIER: &'static str = "CheckMortality";
+	type AccountId = AccountId;
+	type Call = RuntimeCall;
+	type AdditionalSigned = Hash;
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<Hash, TransactionValidityError> {
+		self.inner().additional_signed()
+	}
+
+	fn validate(
+		&self,
+		who: &AccountId,
+		call: &RuntimeCall,
+		info: &DispatchInfoOf<RuntimeCall>,
+		len: usize,
+	) -> TransactionValidity {
+		self.check()?;
+		self.inner().validate(who, call, info, len)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &AccountId,
+		call: &RuntimeCall,
+		info: &DispatchInfoOf<RuntimeCall>,
+		len: usize,
+	) -> Result<(), TransactionValidityError> {
+		self.check()?;
+		self.inner().pre_dispatch(who, call, info, len)
+	}
+}
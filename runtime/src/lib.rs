@@ -6,10 +6,14 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+pub mod apis;
+pub mod extensions;
+
 use pallet_grandpa::{
 	fg_primitives, AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList,
 };
 use sp_api::impl_runtime_apis;
+#[cfg(not(feature = "babe"))]
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
@@ -17,7 +21,7 @@ use sp_runtime::{
 	traits::{
 		AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, One, Verify,
 	},
-	transaction_validity::{TransactionSource, TransactionValidity},
+	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
 use sp_std::prelude::*;
@@ -29,7 +33,8 @@ use sp_version::RuntimeVersion;
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{
-		ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, Randomness, StorageInfo,
+		ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, OnTimestampSet, Randomness,
+		StorageInfo,
 	},
 	weights::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
@@ -38,13 +43,21 @@ pub use frame_support::{
 	StorageValue,
 };
 pub use frame_system::Call as SystemCall;
+use frame_system::EnsureRoot;
 pub use pallet_balances::Call as BalancesCall;
+pub use pallet_sudo::Call as SudoCall;
 pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::{ConstFeeMultiplier, CurrencyAdapter, Multiplier};
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
 pub use sp_runtime::{Perbill, Permill};
 
+/// Import the proof-of-work difficulty pallet.
+pub use pallet_difficulty;
+/// Import the feeless transactions pallet.
+pub use pallet_feeless;
+/// Import the fee sponsorship pallet.
+pub use pallet_sponsorship;
 /// Import the template pallet.
 pub use pallet_template;
 
@@ -83,12 +96,21 @@ pub mod opaque {
 	/// Opaque block identifier type.
 	pub type BlockId = generic::BlockId<Block>;
 
+	#[cfg(not(feature = "babe"))]
 	impl_opaque_keys! {
 		pub struct SessionKeys {
 			pub aura: Aura,
 			pub grandpa: Grandpa,
 		}
 	}
+
+	#[cfg(feature = "babe")]
+	impl_opaque_keys! {
+		pub struct SessionKeys {
+			pub babe: Babe,
+			pub grandpa: Grandpa,
+		}
+	}
 }
 
 // To learn more about runtime versioning, see:
@@ -103,17 +125,17 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 112,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 4,
 	state_version: 1,
 };
 
 /// This determines the average expected block time that we are targeting.
 /// Blocks will be produced at a minimum duration defined by `SLOT_DURATION`.
 /// `SLOT_DURATION` is picked up by `pallet_timestamp` which is in turn picked
-/// up by `pallet_aura` to implement `fn slot_duration()`.
+/// up by `pallet_aura` (or `pallet_babe`) to implement `fn slot_duration()`.
 ///
 /// Change this to adjust the block time.
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
@@ -127,6 +149,29 @@ pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
 
+// NOTE: Currently it is not possible to change the epoch duration after the chain has started.
+//       Attempting to do so will brick block production.
+#[cfg(feature = "babe")]
+pub const EPOCH_DURATION_IN_BLOCKS: BlockNumber = 10 * MINUTES;
+#[cfg(feature = "babe")]
+pub const EPOCH_DURATION_IN_SLOTS: u64 = {
+	const SLOT_FILL_RATE: f64 = MILLISECS_PER_BLOCK as f64 / SLOT_DURATION as f64;
+
+	(EPOCH_DURATION_IN_BLOCKS as f64 * SLOT_FILL_RATE) as u64
+};
+
+/// 1 in 4 blocks (on average, not counting collisions) will be primary BABE blocks.
+#[cfg(feature = "babe")]
+pub const PRIMARY_PROBABILITY: (u64, u64) = (1, 4);
+
+/// The BABE epoch configuration at genesis.
+#[cfg(feature = "babe")]
+pub const BABE_GENESIS_EPOCH_CONFIG: sp_consensus_babe::BabeEpochConfiguration =
+	sp_consensus_babe::BabeEpochConfiguration {
+		c: PRIMARY_PROBABILITY,
+		allowed_slots: sp_consensus_babe::AllowedSlots::PrimaryAndSecondaryPlainSlots,
+	};
+
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
 pub fn native_version() -> NativeVersion {
@@ -135,8 +180,14 @@ pub fn native_version() -> NativeVersion {
 
 const NORMAL_DISPATCH_RATIO: Perbill = Perbill::from_percent(75);
 
+/// How many recent block hashes the runtime keeps.
+const BLOCK_HASH_COUNT: BlockNumber = 2400;
+
 parameter_types! {
-	pub const BlockHashCount: BlockNumber = 2400;
+	pub const BlockHashCount: BlockNumber = BLOCK_HASH_COUNT;
+	/// The longest period a transaction may be valid for: the longest era whose first block is
+	/// still among the last `BlockHashCount`, so that its hash can be checked until the era ends.
+	pub const MaxMortality: u64 = (BLOCK_HASH_COUNT as u64 + 1).next_power_of_two() / 2;
 	pub const Version: RuntimeVersion = VERSION;
 	/// We allow for 2 seconds of compute with a 6 second average block time.
 	pub BlockWeights: frame_system::limits::BlockWeights =
@@ -205,12 +256,39 @@ impl frame_system::Config for Runtime {
 
 impl pallet_randomness_collective_flip::Config for Runtime {}
 
+#[cfg(not(feature = "babe"))]
 impl pallet_aura::Config for Runtime {
 	type AuthorityId = AuraId;
 	type DisabledValidators = ();
 	type MaxAuthorities = ConstU32<32>;
 }
 
+#[cfg(feature = "babe")]
+impl pallet_babe::Config for Runtime {
+	type EpochDuration = ConstU64<EPOCH_DURATION_IN_SLOTS>;
+	type ExpectedBlockTime = ConstU64<MILLISECS_PER_BLOCK>;
+	// Without a session pallet the authority set never rotates.
+	type EpochChangeTrigger = pallet_babe::SameAuthoritiesForever;
+	type DisabledValidators = ();
+
+	type KeyOwnerProofSystem = ();
+
+	type KeyOwnerProof = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::Proof;
+
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::IdentificationTuple;
+
+	type HandleEquivocation = ();
+
+	type WeightInfo = ();
+	type MaxAuthorities = ConstU32<32>;
+}
+
 impl pallet_grandpa::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 
@@ -230,10 +308,33 @@ impl pallet_grandpa::Config for Runtime {
 	type MaxAuthorities = ConstU32<32>;
 }
 
+/// The pallet producing blocks in slots, whichever engine the runtime is built with.
+#[cfg(not(feature = "babe"))]
+type SlotConsensus = Aura;
+#[cfg(feature = "babe")]
+type SlotConsensus = Babe;
+
+/// Forwards timestamp updates to [`SlotConsensus`] while it has authorities, and to
+/// [`Difficulty`] otherwise.
+///
+/// Proof-of-work chains run this runtime without any slot authorities, and their blocks carry no
+/// slot digest the timestamp could be checked against. Only they need the difficulty retargeted.
+pub struct ConsensusTimestampHook;
+
+impl OnTimestampSet<u64> for ConsensusTimestampHook {
+	fn on_timestamp_set(moment: u64) {
+		if SlotConsensus::authorities().is_empty() {
+			Difficulty::on_timestamp_set(moment)
+		} else {
+			SlotConsensus::on_timestamp_set(moment)
+		}
+	}
+}
+
 impl pallet_timestamp::Config for Runtime {
 	/// A timestamp: milliseconds since the unix epoch.
 	type Moment = u64;
-	type OnTimestampSet = Aura;
+	type OnTimestampSet = ConsensusTimestampHook;
 	type MinimumPeriod = ConstU64<{ SLOT_DURATION / 2 }>;
 	type WeightInfo = ();
 }
@@ -268,17 +369,52 @@ impl pallet_transaction_payment::Config for Runtime {
 	type FeeMultiplierUpdate = ConstFeeMultiplier<FeeMultiplier>;
 }
 
+parameter_types! {
+	/// The pool priority operational calls get on top of what their fee buys.
+	pub const OperationalPriorityBoost: TransactionPriority = 1 << 48;
+	/// The pool priority `TemplateModule` calls get on top of what their fee buys.
+	pub const TemplateModulePriorityBoost: TransactionPriority = 1 << 32;
+	/// The most transactions of a single sender a pool holds.
+	pub const MaxPendingPerSender: Index = 64;
+}
+
 impl pallet_sudo::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type RuntimeCall = RuntimeCall;
 }
 
+/// Configure the proof-of-work difficulty adjustment in pallets/difficulty.
+impl pallet_difficulty::Config for Runtime {
+	type TargetBlockTime = ConstU64<MILLISECS_PER_BLOCK>;
+	type MinDifficulty = ConstU128<1_000>;
+}
+
+/// Configure the feeless transactions in pallets/feeless, with sudo managing the allowlist.
+impl pallet_feeless::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AllowlistOrigin = EnsureRoot<AccountId>;
+	type FreeTransactionsPerPeriod = ConstU32<10>;
+	type QuotaPeriod = ConstU32<HOURS>;
+	type MaxNameLength = ConstU32<64>;
+}
+
+/// Configure the fee sponsorships in pallets/sponsorship, burning sponsored fees like all others.
+impl pallet_sponsorship::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	// Burned, like the fees senders pay through `OnChargeTransaction`.
+	type OnFee = ();
+	type MaxNameLength = ConstU32<64>;
+	type MaxCallFilters = ConstU32<16>;
+}
+
 /// Configure the pallet-template in pallets/template.
 impl pallet_template::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
+#[cfg(not(feature = "babe"))]
 construct_runtime!(
 	pub struct Runtime
 	where
@@ -296,6 +432,40 @@ construct_runtime!(
 		Sudo: pallet_sudo,
 		// Include the custom logic from the pallet-template in the runtime.
 		TemplateModule: pallet_template,
+		// Proof-of-work difficulty, only consulted by the `pow` chain spec.
+		Difficulty: pallet_difficulty,
+		// Allowlist and quota of feeless transactions.
+		Feeless: pallet_feeless,
+		// Sponsors paying the fees of others.
+		Sponsorship: pallet_sponsorship,
+	}
+);
+
+// The same runtime, but with BABE producing the blocks.
+#[cfg(feature = "babe")]
+construct_runtime!(
+	pub struct Runtime
+	where
+		Block = Block,
+		NodeBlock = opaque::Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		RandomnessCollectiveFlip: pallet_randomness_collective_flip,
+		Timestamp: pallet_timestamp,
+		Babe: pallet_babe,
+		Grandpa: pallet_grandpa,
+		Balances: pallet_balances,
+		TransactionPayment: pallet_transaction_payment,
+		Sudo: pallet_sudo,
+		// Include the custom logic from the pallet-template in the runtime.
+		TemplateModule: pallet_template,
+		// Proof-of-work difficulty, only consulted by the `pow` chain spec.
+		Difficulty: pallet_difficulty,
+		// Allowlist and quota of feeless transactions.
+		Feeless: pallet_feeless,
+		// Sponsors paying the fees of others.
+		Sponsorship: pallet_sponsorship,
 	}
 );
 
@@ -311,12 +481,20 @@ pub type SignedExtra = (
 	frame_system::CheckSpecVersion<Runtime>,
 	frame_system::CheckTxVersion<Runtime>,
 	frame_system::CheckGenesis<Runtime>,
-	frame_system::CheckEra<Runtime>,
-	frame_system::CheckNonce<Runtime>,
+	extensions::CheckEra<MaxMortality>,
+	extensions::CheckNonce,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	extensions::ChargeSponsoredTransactionPayment,
+	extensions::PrioritizeCalls,
+	extensions::CheckMetadataHash,
 );
 
+/// The extension of `extra` that charges the fee.
+pub fn payment_extension(extra: &SignedExtra) -> &extensions::ChargeSponsoredTransactionPayment {
+	let (_, _, _, _, _, _, _, payment, _, _) = extra;
+	payment
+}
+
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic =
 	generic::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
@@ -404,6 +582,7 @@ impl_runtime_apis! {
 		}
 	}
 
+	#[cfg(not(feature = "babe"))]
 	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
 		fn slot_duration() -> sp_consensus_aura::SlotDuration {
 			sp_consensus_aura::SlotDuration::from_millis(Aura::slot_duration())
@@ -414,6 +593,56 @@ impl_runtime_apis! {
 		}
 	}
 
+	#[cfg(feature = "babe")]
+	impl sp_consensus_babe::BabeApi<Block> for Runtime {
+		fn configuration() -> sp_consensus_babe::BabeConfiguration {
+			let epoch_config = Babe::epoch_config().unwrap_or(BABE_GENESIS_EPOCH_CONFIG);
+			sp_consensus_babe::BabeConfiguration {
+				slot_duration: Babe::slot_duration(),
+				epoch_length: EPOCH_DURATION_IN_SLOTS,
+				c: epoch_config.c,
+				authorities: Babe::authorities().to_vec(),
+				randomness: Babe::randomness(),
+				allowed_slots: epoch_config.allowed_slots,
+			}
+		}
+
+		fn current_epoch_start() -> sp_consensus_babe::Slot {
+			Babe::current_epoch_start()
+		}
+
+		fn current_epoch() -> sp_consensus_babe::Epoch {
+			Babe::current_epoch()
+		}
+
+		fn next_epoch() -> sp_consensus_babe::Epoch {
+			Babe::next_epoch()
+		}
+
+		fn generate_key_ownership_proof(
+			_slot: sp_consensus_babe::Slot,
+			_authority_id: sp_consensus_babe::AuthorityId,
+		) -> Option<sp_consensus_babe::OpaqueKeyOwnershipProof> {
+			// NOTE: this is the only implementation possible since we've
+			// defined our key owner proof type as a bottom type (i.e. a type
+			// with no values).
+			None
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			_equivocation_proof: sp_consensus_babe::EquivocationProof<<Block as BlockT>::Header>,
+			_key_owner_proof: sp_consensus_babe::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			None
+		}
+	}
+
+	impl sp_consensus_pow::DifficultyApi<Block, pallet_difficulty::Difficulty> for Runtime {
+		fn difficulty() -> pallet_difficulty::Difficulty {
+			Difficulty::difficulty()
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			opaque::SessionKeys::generate(seed)
@@ -494,6 +723,44 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl apis::AccountApi<Block> for Runtime {
+		fn account_summary(account: AccountId) -> apis::AccountSummary {
+			apis::account_summary(account)
+		}
+
+		fn account_summaries(accounts: Vec<AccountId>) -> Vec<apis::AccountSummary> {
+			accounts.into_iter().map(apis::account_summary).collect()
+		}
+	}
+
+	impl apis::PaymentApi<Block> for Runtime {
+		fn payer(extrinsic: <Block as BlockT>::Extrinsic) -> extensions::Payer {
+			apis::payer(extrinsic)
+		}
+	}
+
+	impl apis::MetadataHashApi<Block> for Runtime {
+		fn metadata_hash() -> Option<[u8; 32]> {
+			extensions::METADATA_HASH
+		}
+
+		fn metadata_proof(
+			extrinsic: <Block as BlockT>::Extrinsic,
+		) -> Option<metadata_hash::MetadataProof> {
+			apis::metadata_proof(extrinsic)
+		}
+	}
+
+	impl apis::DryRunApi<Block> for Runtime {
+		fn dry_run_call(origin: Option<AccountId>, call: RuntimeCall) -> apis::DryRunResult {
+			apis::dry_run_call(origin, call)
+		}
+
+		fn dry_run_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> apis::DryRunResult {
+			apis::dry_run_extrinsic(extrinsic)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (